@@ -0,0 +1,197 @@
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::SqliteConnection;
+
+use fofo_utils::usizedb;
+use shared_core::SharedCore;
+
+use model::{Bookmark, BookmarkFlag, BookmarkFolder, BookmarkToCreate};
+
+pub mod model;
+
+#[derive(Debug, Clone)]
+pub struct BookmarkSystem {
+    core: SharedCore,
+}
+
+impl BookmarkSystem {
+    pub async fn new(core: SharedCore) -> Self {
+        let mut tx = core.begin_unwrap(true).await;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS bookmarks(
+                id INTEGER PRIMARY KEY,
+                flag_ref_id INT NOT NULL,
+                flag INT NOT NULL,
+                created_at INT NOT NULL,
+                created_by_id INT NOT NULL,
+                folder TEXT NOT NULL,
+                note TEXT NOT NULL,
+
+                UNIQUE(created_by_id, flag, flag_ref_id),
+                FOREIGN KEY(created_by_id) REFERENCES users(id)
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS bookmarks_m1_index
+            on bookmarks (created_by_id, created_at);
+            CREATE INDEX IF NOT EXISTS bookmarks_m2_index
+            on bookmarks (flag_ref_id, flag);",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap(); // create indexes.
+
+        tx.commit().await.unwrap();
+        BookmarkSystem { core }
+    }
+
+    /// Save bookmark, the folder and note are updated if it is saved before.
+    pub async fn set_bookmark(
+        &self,
+        tx: &mut SqliteConnection,
+        user_id: usizedb,
+        bookmark: BookmarkToCreate,
+    ) -> Result<Bookmark> {
+        let now = Utc::now().timestamp() as usizedb;
+        sqlx::query(
+            "INSERT INTO bookmarks (flag_ref_id, flag, created_at, created_by_id, folder, note) VALUES (?, ?, ?, ?, ?, ?) 
+            ON CONFLICT(created_by_id, flag, flag_ref_id) DO UPDATE SET folder = excluded.folder, note = excluded.note",
+        )
+        .bind(bookmark.flag_ref_id)
+        .bind(bookmark.flag)
+        .bind(now)
+        .bind(user_id)
+        .bind(&bookmark.folder)
+        .bind(&bookmark.note)
+        .execute(&mut *tx)
+        .await?;
+        Ok(self
+            .get_bookmark(tx, bookmark.flag_ref_id, bookmark.flag, user_id)
+            .await?
+            .unwrap())
+    }
+
+    pub async fn remove_bookmark(
+        &self,
+        tx: &mut SqliteConnection,
+        flag_ref_id: usizedb,
+        flag: BookmarkFlag,
+        user_id: usizedb,
+    ) -> Result<bool> {
+        let r = sqlx::query(
+            "DELETE FROM bookmarks WHERE flag_ref_id = ? AND flag = ? AND created_by_id = ?",
+        )
+        .bind(flag_ref_id)
+        .bind(flag)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        Ok(r.rows_affected() == 1)
+    }
+
+    pub async fn get_bookmark(
+        &self,
+        tx: &mut SqliteConnection,
+        flag_ref_id: usizedb,
+        flag: BookmarkFlag,
+        user_id: usizedb,
+    ) -> Result<Option<Bookmark>> {
+        Ok(sqlx::query_as::<_, Bookmark>(
+            "SELECT * FROM bookmarks WHERE flag_ref_id = ? AND flag = ? AND created_by_id = ? LIMIT 1",
+        )
+        .bind(flag_ref_id)
+        .bind(flag)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?)
+    }
+
+    /// Bookmarks of user on the targets in one query.
+    pub async fn get_bookmarks_of_targets(
+        &self,
+        tx: &mut SqliteConnection,
+        flag_ref_ids: &[usizedb],
+        flag: BookmarkFlag,
+        user_id: usizedb,
+    ) -> Result<Vec<Bookmark>> {
+        if flag_ref_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        let ids = flag_ref_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+        Ok(sqlx::query_as::<_, Bookmark>(&format!(
+            "SELECT * FROM bookmarks WHERE flag_ref_id IN ({ids}) AND flag = ? AND created_by_id = ?"
+        ))
+        .bind(flag)
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await?)
+    }
+
+    fn get_where_conds(flag: Option<BookmarkFlag>, folder: Option<&str>) -> String {
+        let mut conds = vec!["created_by_id = ?".to_owned()];
+        if let Some(flag) = flag {
+            conds.push(format!("flag = {}", flag as u8));
+        }
+        if folder.is_some() {
+            conds.push("folder = ?".to_owned());
+        }
+        format!("WHERE {}", conds.join(" AND "))
+    }
+
+    /// Get bookmarks of user, the newest saved first.
+    pub async fn get_bookmarks(
+        &self,
+        tx: &mut SqliteConnection,
+        user_id: usizedb,
+        flag: Option<BookmarkFlag>,
+        folder: Option<&str>,
+        index: usizedb,
+        limit: usizedb,
+    ) -> Result<Vec<Bookmark>> {
+        let offset = index * limit;
+        let where_conds = Self::get_where_conds(flag, folder);
+        let q = format!("SELECT * FROM bookmarks {where_conds} ORDER BY created_at DESC, id DESC LIMIT {limit} OFFSET {offset}");
+        let mut query = sqlx::query_as::<_, Bookmark>(&q).bind(user_id);
+        if let Some(folder) = folder {
+            query = query.bind(folder);
+        }
+        Ok(query.fetch_all(&mut *tx).await?)
+    }
+
+    pub async fn get_bookmarks_count(
+        &self,
+        tx: &mut SqliteConnection,
+        user_id: usizedb,
+        flag: Option<BookmarkFlag>,
+        folder: Option<&str>,
+    ) -> Result<usizedb> {
+        let where_conds = Self::get_where_conds(flag, folder);
+        let q = format!("SELECT COUNT(*) FROM bookmarks {where_conds}");
+        let mut query = sqlx::query_scalar(&q).bind(user_id);
+        if let Some(folder) = folder {
+            query = query.bind(folder);
+        }
+        Ok(query.fetch_one(&mut *tx).await?)
+    }
+
+    pub async fn get_folders(
+        &self,
+        tx: &mut SqliteConnection,
+        user_id: usizedb,
+    ) -> Result<Vec<BookmarkFolder>> {
+        Ok(sqlx::query_as::<_, BookmarkFolder>(
+            "SELECT folder, COUNT(*) AS total FROM bookmarks WHERE created_by_id = ? GROUP BY folder ORDER BY folder",
+        )
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await?)
+    }
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+use sqlx::FromRow;
+
+use fofo_utils::usizedb;
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct Bookmark {
+    pub id: usizedb,
+    pub flag: BookmarkFlag,
+    pub flag_ref_id: usizedb,
+    pub created_by_id: usizedb,
+    pub created_at: usizedb,
+    /// Empty means the default folder.
+    pub folder: String,
+    pub note: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BookmarkToCreate {
+    pub flag: BookmarkFlag,
+    pub flag_ref_id: usizedb,
+    #[serde(default)]
+    pub folder: String,
+    #[serde(default)]
+    pub note: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct BookmarkFolder {
+    pub folder: String,
+    pub total: usizedb,
+}
+
+#[derive(Debug, Deserialize_repr, Serialize_repr, PartialEq, Clone, Copy, sqlx::Type, Eq, Hash)]
+#[repr(u8)]
+pub enum BookmarkFlag {
+    TargetPost,
+    TargetComment,
+}
@@ -1,7 +1,9 @@
 pub mod model;
 
+use std::collections::{HashMap, HashSet};
+
 use anyhow::{bail, Result};
-use fofo_utils::usizedb;
+use fofo_utils::{category_descendants_cte, usizedb, MAX_CATEGORY_DEPTH};
 use futures::TryStreamExt;
 use model::{Category, CategoryToCreate, CategoryToUpdate};
 use moka::future::Cache;
@@ -11,7 +13,23 @@ use storage::S3Ref;
 use tracing::warn;
 use user_system::model::{UserInfo, UserType};
 
-use self::model::{CategoryLevels, CategoryStatus, GetCategoriesSort};
+use self::model::{
+    CategoryLevels, CategoryPolicy, CategoryPosition, CategorySection, CategoryStatus,
+    CategoryTreeNode, GetCategoriesSort, SectionPosition,
+};
+
+/// The category and its ancestors walked up while `inherit_col` is set, depth of the category is 0.
+fn inherited_cte(inherit_col: &str) -> String {
+    format!(
+        "WITH RECURSIVE up(id, parent_id, inherit, depth) AS (
+            SELECT id, parent_id, {inherit_col}, 0 FROM categories WHERE id = ?
+            UNION ALL
+            SELECT c.id, c.parent_id, c.{inherit_col}, up.depth + 1 FROM categories c
+            JOIN up ON c.id = up.parent_id
+            WHERE up.inherit = 1 AND up.depth < {MAX_CATEGORY_DEPTH}
+        )"
+    )
+}
 
 #[derive(Debug, Clone)]
 pub struct CategorySystem {
@@ -35,13 +53,35 @@ impl CategorySystem {
                 write_level INT NOT NULL,
                 comment_level INT NOT NULL,
                 total_post INT NOT NULL,
-                cover_url TEXT NULL
+                cover_url TEXT NULL,
+                qa_enabled INT NOT NULL DEFAULT 0,
+                parent_id INT NOT NULL DEFAULT 0,
+                inherit_levels INT NOT NULL DEFAULT 0,
+                inherit_groups INT NOT NULL DEFAULT 0,
+                inherit_moderators INT NOT NULL DEFAULT 0,
+                section_id INT NOT NULL DEFAULT 0,
+                position INT NOT NULL DEFAULT 0
             )",
         )
         .execute(tx.as_mut())
         .await
         .unwrap();
 
+        // columns added after the table is created.
+        for (column, definition) in [
+            ("qa_enabled", "INT NOT NULL DEFAULT 0"),
+            ("parent_id", "INT NOT NULL DEFAULT 0"),
+            ("inherit_levels", "INT NOT NULL DEFAULT 0"),
+            ("inherit_groups", "INT NOT NULL DEFAULT 0"),
+            ("inherit_moderators", "INT NOT NULL DEFAULT 0"),
+            ("section_id", "INT NOT NULL DEFAULT 0"),
+            ("position", "INT NOT NULL DEFAULT 0"),
+        ] {
+            fofo_utils::add_column_if_not_exists(tx.as_mut(), "categories", column, definition)
+                .await
+                .unwrap();
+        }
+
         sqlx::query(
             "CREATE TABLE IF NOT EXISTS category_groups(
                 category_id INT NOT NULL,
@@ -52,6 +92,36 @@ impl CategorySystem {
         .await
         .unwrap();
 
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS category_sections(
+                id INTEGER PRIMARY KEY,
+                title VARCHAR(128) NOT NULL,
+                position INT NOT NULL
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS category_policies(
+                category_id INTEGER PRIMARY KEY,
+                editable_seconds INT NULL,
+                top_index_max INT NULL,
+                custom_post_cover_supported INT NULL,
+                max_tags INT NULL,
+                min_title_length INT NOT NULL,
+                min_content_length INT NOT NULL,
+                required_tags TEXT NOT NULL,
+                allowed_content_types INT NOT NULL,
+                slow_mode_seconds INT NOT NULL,
+                approval_required INT NOT NULL
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
         sqlx::query(
             "CREATE TABLE IF NOT EXISTS category_moderators(
                 category_id INT NOT NULL,
@@ -65,6 +135,10 @@ impl CategorySystem {
         sqlx::query(
             "CREATE UNIQUE INDEX IF NOT EXISTS category_title_index
             on categories (title);
+            CREATE INDEX IF NOT EXISTS category_parent_index
+            on categories (parent_id);
+            CREATE INDEX IF NOT EXISTS category_section_index
+            on categories (section_id, position);
             CREATE INDEX IF NOT EXISTS category_groups_category
             on category_groups (category_id);
             CREATE INDEX IF NOT EXISTS category_groups_group
@@ -125,7 +199,7 @@ impl CategorySystem {
             }
         }
         let mut tx = self.core.begin_unwrap(false).await;
-        let r = sqlx::query("INSERT INTO categories (title, description, description_content_type, status, read_level, write_level, comment_level, total_post, cover_url) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)")
+        let r = sqlx::query("INSERT INTO categories (title, description, description_content_type, status, read_level, write_level, comment_level, total_post, cover_url, qa_enabled, parent_id, inherit_levels, inherit_groups, inherit_moderators, section_id, position) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, (SELECT COALESCE(MAX(position), 0) + 1 FROM categories WHERE section_id = ?))")
             .bind(&category.title)
             .bind(&category.description)
             .bind(&category.description_content_type)
@@ -135,11 +209,23 @@ impl CategorySystem {
             .bind(&category.comment_level)
             .bind(0)
             .bind(&category.cover_url)
+            .bind(category.qa_enabled)
+            .bind(category.parent_id)
+            .bind(category.inherit_levels)
+            .bind(category.inherit_groups)
+            .bind(category.inherit_moderators)
+            .bind(category.section_id)
+            .bind(category.section_id)
             .execute(tx.as_mut())
             .await?;
 
         if r.rows_affected() == 1 {
-            let id = r.last_insert_rowid() as _;
+            let id: usizedb = r.last_insert_rowid() as _;
+            let position = sqlx::query("SELECT position FROM categories WHERE id=?")
+                .bind(id)
+                .fetch_one(tx.as_mut())
+                .await?
+                .get("position");
             self.insert_groups_and_moderators(
                 tx.as_mut(),
                 id,
@@ -147,6 +233,8 @@ impl CategorySystem {
                 &category.moderator_ids,
             )
             .await?;
+            self.set_policy(tx.as_mut(), id, category.policy.as_ref())
+                .await?;
 
             let category = Category {
                 id,
@@ -160,15 +248,29 @@ impl CategorySystem {
                 moderator_ids: category.moderator_ids,
                 group_ids: category.group_ids,
                 total_post: 0,
+                total_post_tree: 0,
                 cover_url: category.cover_url,
+                qa_enabled: category.qa_enabled,
+                parent_id: category.parent_id,
+                inherit_levels: category.inherit_levels,
+                inherit_groups: category.inherit_groups,
+                inherit_moderators: category.inherit_moderators,
+                section_id: category.section_id,
+                position,
+                policy: category.policy,
             };
             tx.commit().await?;
+            if category.parent_id > 0 {
+                // ancestors are cached with old total post of tree.
+                self.invalidate_all_cache();
+            }
             Ok(category)
         } else {
             bail!("Insert failed.")
         }
     }
 
+    /// Parent may be changed and ancestors are affected, call `invalidate_all_cache` after commit.
     pub async fn update_category(
         &self,
         tx: &mut SqliteConnection,
@@ -182,7 +284,7 @@ impl CategorySystem {
                 }
             }
         }
-        let r = sqlx::query("UPDATE categories SET title=?, description=?, description_content_type=?, status=?, read_level=?, write_level=?, comment_level=?, cover_url=? WHERE id=?")
+        let r = sqlx::query("UPDATE categories SET title=?, description=?, description_content_type=?, status=?, read_level=?, write_level=?, comment_level=?, cover_url=?, qa_enabled=?, parent_id=?, inherit_levels=?, inherit_groups=?, inherit_moderators=?, section_id=? WHERE id=?")
             .bind(&category.title)
             .bind(&category.description)
             .bind(&category.description_content_type)
@@ -190,8 +292,14 @@ impl CategorySystem {
             .bind(&category.read_level)
             .bind(&category.write_level)
             .bind(&category.comment_level)
-            .bind(id)
             .bind(&category.cover_url)
+            .bind(category.qa_enabled)
+            .bind(category.parent_id)
+            .bind(category.inherit_levels)
+            .bind(category.inherit_groups)
+            .bind(category.inherit_moderators)
+            .bind(category.section_id)
+            .bind(id)
             .execute(&mut *tx)
             .await?;
 
@@ -203,12 +311,13 @@ impl CategorySystem {
                 &category.moderator_ids,
             )
             .await?;
-            self.invalidate_cache(id).await;
-            let total_post = sqlx::query("SELECT total_post FROM categories WHERE id=?")
+            self.set_policy(&mut *tx, id, category.policy.as_ref())
+                .await?;
+            let row = sqlx::query("SELECT total_post, position FROM categories WHERE id=?")
                 .bind(id)
                 .fetch_one(&mut *tx)
-                .await?
-                .get("total_post");
+                .await?;
+            let total_post_tree = self.get_total_post_tree(tx, id).await?;
             return Ok(Some(Category {
                 id,
                 title: category.title,
@@ -220,8 +329,17 @@ impl CategorySystem {
                 comment_level: category.comment_level,
                 moderator_ids: category.moderator_ids,
                 group_ids: category.group_ids,
-                total_post,
+                total_post: row.get("total_post"),
+                total_post_tree,
+                position: row.get("position"),
                 cover_url: category.cover_url,
+                qa_enabled: category.qa_enabled,
+                parent_id: category.parent_id,
+                inherit_levels: category.inherit_levels,
+                inherit_groups: category.inherit_groups,
+                inherit_moderators: category.inherit_moderators,
+                section_id: category.section_id,
+                policy: category.policy,
             }));
         }
 
@@ -277,18 +395,135 @@ impl CategorySystem {
             .unwrap();
     }
 
+    async fn set_policy(
+        &self,
+        tx: &mut SqliteConnection,
+        category_id: usizedb,
+        policy: Option<&CategoryPolicy>,
+    ) -> Result<()> {
+        sqlx::query("DELETE FROM category_policies WHERE category_id=?")
+            .bind(category_id)
+            .execute(&mut *tx)
+            .await?;
+        if let Some(policy) = policy {
+            sqlx::query("INSERT INTO category_policies (category_id, editable_seconds, top_index_max, custom_post_cover_supported, max_tags, min_title_length, min_content_length, required_tags, allowed_content_types, slow_mode_seconds, approval_required) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")
+                .bind(category_id)
+                .bind(policy.editable_seconds)
+                .bind(policy.top_index_max)
+                .bind(policy.custom_post_cover_supported)
+                .bind(policy.max_tags)
+                .bind(policy.min_title_length)
+                .bind(policy.min_content_length)
+                .bind(fofo_utils::array_to_string(&policy.required_tags))
+                .bind(policy.content_types_to_mask())
+                .bind(policy.slow_mode_seconds)
+                .bind(policy.approval_required)
+                .execute(&mut *tx)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn get_policy(
+        &self,
+        tx: &mut SqliteConnection,
+        category_id: usizedb,
+    ) -> Result<Option<CategoryPolicy>> {
+        let row = sqlx::query("SELECT * FROM category_policies WHERE category_id=?")
+            .bind(category_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        Ok(match row {
+            Some(row) => Some(CategoryPolicy {
+                editable_seconds: row.try_get("editable_seconds")?,
+                top_index_max: row.try_get("top_index_max")?,
+                custom_post_cover_supported: row.try_get("custom_post_cover_supported")?,
+                max_tags: row.try_get("max_tags")?,
+                min_title_length: row.try_get("min_title_length")?,
+                min_content_length: row.try_get("min_content_length")?,
+                required_tags: fofo_utils::string_to_array(row.try_get("required_tags")?)?,
+                allowed_content_types: CategoryPolicy::content_types_from_mask(
+                    row.try_get("allowed_content_types")?,
+                ),
+                slow_mode_seconds: row.try_get("slow_mode_seconds")?,
+                approval_required: row.try_get("approval_required")?,
+            }),
+            None => None,
+        })
+    }
+
+    /// The policy of category, default policy if the category has not set it.
+    pub async fn get_effective_policy(
+        &self,
+        tx: &mut SqliteConnection,
+        category_id: usizedb,
+    ) -> Result<CategoryPolicy> {
+        Ok(self
+            .get_category(tx, category_id)
+            .await?
+            .and_then(|c| c.policy)
+            .unwrap_or_default())
+    }
+
+    /// Invalidate all cached categories, call it after the tree is changed and committed.
+    pub fn invalidate_all_cache(&self) {
+        self.cached_categories.invalidate_all();
+        self.cached_categories_array.invalidate_all();
+    }
+
+    async fn get_total_post_tree(&self, tx: &mut SqliteConnection, id: usizedb) -> Result<usizedb> {
+        let cte = category_descendants_cte("?");
+        Ok(sqlx::query_scalar(&format!(
+            "{cte} SELECT COALESCE(SUM(total_post), 0) FROM categories WHERE id IN (SELECT id FROM down)"
+        ))
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?)
+    }
+
+    /// Total post of each category with its descendants, summed up in memory from one query.
+    async fn get_total_post_trees(
+        &self,
+        tx: &mut SqliteConnection,
+    ) -> Result<HashMap<usizedb, usizedb>> {
+        let rows: Vec<(usizedb, usizedb, usizedb)> =
+            sqlx::query_as("SELECT id, parent_id, total_post FROM categories")
+                .fetch_all(&mut *tx)
+                .await?;
+        let parents: HashMap<usizedb, usizedb> = rows
+            .iter()
+            .map(|(id, parent_id, _)| (*id, *parent_id))
+            .collect();
+        let mut totals: HashMap<usizedb, usizedb> = rows
+            .iter()
+            .map(|(id, _, total_post)| (*id, *total_post))
+            .collect();
+        // posts of category are added to its ancestors.
+        for (_, parent_id, total_post) in &rows {
+            let mut parent_id = *parent_id;
+            for _ in 0..MAX_CATEGORY_DEPTH {
+                match totals.get_mut(&parent_id) {
+                    Some(total) => *total += total_post,
+                    None => break,
+                }
+                parent_id = parents[&parent_id];
+            }
+        }
+        Ok(totals)
+    }
+
     async fn from_row(
         &self,
         tx: &mut SqliteConnection,
         row: SqliteRow,
         include_content: bool,
-    ) -> Category {
+    ) -> Result<Category> {
         let cover_url = {
             let cover_url: Option<String> = row.get("cover_url");
             cover_url.map(|url| self.s3.get_real_url(url))
         };
         let id = row.try_get("id").unwrap();
-        Category {
+        Ok(Category {
             id,
             title: row.try_get("title").unwrap(),
             description: if include_content {
@@ -316,8 +551,18 @@ impl CategorySystem {
             .map(|rows| rows.into_iter().map(|row| row.get(0)).collect())
             .unwrap_or(Vec::new()),
             total_post: row.try_get("total_post").unwrap(),
+            // filled by caller, lists sum it up once for all categories.
+            total_post_tree: 0,
             cover_url,
-        }
+            qa_enabled: row.try_get("qa_enabled").unwrap(),
+            parent_id: row.try_get("parent_id").unwrap(),
+            inherit_levels: row.try_get("inherit_levels").unwrap(),
+            inherit_groups: row.try_get("inherit_groups").unwrap(),
+            inherit_moderators: row.try_get("inherit_moderators").unwrap(),
+            section_id: row.try_get("section_id").unwrap(),
+            position: row.try_get("position").unwrap(),
+            policy: self.get_policy(tx, id).await?,
+        })
     }
 
     pub async fn get_category(
@@ -334,7 +579,8 @@ impl CategorySystem {
                 .try_next()
                 .await?;
             if let Some(row) = r {
-                let v = self.from_row(tx, row, true).await;
+                let mut v = self.from_row(tx, row, true).await?;
+                v.total_post_tree = self.get_total_post_tree(tx, id).await?;
                 self.cached_categories.insert(id, v.clone()).await;
                 Some(v)
             } else {
@@ -352,20 +598,18 @@ impl CategorySystem {
         desc: bool,
     ) -> Result<Vec<Category>> {
         let offset = index * limit;
+        let direction = if desc { "DESC" } else { "ASC" };
         let order_by = match sort {
-            GetCategoriesSort::Id => "id",
-            GetCategoriesSort::Title => "title",
-            GetCategoriesSort::TotalPost => "total_post",
-        };
-        let query_str = if desc {
-            format!(
-                "SELECT * FROM categories ORDER BY {order_by} DESC LIMIT {limit} OFFSET {offset}"
-            )
-        } else {
-            format!(
-                "SELECT * FROM categories ORDER BY {order_by} ASC LIMIT {limit} OFFSET {offset}"
-            )
+            GetCategoriesSort::Id => format!("categories.id {direction}"),
+            GetCategoriesSort::Title => format!("categories.title {direction}"),
+            GetCategoriesSort::TotalPost => format!("categories.total_post {direction}"),
+            GetCategoriesSort::Position => format!(
+                "COALESCE(s.position, 0) {direction}, categories.section_id {direction}, categories.position {direction}, categories.id {direction}"
+            ),
         };
+        let query_str = format!(
+            "SELECT categories.* FROM categories LEFT JOIN category_sections s ON s.id = categories.section_id ORDER BY {order_by} LIMIT {limit} OFFSET {offset}"
+        );
 
         Ok(
             if let Some(cached) = self.cached_categories_array.get(&query_str) {
@@ -374,8 +618,12 @@ impl CategorySystem {
                 let mut categories = Vec::with_capacity(limit as _);
                 {
                     let r = sqlx::query(&query_str).fetch_all(&mut *tx).await?;
+                    let total_post_trees = self.get_total_post_trees(tx).await?;
                     for row in r {
-                        categories.push(self.from_row(tx, row, false).await);
+                        let mut category = self.from_row(tx, row, false).await?;
+                        category.total_post_tree =
+                            total_post_trees.get(&category.id).copied().unwrap_or(0);
+                        categories.push(category);
                     }
                 }
                 self.cached_categories_array
@@ -399,11 +647,28 @@ impl CategorySystem {
             .unwrap_or(0))
     }
 
+    /// Ids of all categories.
+    pub async fn get_ids(&self, tx: &mut SqliteConnection) -> Result<Vec<usizedb>> {
+        Ok(sqlx::query_scalar("SELECT id FROM categories ORDER BY id")
+            .fetch_all(&mut *tx)
+            .await?)
+    }
+
+    /// Call `invalidate_all_cache` after commit.
     pub async fn delete_category(&self, tx: &mut SqliteConnection, id: usizedb) -> Result<bool> {
+        // children are moved to the parent of deleted category.
+        sqlx::query(
+            "UPDATE categories SET parent_id = (SELECT parent_id FROM categories WHERE id = ?) WHERE parent_id = ?",
+        )
+        .bind(id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
         let r = sqlx::query("DELETE FROM categories WHERE id = ?")
             .bind(id)
             .execute(&mut *tx)
             .await?;
+        self.set_policy(&mut *tx, id, None).await?;
 
         if r.rows_affected() > 1 {
             warn!("rows affected is more than 1.")
@@ -444,6 +709,7 @@ impl CategorySystem {
         category_id: usizedb,
         user: &UserInfo,
     ) -> Result<bool> {
+        let category_id = self.get_groups_category_id(tx, category_id).await?;
         if sqlx::query("SELECT 1 FROM category_groups WHERE category_id=?").bind(category_id).fetch_optional(&mut *tx).await?.is_none() {
             return  Ok(true); // category don't have any group.
         }
@@ -468,14 +734,33 @@ impl CategorySystem {
         tx: &mut SqliteConnection,
         category_id: usizedb,
     ) -> Result<CategoryLevels> {
-        Ok(sqlx::query_as(
-            "SELECT read_level, write_level, comment_level FROM categories WHERE id=? LIMIT 1",
-        )
+        let cte = inherited_cte("inherit_levels");
+        Ok(sqlx::query_as(&format!(
+            "{cte} SELECT read_level, write_level, comment_level FROM categories
+            WHERE id = (SELECT id FROM up ORDER BY depth DESC LIMIT 1)"
+        ))
         .bind(category_id)
         .fetch_one(&mut *tx)
         .await?)
     }
 
+    /// The category whose groups are used by `category_id`.
+    async fn get_groups_category_id(
+        &self,
+        tx: &mut SqliteConnection,
+        category_id: usizedb,
+    ) -> Result<usizedb> {
+        let cte = inherited_cte("inherit_groups");
+        Ok(sqlx::query_scalar(&format!(
+            "{cte} SELECT id FROM up ORDER BY depth DESC LIMIT 1"
+        ))
+        .bind(category_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .unwrap_or(category_id))
+    }
+
+    /// Admin or moderator of any category.
     pub async fn can_manage(
         &self,
         tx: &mut SqliteConnection,
@@ -483,7 +768,7 @@ impl CategorySystem {
         user: Option<&UserInfo>,
     ) -> Result<bool> {
         Ok(match user {
-            Some(user) if user.is_admin() || sqlx::query("SELECT 1 FROM category_moderators WHERE category_id=? AND user_id=? LIMIT 1")
+            Some(user) if user.is_admin() || sqlx::query(&format!("{} SELECT 1 FROM category_moderators WHERE category_id IN (SELECT id FROM up) AND user_id=? LIMIT 1", inherited_cte("inherit_moderators")))
             .bind(category_id)
             .bind(user.id)
             .fetch_optional(&mut *tx)
@@ -550,4 +835,186 @@ impl CategorySystem {
             Some(_) | None => self.can_manage(tx, category_id, user).await?,
         })
     }
+
+    /// Ids of descendants, the category itself is excluded.
+    pub async fn get_descendant_ids(
+        &self,
+        tx: &mut SqliteConnection,
+        id: usizedb,
+    ) -> Result<Vec<usizedb>> {
+        let cte = category_descendants_cte("?");
+        Ok(
+            sqlx::query_scalar(&format!("{cte} SELECT id FROM down WHERE depth > 0"))
+                .bind(id)
+                .fetch_all(&mut *tx)
+                .await?,
+        )
+    }
+
+    /// Parent is legal if it exists and it is not the category or its descendants.
+    pub async fn is_legal_parent(
+        &self,
+        tx: &mut SqliteConnection,
+        id: Option<usizedb>,
+        parent_id: usizedb,
+    ) -> Result<bool> {
+        if parent_id == 0 {
+            return Ok(true);
+        }
+        if !self.is_exists(tx, parent_id).await? {
+            return Ok(false);
+        }
+        Ok(match id {
+            Some(id) => {
+                id != parent_id && !self.get_descendant_ids(tx, id).await?.contains(&parent_id)
+            }
+            None => true,
+        })
+    }
+
+    /// Categories in `visible_ids` as trees, categories are ordered by id in the same level.
+    /// Descendants of the invisible category are excluded too.
+    pub async fn get_category_tree(
+        &self,
+        tx: &mut SqliteConnection,
+        visible_ids: &HashSet<usizedb>,
+    ) -> Result<Vec<CategoryTreeNode>> {
+        let mut categories = Vec::new();
+        {
+            let r = sqlx::query("SELECT * FROM categories ORDER BY id")
+                .fetch_all(&mut *tx)
+                .await?;
+            let total_post_trees = self.get_total_post_trees(tx).await?;
+            for row in r {
+                let mut category = self.from_row(tx, row, false).await?;
+                category.total_post_tree = total_post_trees.get(&category.id).copied().unwrap_or(0);
+                categories.push(category);
+            }
+        }
+        let ids: HashSet<usizedb> = categories.iter().map(|c| c.id).collect();
+        let mut children: HashMap<usizedb, Vec<Category>> = HashMap::new();
+        for c in categories {
+            // category with missing parent is a top category.
+            let parent_id = if ids.contains(&c.parent_id) {
+                c.parent_id
+            } else {
+                0
+            };
+            children.entry(parent_id).or_default().push(c);
+        }
+
+        fn build(
+            parent_id: usizedb,
+            children: &mut HashMap<usizedb, Vec<Category>>,
+            visible_ids: &HashSet<usizedb>,
+        ) -> Vec<CategoryTreeNode> {
+            children
+                .remove(&parent_id)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|category| visible_ids.contains(&category.id))
+                .map(|category| {
+                    let id = category.id;
+                    CategoryTreeNode {
+                        category,
+                        children: build(id, children, visible_ids),
+                    }
+                })
+                .collect()
+        }
+        Ok(build(0, &mut children, visible_ids))
+    }
+
+    pub async fn get_sections(&self, tx: &mut SqliteConnection) -> Result<Vec<CategorySection>> {
+        Ok(sqlx::query_as::<_, CategorySection>(
+            "SELECT * FROM category_sections ORDER BY position, id",
+        )
+        .fetch_all(&mut *tx)
+        .await?)
+    }
+
+    pub async fn is_section_exists(&self, tx: &mut SqliteConnection, id: usizedb) -> Result<bool> {
+        Ok(
+            sqlx::query("SELECT 1 FROM category_sections WHERE id=? LIMIT 1")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .is_some(),
+        )
+    }
+
+    /// New section is placed at the end.
+    pub async fn create_section(
+        &self,
+        tx: &mut SqliteConnection,
+        title: &str,
+    ) -> Result<CategorySection> {
+        let r = sqlx::query(
+            "INSERT INTO category_sections (title, position)
+            VALUES (?, (SELECT COALESCE(MAX(position), 0) + 1 FROM category_sections))",
+        )
+        .bind(title)
+        .execute(&mut *tx)
+        .await?;
+        Ok(
+            sqlx::query_as::<_, CategorySection>("SELECT * FROM category_sections WHERE id=?")
+                .bind(r.last_insert_rowid())
+                .fetch_one(&mut *tx)
+                .await?,
+        )
+    }
+
+    pub async fn update_section(
+        &self,
+        tx: &mut SqliteConnection,
+        id: usizedb,
+        title: &str,
+    ) -> Result<bool> {
+        let r = sqlx::query("UPDATE category_sections SET title = ? WHERE id = ?")
+            .bind(title)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        Ok(r.rows_affected() == 1)
+    }
+
+    /// Categories of the deleted section are moved out of any section,
+    /// call `invalidate_all_cache` after commit.
+    pub async fn delete_section(&self, tx: &mut SqliteConnection, id: usizedb) -> Result<bool> {
+        let r = sqlx::query("DELETE FROM category_sections WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE categories SET section_id = 0 WHERE section_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        Ok(r.rows_affected() == 1)
+    }
+
+    /// Set positions of sections and categories in bulk, sections of categories are moved too.
+    /// Call `invalidate_all_cache` after commit.
+    pub async fn reorder(
+        &self,
+        tx: &mut SqliteConnection,
+        sections: &[SectionPosition],
+        categories: &[CategoryPosition],
+    ) -> Result<()> {
+        for section in sections {
+            sqlx::query("UPDATE category_sections SET position = ? WHERE id = ?")
+                .bind(section.position)
+                .bind(section.id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        for category in categories {
+            sqlx::query("UPDATE categories SET section_id = ?, position = ? WHERE id = ?")
+                .bind(category.section_id)
+                .bind(category.position)
+                .bind(category.id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        Ok(())
+    }
 }
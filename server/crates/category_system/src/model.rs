@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use sqlx::FromRow;
 
-use fofo_utils::{usizedb, ContentType};
+use fofo_utils::{config::Config, usizedb, ContentType};
 use user_system::model::UserType;
 
 #[derive(Debug, Deserialize_repr, Serialize_repr, PartialEq, Clone, Copy, sqlx::Type)]
@@ -26,7 +26,32 @@ pub struct Category {
     pub moderator_ids: Vec<usizedb>,
     pub group_ids: Vec<usizedb>,
     pub total_post: usizedb,
+    /// Total post of this category and its descendants.
+    pub total_post_tree: usizedb,
+    /// Display order in the section, ascending.
+    pub position: usizedb,
     pub cover_url: Option<String>,
+    /// Question and answer mode, the post can accept a comment as answer.
+    #[serde(default)]
+    pub qa_enabled: bool,
+    /// Parent category, 0 means it is a top category.
+    #[serde(default)]
+    pub parent_id: usizedb,
+    /// Use the read, write and comment levels of parent instead of its own.
+    #[serde(default)]
+    pub inherit_levels: bool,
+    /// Use the groups of parent instead of its own.
+    #[serde(default)]
+    pub inherit_groups: bool,
+    /// Moderators of parent also manage this category.
+    #[serde(default)]
+    pub inherit_moderators: bool,
+    /// Section which category is displayed in, 0 means no section.
+    #[serde(default)]
+    pub section_id: usizedb,
+    /// Posting rules of this category, None means the global config is used.
+    #[serde(default)]
+    pub policy: Option<CategoryPolicy>,
 }
 
 #[derive(Debug, FromRow, Deserialize, Serialize, Clone)]
@@ -41,6 +66,27 @@ pub struct CategoryToCreate {
     pub moderator_ids: Vec<usizedb>,
     pub group_ids: Vec<usizedb>,
     pub cover_url: Option<String>,
+    /// Question and answer mode, the post can accept a comment as answer.
+    #[serde(default)]
+    pub qa_enabled: bool,
+    /// Parent category, 0 means it is a top category.
+    #[serde(default)]
+    pub parent_id: usizedb,
+    /// Use the read, write and comment levels of parent instead of its own.
+    #[serde(default)]
+    pub inherit_levels: bool,
+    /// Use the groups of parent instead of its own.
+    #[serde(default)]
+    pub inherit_groups: bool,
+    /// Moderators of parent also manage this category.
+    #[serde(default)]
+    pub inherit_moderators: bool,
+    /// Section which category is displayed in, 0 means no section.
+    #[serde(default)]
+    pub section_id: usizedb,
+    /// Posting rules of this category, None means the global config is used.
+    #[serde(default)]
+    pub policy: Option<CategoryPolicy>,
 }
 
 #[derive(Debug, FromRow, Deserialize, Serialize, Clone)]
@@ -55,6 +101,112 @@ pub struct CategoryToUpdate {
     pub moderator_ids: Vec<usizedb>,
     pub group_ids: Vec<usizedb>,
     pub cover_url: Option<String>,
+    /// Question and answer mode, the post can accept a comment as answer.
+    #[serde(default)]
+    pub qa_enabled: bool,
+    /// Parent category, 0 means it is a top category.
+    #[serde(default)]
+    pub parent_id: usizedb,
+    /// Use the read, write and comment levels of parent instead of its own.
+    #[serde(default)]
+    pub inherit_levels: bool,
+    /// Use the groups of parent instead of its own.
+    #[serde(default)]
+    pub inherit_groups: bool,
+    /// Moderators of parent also manage this category.
+    #[serde(default)]
+    pub inherit_moderators: bool,
+    /// Section which category is displayed in, 0 means no section.
+    #[serde(default)]
+    pub section_id: usizedb,
+    /// Posting rules of this category, None means the global config is used.
+    #[serde(default)]
+    pub policy: Option<CategoryPolicy>,
+}
+
+/// Posting rules of category, the `None` values fall back to the global config.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct CategoryPolicy {
+    #[serde(default)]
+    pub editable_seconds: Option<usizedb>,
+    #[serde(default)]
+    pub top_index_max: Option<usizedb>,
+    #[serde(default)]
+    pub custom_post_cover_supported: Option<bool>,
+    #[serde(default)]
+    pub max_tags: Option<usizedb>,
+    /// Minimum chars of post title, 0 means no limit.
+    #[serde(default)]
+    pub min_title_length: usizedb,
+    /// Minimum chars of post content, 0 means no limit.
+    #[serde(default)]
+    pub min_content_length: usizedb,
+    /// Post must have at least one of these tags, empty means no requirement.
+    #[serde(default)]
+    pub required_tags: Vec<String>,
+    /// Content types of post, empty means all types are allowed.
+    #[serde(default)]
+    pub allowed_content_types: Vec<ContentType>,
+    /// Minimum seconds between two posts of the same user, 0 means disabled.
+    #[serde(default)]
+    pub slow_mode_seconds: usizedb,
+    /// New posts are pending until approved by a moderator.
+    #[serde(default)]
+    pub approval_required: bool,
+}
+
+impl CategoryPolicy {
+    pub const DEFAULT_MAX_TAGS: usizedb = 9;
+    const CONTENT_TYPES: [ContentType; 4] = [
+        ContentType::Markdown,
+        ContentType::PlainText,
+        ContentType::Html,
+        ContentType::BBCode,
+    ];
+
+    pub fn get_editable_seconds(&self, config: &Config) -> usizedb {
+        self.editable_seconds
+            .unwrap_or(config.editable_seconds as usizedb)
+    }
+
+    pub fn get_top_index_max(&self, config: &Config) -> usizedb {
+        self.top_index_max
+            .unwrap_or(config.top_index_max as usizedb)
+    }
+
+    pub fn is_custom_post_cover_supported(&self, config: &Config) -> bool {
+        self.custom_post_cover_supported
+            .unwrap_or(config.custom_post_cover_supported)
+    }
+
+    pub fn get_max_tags(&self) -> usizedb {
+        self.max_tags.unwrap_or(Self::DEFAULT_MAX_TAGS)
+    }
+
+    pub fn is_content_type_allowed(&self, content_type: ContentType) -> bool {
+        self.allowed_content_types.is_empty() || self.allowed_content_types.contains(&content_type)
+    }
+
+    /// Stored as a bit mask, the bit of content type is its value.
+    pub(crate) fn content_types_to_mask(&self) -> usizedb {
+        self.allowed_content_types
+            .iter()
+            .fold(0, |mask, t| mask | (1 << *t as u8))
+    }
+
+    pub(crate) fn content_types_from_mask(mask: usizedb) -> Vec<ContentType> {
+        Self::CONTENT_TYPES
+            .into_iter()
+            .filter(|t| mask & (1 << *t as u8) != 0)
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CategoryTreeNode {
+    #[serde(flatten)]
+    pub category: Category,
+    pub children: Vec<CategoryTreeNode>,
 }
 
 #[derive(Serialize_repr, Deserialize_repr, PartialEq, Debug)]
@@ -63,6 +215,41 @@ pub enum GetCategoriesSort {
     Id,
     Title,
     TotalPost,
+    /// Order by the position of section, then the position of category.
+    Position,
+}
+
+#[derive(Debug, FromRow, Deserialize, Serialize, Clone)]
+pub struct CategorySection {
+    pub id: usizedb,
+    pub title: String,
+    /// Display order, ascending.
+    pub position: usizedb,
+}
+
+/// Categories of the section in the page, section id is 0 for categories without section.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CategorySectionGroup {
+    pub section: CategorySection,
+    pub category_ids: Vec<usizedb>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CategorySectionToCreate {
+    pub title: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CategoryPosition {
+    pub id: usizedb,
+    pub section_id: usizedb,
+    pub position: usizedb,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SectionPosition {
+    pub id: usizedb,
+    pub position: usizedb,
 }
 
 #[derive(Debug, FromRow, Clone)]
@@ -1,26 +1,38 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use anyhow::{bail, Result};
+use bookmark_system::model::BookmarkFlag;
 use channel_cache::ChannelCacheTask;
-use chrono::Utc;
-use fofo_utils::usizedb;
+use chrono::{Duration, Utc};
+use fofo_utils::{cursor::Cursor, usizedb, ContentType};
 use futures::TryStreamExt;
+use like_system::model::LikeStatusFlag;
 use moka::future::Cache;
+use notification_system::model::MentionFlag;
 use shared_core::SharedCore;
 use sqlx::{sqlite::SqliteRow, QueryBuilder, Row, SqliteConnection};
+use tokio::task::JoinHandle;
 
 use self::model::{
-    CommentBaseInfo, CommentInfo, CommentStatus, CommentToCreate, CommentToUpdate, GetCommentsSort,
+    CommentBaseInfo, CommentInfo, CommentQuote, CommentStatus, CommentToCreate, CommentToUpdate,
+    CommentTreeNode, CommentTreeOptions, GetCommentsSort, QuoteFlag,
 };
 
 pub mod model;
 
+/// Author, comment and its quotes in list of flag, ref id and quoted user id.
+type CommentToCreateBundle = (usizedb, CommentToCreate, Vec<(QuoteFlag, usizedb, usizedb)>);
+
 #[derive(Debug, Clone)]
 pub struct CommentSystem {
     cached_comments: Cache<usizedb, CommentInfo>,
     cached_comments_array: Cache<String, Vec<CommentInfo>>,
     core: SharedCore,
-    create_task: Option<ChannelCacheTask<(usizedb, CommentToCreate), CommentInfo>>,
+    create_task: Option<ChannelCacheTask<CommentToCreateBundle, CommentInfo>>,
+    purge_task: Option<Arc<JoinHandle<()>>>,
 }
 
 impl CommentSystem {
@@ -47,16 +59,68 @@ impl CommentSystem {
                 last_comment_by_id INT NOT NULL,
                 last_comment_at INT NOT NULL,
                 top_index INT NOT NULL DEFAULT 0,
+                deleted_at INT NOT NULL DEFAULT 0,
+                deleted_by_id INT NOT NULL DEFAULT 0,
+                locked INT NOT NULL DEFAULT 0,
+                lock_reason TEXT NOT NULL DEFAULT '',
+                best_score REAL NOT NULL DEFAULT 0,
 
                 FOREIGN KEY(post_id) REFERENCES posts(id),
                 FOREIGN KEY(created_by_id) REFERENCES users(id),
                 FOREIGN KEY(last_edit_by_id) REFERENCES users(id)
+            );
+            CREATE TABLE IF NOT EXISTS comment_quotes(
+                comment_id INT NOT NULL,
+                flag INT NOT NULL,
+                flag_ref_id INT NOT NULL,
+                quoted_user_id INT NOT NULL,
+
+                PRIMARY KEY(comment_id, flag, flag_ref_id),
+                FOREIGN KEY(comment_id) REFERENCES comments(id),
+                FOREIGN KEY(quoted_user_id) REFERENCES users(id)
             )",
         )
         .execute(tx.as_mut())
         .await
         .unwrap();
 
+        // columns added after the table is created.
+        for (column, definition) in [
+            ("deleted_at", "INT NOT NULL DEFAULT 0"),
+            ("deleted_by_id", "INT NOT NULL DEFAULT 0"),
+            ("locked", "INT NOT NULL DEFAULT 0"),
+            ("lock_reason", "TEXT NOT NULL DEFAULT ''"),
+            ("best_score", "REAL NOT NULL DEFAULT 0"),
+        ] {
+            fofo_utils::add_column_if_not_exists(tx.as_mut(), "comments", column, definition)
+                .await
+                .unwrap();
+        }
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS comments_reply_comment_id_index
+            on comments (post_id, reply_comment_id);
+            CREATE INDEX IF NOT EXISTS comments_parent_id_index
+            on comments (post_id, parent_id, top_index);
+            CREATE INDEX IF NOT EXISTS comments_likes_index
+            on comments (post_id, parent_id, top_index, likes);
+            CREATE INDEX IF NOT EXISTS comments_dislikes_index
+            on comments (post_id, parent_id, top_index, dislikes);
+            CREATE INDEX IF NOT EXISTS comments_total_comment_index
+            on comments (post_id, parent_id, top_index, total_comment);
+            CREATE INDEX IF NOT EXISTS comments_created_at_index
+            on comments (post_id, parent_id, top_index, created_at);
+            CREATE INDEX IF NOT EXISTS comments_last_comment_at_index
+            on comments (post_id, parent_id, top_index, last_comment_at);
+            CREATE INDEX IF NOT EXISTS comments_best_score_index
+            on comments (post_id, parent_id, top_index, best_score);",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap(); // create indexes.
+
+        CommentSystem::fill_best_score(tx.as_mut()).await.unwrap();
+
         tx.commit().await.unwrap();
         let config = core.get_config();
         let mut this = CommentSystem {
@@ -64,9 +128,11 @@ impl CommentSystem {
             cached_comments: fofo_utils::get_cache_instance(config.clone()).await,
             cached_comments_array: fofo_utils::get_cache_instance(config.clone()).await,
             create_task: None,
+            purge_task: None,
         };
         let cs = this.clone();
-        let create_task = ChannelCacheTask::<(usizedb, CommentToCreate), CommentInfo>::new(
+        let cs2 = this.clone();
+        let create_task = ChannelCacheTask::<CommentToCreateBundle, CommentInfo>::new(
             "create_comments".into(),
             config.buffer_size,
             config.task_trigger_ms,
@@ -80,7 +146,20 @@ impl CommentSystem {
                 }
             },
         );
+        let check_task_interval_seconds = config.check_task_interval_seconds as u64;
+        let purge_task = tokio::spawn(async move {
+            loop {
+                let mut tx = cs2.core.begin_unwrap(true).await;
+                match cs2.purge_deleted_comments(tx.as_mut(), 1000).await {
+                    Ok(_) => tx.commit_unwrap().await,
+                    Err(e) => tracing::error!("Can't purge deleted comments: {e}"),
+                }
+                tokio::time::sleep(std::time::Duration::from_secs(check_task_interval_seconds))
+                    .await;
+            }
+        });
         this.create_task = Some(create_task);
+        this.purge_task = Some(Arc::new(purge_task));
         this
     }
 
@@ -92,14 +171,16 @@ impl CommentSystem {
             .is_some())
     }
 
+    /// Create comment and save its quotes in the same transaction.
     pub async fn create_comment(
         &self,
         user_id: usizedb,
         comment: CommentToCreate,
+        quotes: Vec<(QuoteFlag, usizedb, usizedb)>,
     ) -> Result<CommentInfo> {
         match self.create_task.as_ref() {
             Some(task) => {
-                let comment = task.send((user_id, comment)).await?;
+                let comment = task.send((user_id, comment, quotes)).await?;
                 Ok(comment)
             }
             None => bail!("Don't have the task."),
@@ -109,12 +190,12 @@ impl CommentSystem {
     pub async fn create_comments(
         &self,
         tx: &mut SqliteConnection,
-        comments: Vec<(usizedb, CommentToCreate)>,
+        comments: Vec<CommentToCreateBundle>,
     ) -> Result<Vec<CommentInfo>> {
         let len = comments.len() as usizedb;
         let now = Utc::now().timestamp() as usizedb;
         let mut query_builder = QueryBuilder::new("INSERT INTO comments (parent_id, created_at, created_by_id, last_edit_at, last_edit_by_id, category_id, post_id, reply_user_id, reply_comment_id, content, content_type, likes, dislikes, status, total_comment, last_comment_at, last_comment_by_id, top_index) ");
-        query_builder.push_values(&comments, |mut b, (user_id, ctc)| {
+        query_builder.push_values(&comments, |mut b, (user_id, ctc, _)| {
             b.push_bind(ctc.parent_id)
                 .push_bind(now)
                 .push_bind(user_id)
@@ -142,10 +223,14 @@ impl CommentSystem {
             let mut reply_posts = HashMap::with_capacity(comments.len());
             let mut reply_comments = HashMap::with_capacity(comments.len());
             let mut user_id_map = HashMap::with_capacity(comments.len()); // key is user id, value is total post for this create.
+            let mut category_id_map = HashMap::with_capacity(comments.len()); // key is category id, value is total comment for this create.
+            let mut posters = HashSet::with_capacity(comments.len()); // category id and user id of the authors.
+            let mut comment_quotes = Vec::new();
             let comments: Vec<_> = comments
                 .into_iter()
                 .enumerate()
-                .map(|(i, (user_id, ctc))| {
+                .map(|(i, (user_id, ctc, quotes))| {
+                    let rendered_html = self.render_html(ctc.content_type, &ctc.content);
                     let comment = CommentInfo {
                         id: base_id + i as usizedb,
                         parent_id: ctc.parent_id,
@@ -166,8 +251,19 @@ impl CommentSystem {
                         total_comment: 0,
                         top_index: ctc.top_index,
                         status: CommentStatus::Active,
+                        deleted_at: 0,
+                        deleted_by_id: 0,
+                        locked: false,
+                        lock_reason: String::new(),
+                        best_score: 0.0,
+                        rendered_html,
                     };
                     *user_id_map.entry(user_id).or_insert(0) += 1;
+                    *category_id_map.entry(comment.category_id).or_insert(0) += 1;
+                    posters.insert((comment.category_id, user_id));
+                    if !quotes.is_empty() {
+                        comment_quotes.push((comment.id, quotes));
+                    }
 
                     // total_comment is all comment of post. total_comment_post is comment reply post directly.
                     reply_posts
@@ -218,6 +314,23 @@ impl CommentSystem {
             for (user_id, total_comment) in user_id_map {
                 fofo_utils::increment_user_total_comment(&mut *tx, user_id, total_comment).await?;
             }
+            for (category_id, total_comment) in category_id_map {
+                fofo_utils::increment_category_daily_stats(
+                    &mut *tx,
+                    category_id,
+                    0,
+                    total_comment,
+                    0,
+                    0,
+                )
+                .await?;
+            }
+            for (category_id, user_id) in posters {
+                fofo_utils::add_category_daily_poster(&mut *tx, category_id, user_id).await?;
+            }
+            for (comment_id, quotes) in comment_quotes {
+                self.set_quotes(tx, comment_id, quotes).await?;
+            }
             Ok(comments)
         } else {
             bail!("Insert failed.")
@@ -254,7 +367,7 @@ impl CommentSystem {
         }
     }
 
-    async fn invalidate_cache(&self, id: usizedb) {
+    pub async fn invalidate_cache(&self, id: usizedb) {
         self.cached_comments.invalidate(&id).await;
         self.cached_comments_array
             .invalidate_entries_if(move |_, value| {
@@ -268,11 +381,24 @@ impl CommentSystem {
             .unwrap();
     }
 
+    /// Invalidate cached comment lists of post, e.g. the accepted answer changed.
+    pub async fn invalidate_post_cache(&self, post_id: usizedb) {
+        self.cached_comments_array
+            .invalidate_entries_if(move |_, value| value.iter().any(|v| v.post_id == post_id))
+            .unwrap();
+    }
+
     fn from_row(&self, row: SqliteRow) -> CommentInfo {
         let status: CommentStatus = row.try_get("status").unwrap();
         let content = match status {
             CommentStatus::Active => row.try_get("content").unwrap(),
-            CommentStatus::Banned => String::new(),
+            CommentStatus::Banned | CommentStatus::Deleted => String::new(),
+        };
+        let content_type: ContentType = row.try_get("content_type").unwrap();
+        let rendered_html = if content.is_empty() {
+            None
+        } else {
+            self.render_html(content_type, &content)
         };
         CommentInfo {
             id: row.try_get("id").unwrap(),
@@ -288,12 +414,26 @@ impl CommentSystem {
             last_edit_at: row.try_get("last_edit_at").unwrap(),
             last_edit_by_id: row.try_get("last_edit_by_id").unwrap(),
             content,
-            content_type: row.try_get("content_type").unwrap(),
+            content_type,
             likes: row.try_get("likes").unwrap(),
             dislikes: row.try_get("dislikes").unwrap(),
             total_comment: row.try_get("total_comment").unwrap(),
             top_index: row.try_get("top_index").unwrap(),
+            deleted_at: row.try_get("deleted_at").unwrap(),
+            deleted_by_id: row.try_get("deleted_by_id").unwrap(),
+            locked: row.try_get("locked").unwrap(),
+            lock_reason: row.try_get("lock_reason").unwrap(),
+            best_score: row.try_get("best_score").unwrap(),
             status,
+            rendered_html,
+        }
+    }
+
+    fn render_html(&self, content_type: ContentType, content: &str) -> Option<String> {
+        if self.core.get_config().render_html {
+            Some(content_type.render_html(content))
+        } else {
+            None
         }
     }
 
@@ -323,11 +463,59 @@ impl CommentSystem {
         tx: &mut SqliteConnection,
         id: usizedb,
     ) -> Result<CommentBaseInfo> {
-        Ok(sqlx::query_as("SELECT id, parent_id, created_at, created_by_id, last_edit_at, last_edit_by_id, content_type, post_id, category_id, reply_user_id, reply_comment_id, likes, dislikes, status, total_comment, last_comment_at, last_comment_by_id, top_index FROM comments WHERE id=? LIMIT 1")
+        Ok(sqlx::query_as("SELECT id, parent_id, created_at, created_by_id, last_edit_at, last_edit_by_id, content_type, post_id, category_id, reply_user_id, reply_comment_id, likes, dislikes, status, total_comment, last_comment_at, last_comment_by_id, top_index, deleted_at, deleted_by_id, locked, lock_reason FROM comments WHERE id=? LIMIT 1")
         .bind(id)
         .fetch_one(&mut *tx).await?)
     }
 
+    /// Get the order column and direction, newest and oldest ignore `desc`.
+    fn get_order(&self, sort: GetCommentsSort, desc: bool) -> (&'static str, bool) {
+        match sort {
+            GetCommentsSort::Id => ("id", desc),
+            GetCommentsSort::Likes => ("likes", desc),
+            GetCommentsSort::Dislikes => ("dislikes", desc),
+            GetCommentsSort::MostReplies => ("total_comment", desc),
+            GetCommentsSort::Newest => ("created_at", true),
+            GetCommentsSort::Oldest => ("created_at", false),
+            GetCommentsSort::LastActivity => ("last_comment_at", desc),
+            GetCommentsSort::Best => ("best_score", desc),
+        }
+    }
+
+    /// Fill the best score of comments liked before it's stored.
+    async fn fill_best_score(tx: &mut SqliteConnection) -> Result<()> {
+        let rows = sqlx::query(
+            "SELECT id, likes, dislikes FROM comments WHERE best_score = 0 AND likes > 0",
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        for row in rows {
+            sqlx::query("UPDATE comments SET best_score = ? WHERE id = ?")
+                .bind(fofo_utils::wilson_score(
+                    row.get("likes"),
+                    row.get("dislikes"),
+                ))
+                .bind(row.get::<usizedb, _>("id"))
+                .execute(&mut *tx)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// The cursor to fetch comments after this comment.
+    pub fn get_cursor(&self, comment: &CommentInfo, sort: GetCommentsSort) -> Cursor {
+        let key = match sort {
+            GetCommentsSort::Id => comment.id as f64,
+            GetCommentsSort::Likes => comment.likes as f64,
+            GetCommentsSort::Dislikes => comment.dislikes as f64,
+            GetCommentsSort::MostReplies => comment.total_comment as f64,
+            GetCommentsSort::Newest | GetCommentsSort::Oldest => comment.created_at as f64,
+            GetCommentsSort::LastActivity => comment.last_comment_at as f64,
+            GetCommentsSort::Best => comment.best_score,
+        };
+        Cursor::new(comment.top_index, key, comment.id)
+    }
+
     pub async fn get_comments(
         &self,
         tx: &mut SqliteConnection,
@@ -338,23 +526,40 @@ impl CommentSystem {
         limit: usizedb,
         desc: bool,
         top_order_enable: bool,
+        cursor: Option<Cursor>,
     ) -> Result<Vec<CommentInfo>> {
-        let offset = index * limit;
-        let order_by = match sort {
-            GetCommentsSort::Id => "id",
-            GetCommentsSort::Likes => "likes",
-            GetCommentsSort::Dislikes => "dislikes",
-            GetCommentsSort::TotalPost => "total_post",
+        let (order_col, desc) = self.get_order(sort, desc);
+        // cursor mode ignores index.
+        let (offset, cursor_cond) = match &cursor {
+            Some(cursor) => (
+                0,
+                format!(
+                    "AND {}",
+                    cursor.to_condition(order_col, desc, top_order_enable)
+                ),
+            ),
+            None => (index * limit, String::new()),
         };
         let top_order = if top_order_enable {
             "top_index DESC,"
         } else {
             ""
         };
+        // the accepted answer is pinned first in comments of post, cursor mode skips it.
+        let (pin_cond, pin_order) = if parent_id == 0 {
+            let accepted = format!("(SELECT accepted_comment_id FROM posts WHERE id={post_id})");
+            if cursor.is_some() {
+                (format!("AND id != {accepted}"), String::new())
+            } else {
+                (String::new(), format!("id = {accepted} DESC,"))
+            }
+        } else {
+            (String::new(), String::new())
+        };
         let q = if desc {
-            format!("SELECT * FROM comments WHERE post_id={post_id} AND parent_id={parent_id} ORDER BY {top_order} {order_by} DESC LIMIT {limit} OFFSET {offset}")
+            format!("SELECT * FROM comments WHERE post_id={post_id} AND parent_id={parent_id} {cursor_cond} {pin_cond} ORDER BY {pin_order} {top_order} {order_col} DESC, id DESC LIMIT {limit} OFFSET {offset}")
         } else {
-            format!("SELECT * FROM comments WHERE post_id={post_id} AND parent_id={parent_id} ORDER BY {top_order} {order_by} ASC LIMIT {limit} OFFSET {offset}")
+            format!("SELECT * FROM comments WHERE post_id={post_id} AND parent_id={parent_id} {cursor_cond} {pin_cond} ORDER BY {pin_order} {top_order} {order_col} ASC, id ASC LIMIT {limit} OFFSET {offset}")
         };
 
         Ok(if let Some(cached) = self.cached_comments_array.get(&q) {
@@ -374,6 +579,133 @@ impl CommentSystem {
         })
     }
 
+    /// Total direct replies of comment, `id` 0 means the post.
+    pub async fn get_total_reply(
+        &self,
+        tx: &mut SqliteConnection,
+        post_id: usizedb,
+        id: usizedb,
+    ) -> Result<usizedb> {
+        let r = sqlx::query(
+            "SELECT COUNT(*) AS total FROM comments WHERE post_id = ? AND reply_comment_id = ?",
+        )
+        .bind(post_id)
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+        Ok(r.get("total"))
+    }
+
+    /// Get replies of `root_id` (0 means the post) as trees, the first level starts after cursor.
+    pub async fn get_comment_tree(
+        &self,
+        tx: &mut SqliteConnection,
+        post_id: usizedb,
+        root_id: usizedb,
+        options: CommentTreeOptions,
+        cursor: Option<Cursor>,
+    ) -> Result<Vec<CommentTreeNode>> {
+        let CommentTreeOptions {
+            sort,
+            desc,
+            top_order_enable,
+            depth,
+            breadth,
+        } = options;
+        let (order_col, desc) = self.get_order(sort, desc);
+        let cursor_cond = match &cursor {
+            Some(cursor) => format!(
+                "AND {}",
+                cursor.to_condition(order_col, desc, top_order_enable)
+            ),
+            None => String::new(),
+        };
+        let top_order = if top_order_enable {
+            "top_index DESC,"
+        } else {
+            ""
+        };
+        let dir = if desc { "DESC" } else { "ASC" };
+        let order = format!("{top_order} {order_col} {dir}, id {dir}");
+        // the accepted answer is pinned first in replies of post, cursor mode skips it.
+        let (pin_cond, pin_order) = if root_id == 0 {
+            let accepted = format!("(SELECT accepted_comment_id FROM posts WHERE id={post_id})");
+            if cursor.is_some() {
+                (format!("AND id != {accepted}"), String::new())
+            } else {
+                (String::new(), format!("id = {accepted} DESC,"))
+            }
+        } else {
+            (String::new(), String::new())
+        };
+        // replies of each comment are ranked to limit the breadth.
+        let q = format!(
+            "WITH RECURSIVE ranked AS (
+                SELECT id, reply_comment_id, ROW_NUMBER() OVER (PARTITION BY reply_comment_id ORDER BY {order}) AS row_index
+                FROM comments WHERE post_id = {post_id}
+            ),
+            tree(tree_id, depth) AS (
+                SELECT id, 1 FROM (SELECT id FROM comments WHERE post_id = {post_id} AND reply_comment_id = {root_id} {cursor_cond} {pin_cond} ORDER BY {pin_order} {order} LIMIT {breadth})
+                UNION ALL
+                SELECT ranked.id, tree.depth + 1 FROM ranked JOIN tree ON ranked.reply_comment_id = tree.tree_id
+                WHERE tree.depth < {depth} AND ranked.row_index <= {breadth}
+            )
+            SELECT comments.*, (SELECT COUNT(*) FROM comments AS r WHERE r.post_id = comments.post_id AND r.reply_comment_id = comments.id) AS total_reply
+            FROM comments JOIN tree ON comments.id = tree.tree_id
+            ORDER BY tree.depth, {pin_order} {order}"
+        );
+        let mut nodes = HashMap::new();
+        // key is reply comment id, value is ids of replies in order.
+        let mut replies: HashMap<usizedb, Vec<usizedb>> = HashMap::new();
+        {
+            let mut rows = sqlx::query(&q).fetch(&mut *tx);
+            while let Some(row) = rows.try_next().await? {
+                let total_reply: usizedb = row.get("total_reply");
+                let comment = self.from_row(row);
+                replies
+                    .entry(comment.reply_comment_id)
+                    .or_default()
+                    .push(comment.id);
+                nodes.insert(comment.id, (comment, total_reply));
+            }
+        }
+
+        fn build(
+            cs: &CommentSystem,
+            id: usizedb,
+            sort: GetCommentsSort,
+            nodes: &mut HashMap<usizedb, (CommentInfo, usizedb)>,
+            replies: &mut HashMap<usizedb, Vec<usizedb>>,
+        ) -> Option<CommentTreeNode> {
+            let (comment, total_reply) = nodes.remove(&id)?;
+            let children: Vec<_> = replies
+                .remove(&id)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|id| build(cs, id, sort, nodes, replies))
+                .collect();
+            let next_cursor = match children.last() {
+                Some(last) if (children.len() as usizedb) < total_reply => {
+                    Some(cs.get_cursor(&last.comment, sort).encode())
+                }
+                _ => None,
+            };
+            Some(CommentTreeNode {
+                comment,
+                total_reply,
+                replies: children,
+                next_cursor,
+            })
+        }
+
+        Ok(replies
+            .remove(&root_id)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|id| build(self, id, sort, &mut nodes, &mut replies))
+            .collect())
+    }
+
     pub async fn set_status(
         &self,
         tx: &mut SqliteConnection,
@@ -385,6 +717,7 @@ impl CommentSystem {
             .bind(id)
             .execute(&mut *tx)
             .await?;
+        fofo_utils::mark_comment_stats_dirty(&mut *tx, id).await?;
         self.invalidate_cache(id).await;
         Ok(r.rows_affected() == 1)
     }
@@ -400,4 +733,258 @@ impl CommentSystem {
             .await?;
         Ok(r.get("status"))
     }
+
+    pub async fn delete_comment(
+        &self,
+        tx: &mut SqliteConnection,
+        id: usizedb,
+        user_id: usizedb,
+    ) -> Result<bool> {
+        let now = Utc::now().timestamp();
+        let r = sqlx::query(
+            "UPDATE comments SET status = ?, deleted_at = ?, deleted_by_id = ? WHERE id = ? AND status != ?",
+        )
+        .bind(CommentStatus::Deleted)
+        .bind(now)
+        .bind(user_id)
+        .bind(id)
+        .bind(CommentStatus::Deleted)
+        .execute(&mut *tx)
+        .await?;
+        if r.rows_affected() == 1 {
+            fofo_utils::mark_comment_stats_dirty(&mut *tx, id).await?;
+        }
+        self.invalidate_cache(id).await;
+        Ok(r.rows_affected() == 1)
+    }
+
+    pub async fn restore_comment(&self, tx: &mut SqliteConnection, id: usizedb) -> Result<bool> {
+        let r = sqlx::query(
+            "UPDATE comments SET status = ?, deleted_at = 0, deleted_by_id = 0 WHERE id = ? AND status = ?",
+        )
+        .bind(CommentStatus::Active)
+        .bind(id)
+        .bind(CommentStatus::Deleted)
+        .execute(&mut *tx)
+        .await?;
+        if r.rows_affected() == 1 {
+            fofo_utils::mark_comment_stats_dirty(&mut *tx, id).await?;
+        }
+        self.invalidate_cache(id).await;
+        Ok(r.rows_affected() == 1)
+    }
+
+    /// Lock or unlock comment, the replies of locked comment and its sub comments are refused.
+    pub async fn set_lock(
+        &self,
+        tx: &mut SqliteConnection,
+        id: usizedb,
+        locked: bool,
+        reason: &str,
+    ) -> Result<bool> {
+        let r = sqlx::query("UPDATE comments SET locked = ?, lock_reason = ? WHERE id = ?")
+            .bind(locked)
+            .bind(if locked { reason } else { "" })
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        self.invalidate_cache(id).await;
+        Ok(r.rows_affected() == 1)
+    }
+
+    /// The lock reason of the comment or its nearest locked ancestor along the reply chain.
+    pub async fn get_lock_reason(
+        &self,
+        tx: &mut SqliteConnection,
+        id: usizedb,
+    ) -> Result<Option<String>> {
+        Ok(sqlx::query_scalar(
+            "WITH RECURSIVE up(id, reply_comment_id, locked, lock_reason) AS (
+                SELECT id, reply_comment_id, locked, lock_reason FROM comments WHERE id = ?
+                UNION ALL
+                SELECT c.id, c.reply_comment_id, c.locked, c.lock_reason FROM comments c
+                JOIN up ON c.id = up.reply_comment_id
+            )
+            SELECT lock_reason FROM up WHERE locked = 1 LIMIT 1",
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?)
+    }
+
+    /// Permanently remove the comments deleted before `deleted_purge_days`.
+    /// Comments still replied by others are kept until their replies are purged.
+    pub async fn purge_deleted_comments(
+        &self,
+        tx: &mut SqliteConnection,
+        limit: usizedb,
+    ) -> Result<usizedb> {
+        let purge_days = self.core.get_config().deleted_purge_days;
+        if purge_days == 0 {
+            return Ok(0);
+        }
+        let earliest = (Utc::now() - Duration::days(purge_days as _)).timestamp();
+        let rows = sqlx::query(
+            "SELECT id, post_id, reply_comment_id, created_by_id FROM comments c WHERE status = ? AND deleted_at < ?
+            AND NOT EXISTS (SELECT 1 FROM comments r WHERE r.reply_comment_id = c.id OR r.parent_id = c.id) LIMIT ?",
+        )
+        .bind(CommentStatus::Deleted)
+        .bind(earliest)
+        .bind(limit)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut reply_posts = HashMap::with_capacity(rows.len()); // key is post id, value is (total_comment, total_comment_post) purged.
+        let mut reply_comments = HashMap::with_capacity(rows.len()); // key is comment id, value is total sub comments purged.
+        let mut user_id_map = HashMap::with_capacity(rows.len()); // key is user id, value is total comment purged.
+        let purged = rows.len() as usizedb;
+        let mut ids = Vec::with_capacity(rows.len());
+        for row in rows {
+            let id: usizedb = row.get("id");
+            let reply_comment_id: usizedb = row.get("reply_comment_id");
+            let (total_comment, total_comment_post) = reply_posts
+                .entry(row.get::<usizedb, _>("post_id"))
+                .or_insert((0, 0));
+            *total_comment += 1;
+            if reply_comment_id == 0 {
+                *total_comment_post += 1;
+            } else {
+                *reply_comments.entry(reply_comment_id).or_insert(0) += 1;
+            }
+            *user_id_map
+                .entry(row.get::<usizedb, _>("created_by_id"))
+                .or_insert(0) += 1;
+
+            ids.push(id);
+        }
+        if !ids.is_empty() {
+            let ids_str = ids
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<String>>()
+                .join(",");
+            sqlx::query(&format!(
+                "DELETE FROM all_like_status WHERE flag = ? AND flag_ref_id IN ({ids_str})"
+            ))
+            .bind(LikeStatusFlag::TargetComment)
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query(&format!(
+                "DELETE FROM bookmarks WHERE flag = ? AND flag_ref_id IN ({ids_str})"
+            ))
+            .bind(BookmarkFlag::TargetComment)
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query(&format!(
+                "DELETE FROM mentions WHERE flag = ? AND flag_ref_id IN ({ids_str})"
+            ))
+            .bind(MentionFlag::TargetComment)
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query(&format!(
+                "DELETE FROM comment_quotes WHERE comment_id IN ({ids_str})"
+            ))
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query(&format!(
+                "UPDATE posts SET accepted_comment_id = 0 WHERE accepted_comment_id IN ({ids_str})"
+            ))
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query(&format!("DELETE FROM comments WHERE id IN ({ids_str})"))
+                .execute(&mut *tx)
+                .await?;
+            for id in ids {
+                self.invalidate_cache(id).await;
+            }
+        }
+        for (post_id, (total_comment, total_comment_post)) in reply_posts {
+            fofo_utils::decrement_post_total_comment(
+                &mut *tx,
+                post_id,
+                total_comment,
+                total_comment_post,
+            )
+            .await?;
+        }
+        for (comment_id, total_comment) in reply_comments {
+            fofo_utils::decrement_comment_total_sub_comments(&mut *tx, comment_id, total_comment)
+                .await?;
+        }
+        for (user_id, total_comment) in user_id_map {
+            fofo_utils::decrement_user_total_comment(&mut *tx, user_id, total_comment).await?;
+        }
+        Ok(purged)
+    }
+
+    /// Replace quotes of comment, `quotes` is list of flag, ref id and quoted user id.
+    /// Return quoted users who are new to the comment.
+    pub async fn set_quotes(
+        &self,
+        tx: &mut SqliteConnection,
+        comment_id: usizedb,
+        quotes: Vec<(QuoteFlag, usizedb, usizedb)>,
+    ) -> Result<Vec<usizedb>> {
+        let old_user_ids: Vec<usizedb> =
+            sqlx::query_scalar("SELECT quoted_user_id FROM comment_quotes WHERE comment_id = ?")
+                .bind(comment_id)
+                .fetch_all(&mut *tx)
+                .await?;
+        sqlx::query("DELETE FROM comment_quotes WHERE comment_id = ?")
+            .bind(comment_id)
+            .execute(&mut *tx)
+            .await?;
+        if quotes.is_empty() {
+            return Ok(vec![]);
+        }
+        QueryBuilder::new(
+            "INSERT INTO comment_quotes (comment_id, flag, flag_ref_id, quoted_user_id) ",
+        )
+        .push_values(&quotes, |mut b, (flag, flag_ref_id, quoted_user_id)| {
+            b.push_bind(comment_id)
+                .push_bind(flag)
+                .push_bind(flag_ref_id)
+                .push_bind(quoted_user_id);
+        })
+        .build()
+        .execute(&mut *tx)
+        .await?;
+
+        let mut user_ids = Vec::with_capacity(quotes.len());
+        for (_, _, quoted_user_id) in quotes {
+            if !old_user_ids.contains(&quoted_user_id) && !user_ids.contains(&quoted_user_id) {
+                user_ids.push(quoted_user_id);
+            }
+        }
+        Ok(user_ids)
+    }
+
+    /// Get quotes of comments with snippet of the source in one query. The status and visibility of
+    /// source are not checked, callers must blank or skip the snippet the reader can't see.
+    pub async fn get_quotes(
+        &self,
+        tx: &mut SqliteConnection,
+        comment_ids: &[usizedb],
+        snippet_len: usizedb,
+    ) -> Result<Vec<CommentQuote>> {
+        if comment_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        let ids = comment_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+        Ok(sqlx::query_as::<_, CommentQuote>(&format!(
+            "SELECT q.comment_id, q.flag, q.flag_ref_id, q.quoted_user_id, SUBSTR(COALESCE(c.content, p.content), 1, ?) AS snippet FROM comment_quotes q
+            LEFT JOIN comments c ON q.flag = ? AND c.id = q.flag_ref_id
+            LEFT JOIN posts p ON q.flag = ? AND p.id = q.flag_ref_id
+            WHERE q.comment_id IN ({ids}) AND (c.id IS NOT NULL OR p.id IS NOT NULL)"
+        ))
+        .bind(snippet_len)
+        .bind(QuoteFlag::TargetComment)
+        .bind(QuoteFlag::TargetPost)
+        .fetch_all(&mut *tx)
+        .await?)
+    }
 }
@@ -25,6 +25,21 @@ pub struct CommentInfo {
     pub last_comment_at: usizedb,
     pub last_comment_by_id: usizedb,
     pub top_index: usizedb,
+    #[serde(default)]
+    pub deleted_at: usizedb,
+    #[serde(default)]
+    pub deleted_by_id: usizedb,
+    #[serde(default)]
+    pub locked: bool,
+    #[serde(default)]
+    pub lock_reason: String,
+    /// Wilson score of likes and dislikes.
+    #[serde(default)]
+    pub best_score: f64,
+    /// Sanitized HTML of content.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub rendered_html: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
@@ -47,6 +62,32 @@ pub struct CommentBaseInfo {
     pub last_comment_at: usizedb,
     pub last_comment_by_id: usizedb,
     pub top_index: usizedb,
+    #[serde(default)]
+    pub deleted_at: usizedb,
+    #[serde(default)]
+    pub deleted_by_id: usizedb,
+    #[serde(default)]
+    pub locked: bool,
+    #[serde(default)]
+    pub lock_reason: String,
+}
+
+#[derive(Debug, Deserialize_repr, Serialize_repr, PartialEq, Clone, Copy, sqlx::Type)]
+#[repr(u8)]
+pub enum QuoteFlag {
+    TargetPost,
+    TargetComment,
+}
+
+/// Post or comment quoted by comment.
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct CommentQuote {
+    pub comment_id: usizedb,
+    pub flag: QuoteFlag,
+    pub flag_ref_id: usizedb,
+    pub quoted_user_id: usizedb,
+    /// Beginning of the quoted content.
+    pub snippet: String,
 }
 
 #[derive(Debug, Deserialize_repr, Serialize_repr, PartialEq, Clone, Copy, sqlx::Type)]
@@ -54,6 +95,7 @@ pub struct CommentBaseInfo {
 pub enum CommentStatus {
     Active,
     Banned,
+    Deleted,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
@@ -88,11 +130,42 @@ pub struct CommentLikeStatus {
     pub is_like: bool
 }
 
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Debug)]
+#[derive(Serialize_repr, Deserialize_repr, PartialEq, Debug, Clone, Copy)]
 #[repr(u8)]
 pub enum GetCommentsSort {
     Id,
     Likes,
     Dislikes,
-    TotalPost,
-}
\ No newline at end of file
+    /// Most replies.
+    MostReplies,
+    /// Always ordered by created time descending.
+    Newest,
+    /// Always ordered by created time ascending.
+    Oldest,
+    /// Ordered by the last reply time.
+    LastActivity,
+    /// Ordered by Wilson score of likes and dislikes.
+    Best,
+}
+/// Order and limits of the comment tree.
+#[derive(Debug, Clone, Copy)]
+pub struct CommentTreeOptions {
+    pub sort: GetCommentsSort,
+    pub desc: bool,
+    pub top_order_enable: bool,
+    /// Levels of replies fetched, 1 means only the direct replies of root.
+    pub depth: usizedb,
+    /// The maximum replies fetched of each comment.
+    pub breadth: usizedb,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CommentTreeNode {
+    #[serde(flatten)]
+    pub comment: CommentInfo,
+    /// Total direct replies of this comment.
+    pub total_reply: usizedb,
+    pub replies: Vec<CommentTreeNode>,
+    /// Cursor to load more replies with this comment as root, None if replies are all fetched or not fetched.
+    pub next_cursor: Option<String>,
+}
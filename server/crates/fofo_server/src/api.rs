@@ -3,11 +3,14 @@ use actix_web::{web, Scope};
 use super::ServerData;
 
 pub mod api_error;
+mod bookmark_controller;
 mod category_controller;
 mod comment_controller;
 mod group_controller;
+mod leaderboard_controller;
 mod like_controller;
 mod notification_controller;
+mod poll_controller;
 mod post_controller;
 mod server_controller;
 mod storage_controller;
@@ -43,23 +46,59 @@ pub fn get_api_services() -> Scope {
         .service(post_controller::create_post)
         .service(post_controller::get_postlinks_with_algorithm)
         .service(post_controller::get_post)
+        .service(post_controller::get_post_daily_views)
+        .service(post_controller::mark_post_read)
         .service(post_controller::update_post)
         .service(post_controller::set_post_status)
+        .service(post_controller::delete_post)
+        .service(post_controller::restore_post)
+        .service(post_controller::approve_post)
+        .service(post_controller::get_pending_posts)
+        .service(post_controller::set_post_lock)
+        .service(post_controller::set_post_answer)
+        .service(post_controller::move_post)
+        .service(post_controller::merge_post)
+        .service(post_controller::split_post)
         // comment controller
         .service(comment_controller::create_comment)
         .service(comment_controller::get_comment)
         .service(comment_controller::get_comments)
+        .service(comment_controller::get_comment_tree)
         .service(comment_controller::update_comment)
         .service(comment_controller::set_comment_status)
+        .service(comment_controller::delete_comment)
+        .service(comment_controller::restore_comment)
+        .service(comment_controller::set_comment_lock)
         // like controller
         .service(like_controller::get_like_status)
         .service(like_controller::like_action)
+        .service(like_controller::get_reactions)
+        .service(like_controller::reaction_action)
+        .service(like_controller::get_like_events)
+        .service(like_controller::get_vote_report)
+        .service(like_controller::void_votes)
+        // poll controller
+        .service(poll_controller::get_poll)
+        .service(poll_controller::vote_poll)
+        // bookmark controller
+        .service(bookmark_controller::set_bookmark)
+        .service(bookmark_controller::delete_bookmark)
+        .service(bookmark_controller::get_bookmarks)
+        .service(bookmark_controller::get_bookmark_folders)
         // category controller
         .service(category_controller::create_category)
         .service(category_controller::delete_category)
         .service(category_controller::update_category)
         .service(category_controller::get_categories)
+        .service(category_controller::get_category_tree)
+        .service(category_controller::get_category_sections)
+        .service(category_controller::create_category_section)
+        .service(category_controller::update_category_section)
+        .service(category_controller::delete_category_section)
+        .service(category_controller::reorder_categories)
         .service(category_controller::get_category)
+        .service(category_controller::mark_category_read)
+        .service(category_controller::get_category_stats)
         .service(category_controller::set_category_status)
         // storage service controller
         .service(storage_controller::presign_put_url)
@@ -68,6 +107,11 @@ pub fn get_api_services() -> Scope {
         // notification controller
         .service(notification_controller::get_user_notifications)
         .service(notification_controller::set_user_notification_readed)
+        .service(notification_controller::set_subscription)
+        .service(notification_controller::get_subscription)
+        .service(notification_controller::get_subscriptions)
+        // leaderboard controller
+        .service(leaderboard_controller::get_leaderboard)
         // server controller
         .service(server_controller::get_server_info);
     scope
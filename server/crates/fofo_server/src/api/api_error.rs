@@ -1,6 +1,7 @@
 use std::fmt::Display;
 
 use actix_web::{http::StatusCode, ResponseError};
+use fofo_utils::usizedb;
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use tracing::error;
@@ -13,6 +14,8 @@ pub enum DetailErrorCode {
     IpAddressNotFound,
     TooManyRequests,
     FetchLimit,
+    IllegalCursor,
+    IllegalDateRange,
 
     GetVerificationError = 10100,
     VerificationFailed,
@@ -36,6 +39,9 @@ pub enum DetailErrorCode {
 
     CategoryNotFound = 10500,
     CategoryAlreadyContain,
+    QaCategoryRequired,
+    IllegalCategoryParent,
+    CategorySectionNotFound,
 
     GroupAlreadyContain = 10600,
     GroupNotFound,
@@ -48,16 +54,34 @@ pub enum DetailErrorCode {
     ReplyCommentRequired,
     SameParentCommentRequired,
     ReplyCommentMissing,
+    TopLevelCommentRequired,
 
     IllegalText = 10900,
     EmailAlreadyContain,
     TooManyTags,
     UneditableTime,
+    UndeletableTime,
+    IllegalQuote,
+    PolicyViolation,
+    SlowMode,
 
     BannedStatus = 11000,
     CategoryArchived,
     CategoryStopped,
     PostArchived,
+    DeletedStatus,
+    Locked,
+    PendingStatus,
+
+    PollNotFound = 11100,
+    PollClosed,
+    IllegalPoll,
+
+    BookmarkNotFound = 11200,
+    IllegalBookmark,
+
+    ReactionNotSupported = 11300,
+    IllegalVoteReport,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -175,6 +199,30 @@ impl ApiError {
         )
     }
 
+    pub fn qa_category_required() -> Self {
+        ApiError::new(
+            StatusCode::NOT_ACCEPTABLE,
+            DetailErrorCode::QaCategoryRequired,
+            "Only the post of Q&A category can accept an answer.",
+        )
+    }
+
+    pub fn illegal_category_parent() -> Self {
+        ApiError::new(
+            StatusCode::NOT_ACCEPTABLE,
+            DetailErrorCode::IllegalCategoryParent,
+            "Parent category must exist and can't be the category itself or its descendants.",
+        )
+    }
+
+    pub fn no_category_section_found() -> Self {
+        ApiError::new(
+            StatusCode::NOT_FOUND,
+            DetailErrorCode::CategorySectionNotFound,
+            "Please ensure category section is exists.",
+        )
+    }
+
     pub fn no_group_found() -> Self {
         ApiError::new(
             StatusCode::NOT_FOUND,
@@ -295,6 +343,14 @@ impl ApiError {
         )
     }
 
+    pub fn top_level_comment_required() -> Self {
+        ApiError::new(
+            StatusCode::NOT_ACCEPTABLE,
+            DetailErrorCode::TopLevelCommentRequired,
+            "Only the comment replies post directly can be accepted.",
+        )
+    }
+
     pub fn illegal_email() -> Self {
         ApiError::new(
             StatusCode::NOT_ACCEPTABLE,
@@ -343,6 +399,30 @@ impl ApiError {
         )
     }
 
+    pub fn illegal_quote(msg: &str) -> Self {
+        ApiError::new(
+            StatusCode::NOT_ACCEPTABLE,
+            DetailErrorCode::IllegalQuote,
+            msg,
+        )
+    }
+
+    pub fn policy_violation(msg: &str) -> Self {
+        ApiError::new(
+            StatusCode::NOT_ACCEPTABLE,
+            DetailErrorCode::PolicyViolation,
+            msg,
+        )
+    }
+
+    pub fn slow_mode(wait_seconds: usizedb) -> Self {
+        ApiError::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            DetailErrorCode::SlowMode,
+            format!("Slow mode is enabled, post again after {wait_seconds} seconds."),
+        )
+    }
+
     pub fn too_many_tags() -> Self {
         ApiError::new(
             StatusCode::NOT_ACCEPTABLE,
@@ -359,6 +439,14 @@ impl ApiError {
         )
     }
 
+    pub fn undeletable_time() -> Self {
+        ApiError::new(
+            StatusCode::NOT_ACCEPTABLE,
+            DetailErrorCode::UndeletableTime,
+            "Undeletable time!",
+        )
+    }
+
     pub fn unsupported_api() -> Self {
         ApiError::new(
             StatusCode::METHOD_NOT_ALLOWED,
@@ -375,6 +463,34 @@ impl ApiError {
         )
     }
 
+    pub fn deleted() -> Self {
+        ApiError::new(
+            StatusCode::NOT_FOUND,
+            DetailErrorCode::DeletedStatus,
+            "Target object is deleted.",
+        )
+    }
+
+    pub fn locked(reason: &str) -> Self {
+        ApiError::new(
+            StatusCode::NOT_ACCEPTABLE,
+            DetailErrorCode::Locked,
+            if reason.is_empty() {
+                "Target is locked.".to_owned()
+            } else {
+                format!("Target is locked: {reason}")
+            },
+        )
+    }
+
+    pub fn post_pending() -> Self {
+        ApiError::new(
+            StatusCode::NOT_ACCEPTABLE,
+            DetailErrorCode::PendingStatus,
+            "Post is pending for approval.",
+        )
+    }
+
     pub fn post_archived() -> Self {
         ApiError::new(
             StatusCode::NOT_ACCEPTABLE,
@@ -415,6 +531,78 @@ impl ApiError {
         )
     }
 
+    pub fn no_poll_found() -> Self {
+        ApiError::new(
+            StatusCode::NOT_FOUND,
+            DetailErrorCode::PollNotFound,
+            "Please ensure poll is exists.",
+        )
+    }
+
+    pub fn poll_closed() -> Self {
+        ApiError::new(
+            StatusCode::NOT_ACCEPTABLE,
+            DetailErrorCode::PollClosed,
+            "Poll is closed.",
+        )
+    }
+
+    pub fn illegal_poll(msg: &str) -> Self {
+        ApiError::new(
+            StatusCode::NOT_ACCEPTABLE,
+            DetailErrorCode::IllegalPoll,
+            msg,
+        )
+    }
+
+    pub fn no_bookmark_found() -> Self {
+        ApiError::new(
+            StatusCode::NOT_FOUND,
+            DetailErrorCode::BookmarkNotFound,
+            "Please ensure bookmark is exists.",
+        )
+    }
+
+    pub fn illegal_bookmark(msg: &str) -> Self {
+        ApiError::new(
+            StatusCode::NOT_ACCEPTABLE,
+            DetailErrorCode::IllegalBookmark,
+            msg,
+        )
+    }
+
+    pub fn reaction_not_supported() -> Self {
+        ApiError::new(
+            StatusCode::NOT_ACCEPTABLE,
+            DetailErrorCode::ReactionNotSupported,
+            "Reaction is not supported.",
+        )
+    }
+
+    pub fn illegal_vote_report(msg: &str) -> Self {
+        ApiError::new(
+            StatusCode::NOT_ACCEPTABLE,
+            DetailErrorCode::IllegalVoteReport,
+            msg,
+        )
+    }
+
+    pub fn illegal_cursor() -> Self {
+        ApiError::new(
+            StatusCode::BAD_REQUEST,
+            DetailErrorCode::IllegalCursor,
+            "Cursor is illegal.",
+        )
+    }
+
+    pub fn illegal_date_range() -> Self {
+        ApiError::new(
+            StatusCode::BAD_REQUEST,
+            DetailErrorCode::IllegalDateRange,
+            "Start must not be later than end.",
+        )
+    }
+
     pub fn to_err<T>(self) -> Result<T, ApiError> {
         Err(self)
     }
@@ -0,0 +1,146 @@
+mod model;
+
+use actix_web::{delete, get, put, web, HttpResponse};
+
+use crate::{
+    api::{
+        bookmark_controller::model::{DeleteBookmarkQuery, GetBookmarksQuery},
+        util::{GetDatasExtended, GetDatasExtendedBuilder, ListSlice, Verify},
+        SDW,
+    },
+    request_client::RequestClient,
+};
+use bookmark_system::model::{Bookmark, BookmarkFlag, BookmarkFolder, BookmarkToCreate};
+use comment_system::model::CommentStatus;
+
+use super::{
+    api_error::ApiError,
+    util::{check_comment, check_post, WhatToDo},
+};
+
+#[put("/bookmark")]
+pub async fn set_bookmark(
+    s: SDW,
+    body: web::Json<BookmarkToCreate>,
+    client: RequestClient,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    body.verify()?;
+    let user = client.get_user();
+    let mut tx = s.core.begin_unwrap(true).await;
+    match body.flag {
+        BookmarkFlag::TargetPost => {
+            check_post(&s, tx.as_mut(), body.flag_ref_id, user, WhatToDo::None).await?;
+        }
+        BookmarkFlag::TargetComment => {
+            if !s.comment.is_exists(tx.as_mut(), body.flag_ref_id).await? {
+                return ApiError::no_comment_found().to_err();
+            }
+            check_comment(&s, tx.as_mut(), body.flag_ref_id, user, WhatToDo::None).await?;
+            match s.comment.get_status(tx.as_mut(), body.flag_ref_id).await? {
+                CommentStatus::Active => (),
+                CommentStatus::Banned => return ApiError::banned().to_err(),
+                CommentStatus::Deleted => return ApiError::deleted().to_err(),
+            }
+        }
+    }
+    let bookmark = s
+        .bookmark
+        .set_bookmark(tx.as_mut(), client.get_user_unwrap().id, body.into_inner())
+        .await?;
+    tx.commit_unwrap().await;
+    Ok(HttpResponse::Ok().json(bookmark))
+}
+
+#[delete("/bookmark")]
+pub async fn delete_bookmark(
+    s: SDW,
+    query: web::Query<DeleteBookmarkQuery>,
+    client: RequestClient,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    let mut tx = s.core.begin_unwrap(true).await;
+    let removed = s
+        .bookmark
+        .remove_bookmark(
+            tx.as_mut(),
+            query.flag_ref_id,
+            query.flag,
+            client.get_user_unwrap().id,
+        )
+        .await?;
+    tx.commit_unwrap().await;
+    if !removed {
+        return ApiError::no_bookmark_found().to_err();
+    }
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[get("/bookmarks")]
+pub async fn get_bookmarks(
+    s: SDW,
+    query: web::Query<GetBookmarksQuery>,
+    client: RequestClient,
+) -> Result<web::Json<GetDatasExtended<Bookmark>>, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    let limit = query.limit;
+    if limit as usize > s.core.get_config().fetch_limit {
+        return ApiError::fetch_limit().to_err();
+    }
+    let user = client.get_user_unwrap();
+    let folder = query.folder.as_deref();
+    let mut tx = s.core.begin_unwrap(false).await;
+    let items = s
+        .bookmark
+        .get_bookmarks(tx.as_mut(), user.id, query.flag, folder, query.index, limit)
+        .await?;
+    let total = s
+        .bookmark
+        .get_bookmarks_count(tx.as_mut(), user.id, query.flag, folder)
+        .await?;
+
+    let mut builder = GetDatasExtendedBuilder::new(&s);
+    if query.extended {
+        let mut post_ids = Vec::with_capacity(items.len());
+        let mut comment_ids = Vec::with_capacity(items.len());
+        for b in &items {
+            match b.flag {
+                BookmarkFlag::TargetPost => post_ids.push(b.flag_ref_id),
+                BookmarkFlag::TargetComment => comment_ids.push(b.flag_ref_id),
+            }
+        }
+        builder
+            .extend_posts(tx.as_mut(), post_ids)
+            .await?
+            .extend_comments(tx.as_mut(), comment_ids.clone())
+            .await?
+            .extend_comments_quotes(tx.as_mut(), comment_ids, Some(user))
+            .await?;
+    }
+    tx.commit().await?;
+    let data = builder.set_data(ListSlice { items, total }).build();
+    Ok(web::Json(data))
+}
+
+#[get("/bookmark_folders")]
+pub async fn get_bookmark_folders(
+    s: SDW,
+    client: RequestClient,
+) -> Result<web::Json<Vec<BookmarkFolder>>, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    let mut tx = s.core.begin_unwrap(false).await;
+    let folders = s
+        .bookmark
+        .get_folders(tx.as_mut(), client.get_user_unwrap().id)
+        .await?;
+    tx.commit().await?;
+    Ok(web::Json(folders))
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+use bookmark_system::model::{BookmarkFlag, BookmarkToCreate};
+use fofo_utils::usizedb;
+
+use crate::api::{
+    api_error::ApiError,
+    util::{LegalityVerification, Verify},
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteBookmarkQuery {
+    pub flag: BookmarkFlag,
+    pub flag_ref_id: usizedb,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetBookmarksQuery {
+    #[serde(default)]
+    pub index: usizedb,
+    pub limit: usizedb,
+    pub flag: Option<BookmarkFlag>,
+    /// Only bookmarks in folder, all folders if it is None.
+    pub folder: Option<String>,
+    #[serde(default)]
+    pub extended: bool,
+}
+
+impl Verify<Result<(), ApiError>> for BookmarkToCreate {
+    fn verify(&self) -> Result<(), ApiError> {
+        if !self.folder.is_empty() && !LegalityVerification::is_tag(&self.folder) {
+            return ApiError::illegal_bookmark("Folder name is illegal.").to_err();
+        }
+        if !LegalityVerification::is_note(&self.note) {
+            return ApiError::illegal_bookmark("Note is too long.").to_err();
+        }
+        Ok(())
+    }
+}
@@ -1,16 +1,21 @@
 use crate::{
     api::{
         api_error::ApiError,
-        category_controller::model::{GetCategoriesQuery, SetStatusBody},
+        category_controller::model::{
+            GetCategoriesQuery, GetCategoryStatsQuery, ReorderCategoriesBody, SetStatusBody,
+        },
         util::{
-            check_category, GetDatasExtended, GetDatasExtendedBuilder, ListSlice,
-            VerificationTargetWrapper, Verify, WhatToDo,
+            check_category, get_readable_category_ids, GetDatasExtended, GetDatasExtendedBuilder,
+            ListSlice, VerificationTargetWrapper, Verify, WhatToDo, DAILY_STATS_MAX_DAYS,
         },
     },
     request_client::RequestClient,
 };
 use actix_web::{delete, get, post, put, web, HttpResponse};
-use category_system::model::{Category, CategoryToCreate, CategoryToUpdate};
+use category_system::model::{
+    Category, CategorySection, CategorySectionGroup, CategorySectionToCreate, CategoryToCreate,
+    CategoryToUpdate, GetCategoriesSort,
+};
 use fofo_utils::usizedb;
 use storage::object_marker::model::ObjectFlag;
 
@@ -33,6 +38,52 @@ pub async fn get_category(
     Ok(HttpResponse::Ok().json(v))
 }
 
+#[put("/category_read/{id}")]
+pub async fn mark_category_read(
+    s: SDW,
+    path: web::Path<(usizedb,)>,
+    client: RequestClient,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    let (cid,) = path.into_inner();
+    let user = client.get_user();
+    let mut tx = s.core.begin_unwrap(false).await;
+    check_category(&s, tx.as_mut(), cid, user, WhatToDo::None).await?;
+    tx.commit().await?;
+    s.read
+        .mark_category_read(cid, client.get_user_unwrap().id)
+        .await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[get("/category_stats/{id}")]
+pub async fn get_category_stats(
+    s: SDW,
+    path: web::Path<(usizedb,)>,
+    query: web::Query<GetCategoryStatsQuery>,
+    client: RequestClient,
+) -> Result<HttpResponse, ApiError> {
+    if query.start > query.end {
+        return ApiError::illegal_date_range().to_err();
+    }
+    let (cid,) = path.into_inner();
+    let mut tx = s.core.begin_unwrap(false).await;
+    check_category(&s, tx.as_mut(), cid, client.get_user(), WhatToDo::None).await?;
+    let stats = s
+        .leaderboard
+        .get_category_daily_stats(
+            tx.as_mut(),
+            cid,
+            query.start,
+            query.end,
+            DAILY_STATS_MAX_DAYS,
+        )
+        .await?;
+    Ok(HttpResponse::Ok().json(stats))
+}
+
 #[get("/categories")]
 pub async fn get_categories(
     s: SDW,
@@ -45,18 +96,46 @@ pub async fn get_categories(
     if limit as usize > s.core.get_config().fetch_limit {
         return ApiError::fetch_limit().to_err();
     }
+    let grouped = query.grouped;
+    let query = query.into_inner();
+    let sort = if grouped {
+        GetCategoriesSort::Position
+    } else {
+        query.sort
+    };
     let mut tx = s.core.begin_unwrap(false).await;
     let items = s
         .category
-        .get_categories(
-            tx.as_mut(),
-            query.index as _,
-            query.limit as _,
-            query.into_inner().sort,
-            desc,
-        )
+        .get_categories(tx.as_mut(), query.index as _, query.limit as _, sort, desc)
         .await?;
     let total = s.category.get_count(tx.as_mut(), index, limit).await?;
+    let category_sections = if grouped {
+        let sections = s.category.get_sections(tx.as_mut()).await?;
+        let mut groups: Vec<CategorySectionGroup> = Vec::new();
+        for c in &items {
+            match groups.last_mut() {
+                Some(group) if group.section.id == c.section_id => group.category_ids.push(c.id),
+                _ => {
+                    let section = sections
+                        .iter()
+                        .find(|section| section.id == c.section_id)
+                        .cloned()
+                        .unwrap_or(CategorySection {
+                            id: 0,
+                            title: String::new(),
+                            position: 0,
+                        });
+                    groups.push(CategorySectionGroup {
+                        section,
+                        category_ids: vec![c.id],
+                    });
+                }
+            }
+        }
+        Some(groups)
+    } else {
+        None
+    };
     let mut builder = GetDatasExtendedBuilder::new(&s);
     if extended {
         for c in &items {
@@ -68,14 +147,32 @@ pub async fn get_categories(
                 .await?;
         }
     }
-    let data = builder.set_data(ListSlice { items, total }).build();
+    let data = builder
+        .set_data(ListSlice { items, total })
+        .set_category_sections(category_sections)
+        .build();
     Ok(web::Json(data))
 }
 
+#[get("/category_tree")]
+pub async fn get_category_tree(s: SDW, client: RequestClient) -> Result<HttpResponse, ApiError> {
+    let mut tx = s.core.begin_unwrap(false).await;
+    let visible_ids = get_readable_category_ids(&s, tx.as_mut(), client.get_user())
+        .await?
+        .into_iter()
+        .collect();
+    let tree = s
+        .category
+        .get_category_tree(tx.as_mut(), &visible_ids)
+        .await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(tree))
+}
+
 #[post("/category")]
 pub async fn create_category(
     s: SDW,
-    to_create: web::Json<VerificationTargetWrapper<CategoryToCreate>>,
+    mut to_create: web::Json<VerificationTargetWrapper<CategoryToCreate>>,
     client: RequestClient,
 ) -> Result<HttpResponse, ApiError> {
     if !client.is_logined() {
@@ -86,12 +183,32 @@ pub async fn create_category(
     }
     let mut tx = s.core.begin_unwrap(false).await;
     to_create.verify()?;
+    let tar = &mut to_create.target;
+    tar.description = tar
+        .description_content_type
+        .sanitize(std::mem::take(&mut tar.description));
     if s.category
         .is_exists_duplicate_title(tx.as_mut(), &to_create.target.title, None)
         .await?
     {
         return ApiError::unique_category_required().to_err();
     }
+    if !s
+        .category
+        .is_legal_parent(tx.as_mut(), None, to_create.target.parent_id)
+        .await?
+    {
+        return ApiError::illegal_category_parent().to_err();
+    }
+    let section_id = to_create.target.section_id;
+    if section_id > 0
+        && !s
+            .category
+            .is_section_exists(tx.as_mut(), section_id)
+            .await?
+    {
+        return ApiError::no_category_section_found().to_err();
+    }
     for id in &to_create.target.group_ids {
         if s.group.is_exists(tx.as_mut(), id.to_owned()).await? {
             return ApiError::no_group_found().to_err();
@@ -135,6 +252,7 @@ pub async fn delete_category(s: SDW, path: web::Path<(u32,)>) -> Result<HttpResp
     let mut tx = s.core.begin_unwrap(true).await;
     if s.category.delete_category(tx.as_mut(), cid).await? {
         tx.commit().await.expect("Commit failed!");
+        s.category.invalidate_all_cache();
         Ok(HttpResponse::Ok().finish())
     } else {
         ApiError::no_category_found().to_err()
@@ -172,7 +290,7 @@ pub async fn set_category_status(
 pub async fn update_category(
     s: SDW,
     path: web::Path<(u32,)>,
-    to_update: web::Json<VerificationTargetWrapper<CategoryToUpdate>>,
+    mut to_update: web::Json<VerificationTargetWrapper<CategoryToUpdate>>,
     client: RequestClient,
 ) -> Result<HttpResponse, ApiError> {
     if !client.is_logined() {
@@ -183,6 +301,10 @@ pub async fn update_category(
     }
 
     to_update.verify()?;
+    let tar = &mut to_update.target;
+    tar.description = tar
+        .description_content_type
+        .sanitize(std::mem::take(&mut tar.description));
     let (id,) = path.into_inner();
     let mut tx = s.core.begin_unwrap(false).await;
     if !s.category.is_exists(tx.as_mut(), id).await? {
@@ -194,6 +316,22 @@ pub async fn update_category(
     {
         return ApiError::unique_category_required().to_err();
     }
+    if !s
+        .category
+        .is_legal_parent(tx.as_mut(), Some(id), to_update.target.parent_id)
+        .await?
+    {
+        return ApiError::illegal_category_parent().to_err();
+    }
+    let section_id = to_update.target.section_id;
+    if section_id > 0
+        && !s
+            .category
+            .is_section_exists(tx.as_mut(), section_id)
+            .await?
+    {
+        return ApiError::no_category_section_found().to_err();
+    }
 
     for id in &to_update.target.group_ids {
         if s.group.is_exists(tx.as_mut(), id.to_owned()).await? {
@@ -250,8 +388,132 @@ pub async fn update_category(
     {
         Some(c) => {
             tx.commit_unwrap().await;
+            s.category.invalidate_all_cache();
             Ok(HttpResponse::Ok().json(c))
         }
         None => ApiError::unique_category_required().to_err(),
     }
 }
+
+#[get("/category_sections")]
+pub async fn get_category_sections(s: SDW) -> Result<HttpResponse, ApiError> {
+    let mut tx = s.core.begin_unwrap(false).await;
+    let sections = s.category.get_sections(tx.as_mut()).await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(sections))
+}
+
+#[post("/category_section")]
+pub async fn create_category_section(
+    s: SDW,
+    to_create: web::Json<CategorySectionToCreate>,
+    client: RequestClient,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    if !client.get_user_unwrap().is_admin() {
+        return ApiError::only_admin().to_err();
+    }
+    to_create.verify()?;
+    let mut tx = s.core.begin_unwrap(true).await;
+    let section = s
+        .category
+        .create_section(tx.as_mut(), &to_create.title)
+        .await?;
+    tx.commit_unwrap().await;
+    Ok(HttpResponse::Ok().json(section))
+}
+
+#[put("/category_section/{id}")]
+pub async fn update_category_section(
+    s: SDW,
+    path: web::Path<(usizedb,)>,
+    to_update: web::Json<CategorySectionToCreate>,
+    client: RequestClient,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    if !client.get_user_unwrap().is_admin() {
+        return ApiError::only_admin().to_err();
+    }
+    to_update.verify()?;
+    let (id,) = path.into_inner();
+    let mut tx = s.core.begin_unwrap(true).await;
+    if s.category
+        .update_section(tx.as_mut(), id, &to_update.title)
+        .await?
+    {
+        tx.commit_unwrap().await;
+        Ok(HttpResponse::Ok().finish())
+    } else {
+        ApiError::no_category_section_found().to_err()
+    }
+}
+
+#[delete("/category_section/{id}")]
+pub async fn delete_category_section(
+    s: SDW,
+    path: web::Path<(usizedb,)>,
+    client: RequestClient,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    if !client.get_user_unwrap().is_admin() {
+        return ApiError::only_admin().to_err();
+    }
+    let (id,) = path.into_inner();
+    let mut tx = s.core.begin_unwrap(true).await;
+    if s.category.delete_section(tx.as_mut(), id).await? {
+        tx.commit_unwrap().await;
+        s.category.invalidate_all_cache();
+        Ok(HttpResponse::Ok().finish())
+    } else {
+        ApiError::no_category_section_found().to_err()
+    }
+}
+
+#[put("/category_order")]
+pub async fn reorder_categories(
+    s: SDW,
+    body: web::Json<ReorderCategoriesBody>,
+    client: RequestClient,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    if !client.get_user_unwrap().is_admin() {
+        return ApiError::only_admin().to_err();
+    }
+    let mut tx = s.core.begin_unwrap(true).await;
+    for section in &body.sections {
+        if !s
+            .category
+            .is_section_exists(tx.as_mut(), section.id)
+            .await?
+        {
+            return ApiError::no_category_section_found().to_err();
+        }
+    }
+    for category in &body.categories {
+        if !s.category.is_exists(tx.as_mut(), category.id).await? {
+            return ApiError::no_category_found().to_err();
+        }
+        if category.section_id > 0
+            && !s
+                .category
+                .is_section_exists(tx.as_mut(), category.section_id)
+                .await?
+        {
+            return ApiError::no_category_section_found().to_err();
+        }
+    }
+    s.category
+        .reorder(tx.as_mut(), &body.sections, &body.categories)
+        .await?;
+    tx.commit_unwrap().await;
+    s.category.invalidate_all_cache();
+    Ok(HttpResponse::Ok().finish())
+}
@@ -2,7 +2,8 @@ use fofo_utils::usizedb;
 use serde::{Deserialize, Serialize};
 
 use category_system::model::{
-    CategoryStatus, CategoryToCreate, CategoryToUpdate, GetCategoriesSort,
+    CategoryPosition, CategorySectionToCreate, CategoryStatus, CategoryToCreate,
+    CategoryToUpdate, GetCategoriesSort, SectionPosition,
 };
 use crate::api::{
     api_error::ApiError,
@@ -17,6 +18,9 @@ pub struct GetCategoriesQuery {
     pub desc: bool,
     #[serde(default = "GetCategoriesQuery::default_extended")]
     pub extended: bool,
+    /// Group categories by section, categories are ordered by position if it is true.
+    #[serde(default)]
+    pub grouped: bool,
 }
 
 impl GetCategoriesQuery {
@@ -25,11 +29,36 @@ impl GetCategoriesQuery {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetCategoryStatsQuery {
+    /// Start timestamp, include the day of it.
+    pub start: usizedb,
+    /// End timestamp.
+    pub end: usizedb,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SetStatusBody {
     pub status: CategoryStatus,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReorderCategoriesBody {
+    #[serde(default)]
+    pub sections: Vec<SectionPosition>,
+    #[serde(default)]
+    pub categories: Vec<CategoryPosition>,
+}
+
+impl Verify<Result<(), ApiError>> for CategorySectionToCreate {
+    fn verify(&self) -> Result<(), ApiError> {
+        if !LegalityVerification::is_title(&self.title) {
+            return ApiError::illegal_title().to_err();
+        }
+        Ok(())
+    }
+}
+
 macro_rules! impl_verify {
     ($($t:ty),+) => {
         $(impl Verify<Result<(), ApiError>> for $t {
@@ -39,7 +68,7 @@ macro_rules! impl_verify {
                     return ApiError::illegal_title().to_err()
                 }
 
-                if !LegalityVerification::is_content(&c.description) {
+                if !LegalityVerification::is_content_of(&c.description, c.description_content_type) {
                     return ApiError::illegal_content().to_err()
                 }
                 Ok(())
@@ -1,18 +1,24 @@
 use crate::{
     api::{
-        comment_controller::model::{GetCommentsQuery, SetStatusBody},
+        comment_controller::model::{
+            GetCommentTreeQuery, GetCommentsQuery, SetLockBody, SetStatusBody,
+        },
         util::{
-            can_manage_comment, check_comment, check_post, check_user, GetDatasExtended,
-            GetDatasExtendedBuilder, ListSlice, VerificationTargetWrapper, WhatToDo,
+            can_manage_comment, check_comment, check_post, check_quotes, check_user,
+            notify_mentions, notify_quotes, GetDatasExtended, GetDatasExtendedBuilder,
+            LegalityVerification, ListSlice, VerificationTargetWrapper, Verify, WhatToDo,
         },
         SDW,
     },
     request_client::RequestClient,
 };
-use actix_web::{get, post, put, web, HttpResponse};
-use comment_system::model::{CommentInfo, CommentToCreate, CommentToUpdate};
-use fofo_utils::usizedb;
-use notification_system::model::{UserNotificationArguments, UserNotificationType};
+use actix_web::{delete, get, post, put, web, HttpResponse};
+use comment_system::model::{
+    CommentInfo, CommentStatus, CommentToCreate, CommentToUpdate, CommentTreeNode,
+    CommentTreeOptions,
+};
+use fofo_utils::{cursor::Cursor, usizedb};
+use notification_system::model::{MentionFlag, UserNotificationArguments, UserNotificationType};
 
 use super::api_error::ApiError;
 
@@ -52,6 +58,10 @@ pub async fn get_comments(
     let desc = query.desc;
     let extended = query.extended;
     let top_order_enable = query.top_order_enable;
+    let cursor = match &query.cursor {
+        Some(token) => Some(Cursor::decode(token).ok_or_else(ApiError::illegal_cursor)?),
+        None => None,
+    };
     let sort = query.into_inner().sort;
 
     let post = check_post(&s, tx.as_mut(), post_id, user, WhatToDo::None).await?;
@@ -66,9 +76,16 @@ pub async fn get_comments(
             limit,
             desc,
             top_order_enable,
+            cursor,
         )
         .await?;
     let total = post.total_comment_post;
+    let next_cursor = match items.last() {
+        Some(last) if items.len() == limit as usize => {
+            Some(s.comment.get_cursor(last, sort).encode())
+        }
+        _ => None,
+    };
 
     let mut builder = GetDatasExtendedBuilder::new(&s);
     if extended {
@@ -79,15 +96,112 @@ pub async fn get_comments(
                     vec![c.created_by_id, c.last_edit_by_id, c.reply_user_id],
                 )
                 .await?;
-            let comment_ids = items.iter().map(|c| c.id.clone()).collect();
+            let comment_ids: Vec<usizedb> = items.iter().map(|c| c.id.clone()).collect();
             if let Some(user) = user {
                 builder
-                    .extend_comments_like_status(tx.as_mut(), comment_ids, user.id)
+                    .extend_comments_like_status(tx.as_mut(), comment_ids.clone(), user.id)
+                    .await?
+                    .extend_comments_bookmarked(tx.as_mut(), comment_ids, user.id)
                     .await?;
             }
         }
+        let comment_ids: Vec<usizedb> = items.iter().map(|c| c.id).collect();
+        builder
+            .extend_comments_reactions(tx.as_mut(), comment_ids.clone(), user.map(|u| u.id))
+            .await?
+            .extend_comments_quotes(tx.as_mut(), comment_ids, user)
+            .await?;
+    }
+    let data = builder
+        .set_data(ListSlice { items, total })
+        .set_next_cursor(next_cursor)
+        .build();
+    Ok(web::Json(data))
+}
+
+#[get("/comment_tree")]
+pub async fn get_comment_tree(
+    s: SDW,
+    query: web::Query<GetCommentTreeQuery>,
+    client: RequestClient,
+) -> Result<web::Json<GetDatasExtended<CommentTreeNode>>, ApiError> {
+    let user = client.get_user();
+    let mut tx = s.core.begin_unwrap(false).await;
+    let query = query.into_inner();
+    let config = s.core.get_config();
+    if query.breadth as usize > config.fetch_limit
+        || query.depth as usize > config.comment_tree_max_depth
+    {
+        return ApiError::fetch_limit().to_err();
+    }
+    let cursor = match &query.cursor {
+        Some(token) => Some(Cursor::decode(token).ok_or_else(ApiError::illegal_cursor)?),
+        None => None,
+    };
+
+    check_post(&s, tx.as_mut(), query.post_id, user, WhatToDo::None).await?;
+    if query.root_id > 0 {
+        if !s.comment.is_exists(tx.as_mut(), query.root_id).await? {
+            return ApiError::no_comment_found().to_err();
+        }
+        let (_, root) = check_comment(&s, tx.as_mut(), query.root_id, user, WhatToDo::None).await?;
+        if root.post_id != query.post_id {
+            return ApiError::same_post_comment_required().to_err();
+        }
+    }
+    let options = CommentTreeOptions {
+        sort: query.sort,
+        desc: query.desc,
+        top_order_enable: query.top_order_enable,
+        depth: query.depth,
+        breadth: query.breadth,
+    };
+    let items = s
+        .comment
+        .get_comment_tree(tx.as_mut(), query.post_id, query.root_id, options, cursor)
+        .await?;
+    let total = s
+        .comment
+        .get_total_reply(tx.as_mut(), query.post_id, query.root_id)
+        .await?;
+    let next_cursor = match items.last() {
+        Some(last) if items.len() == query.breadth as usize => {
+            Some(s.comment.get_cursor(&last.comment, query.sort).encode())
+        }
+        _ => None,
+    };
+
+    let mut builder = GetDatasExtendedBuilder::new(&s);
+    if query.extended {
+        let mut comments: Vec<&CommentInfo> = Vec::new();
+        let mut stack: Vec<&CommentTreeNode> = items.iter().collect();
+        while let Some(node) = stack.pop() {
+            comments.push(&node.comment);
+            stack.extend(node.replies.iter());
+        }
+        let mut user_ids = Vec::with_capacity(comments.len() * 3);
+        for c in &comments {
+            user_ids.extend([c.created_by_id, c.last_edit_by_id, c.reply_user_id]);
+        }
+        builder.extend_users(tx.as_mut(), user_ids).await?;
+        let comment_ids: Vec<usizedb> = comments.iter().map(|c| c.id).collect();
+        builder
+            .extend_comments_reactions(tx.as_mut(), comment_ids.clone(), user.map(|u| u.id))
+            .await?
+            .extend_comments_quotes(tx.as_mut(), comment_ids.clone(), user)
+            .await?;
+        if let Some(user) = user {
+            builder
+                .extend_comments_like_status(tx.as_mut(), comment_ids.clone(), user.id)
+                .await?
+                .extend_comments_bookmarked(tx.as_mut(), comment_ids, user.id)
+                .await?;
+        }
     }
-    let data = builder.set_data(ListSlice { items, total }).build();
+    let data = builder
+        .set_data(ListSlice { items, total })
+        .set_next_cursor(next_cursor)
+        .build();
     Ok(web::Json(data))
 }
 
@@ -103,6 +217,9 @@ pub async fn create_comment(
     let user = client.get_user();
     let mut tx = s.core.begin_unwrap(false).await;
     check_user(&s, tx.as_mut(), user, WhatToDo::WriteComment).await?;
+    to_create.verify()?;
+    let tar = &mut to_create.target;
+    tar.content = tar.content_type.sanitize(std::mem::take(&mut tar.content));
     let post = check_post(
         &s,
         tx.as_mut(),
@@ -115,7 +232,11 @@ pub async fn create_comment(
     {
         let tar = &mut to_create.target;
         tar.category_id = post.category_id;
-        let max = s.core.get_config().top_index_max as usizedb;
+        let max = s
+            .category
+            .get_effective_policy(tx.as_mut(), post.category_id)
+            .await?
+            .get_top_index_max(&s.core.get_config());
         if tar.top_index > 0 {
             if post.created_by_id != user.unwrap().id
                 && !s
@@ -131,6 +252,11 @@ pub async fn create_comment(
     }
 
     let n_type;
+    let mut lock_reason = if post.locked {
+        Some(post.lock_reason.clone())
+    } else {
+        None
+    };
     if to_create.target.reply_comment_id > 0 {
         let (_, reply_comment) = check_comment(
             &s,
@@ -152,34 +278,110 @@ pub async fn create_comment(
         };
         to_create.target.reply_user_id = reply_comment.created_by_id;
         n_type = UserNotificationType::ReplyComment;
+
+        // locked comment refuses replies of its sub comments too.
+        if lock_reason.is_none() {
+            lock_reason = s
+                .comment
+                .get_lock_reason(tx.as_mut(), reply_comment.id)
+                .await?;
+        }
     } else if to_create.target.parent_id > 0 {
         return ApiError::reply_comment_required().to_err();
     } else {
         to_create.target.reply_user_id = post.created_by_id;
         n_type = UserNotificationType::Comment;
     }
+    if let Some(reason) = lock_reason {
+        if !s
+            .category
+            .can_manage(tx.as_mut(), post.category_id, user)
+            .await?
+        {
+            return ApiError::locked(&reason).to_err();
+        }
+    }
+    let quotes = to_create
+        .target
+        .content_type
+        .get_quotes(&to_create.target.content);
+    let quotes = check_quotes(&s, tx.as_mut(), user, quotes).await?;
     tx.commit().await.unwrap();
 
     let current = user.unwrap();
     let target_user_id = to_create.target.reply_user_id;
+    let mentions = to_create
+        .target
+        .content_type
+        .get_mentions(&to_create.target.content);
+    // all quoted users are new to the created comment.
+    let mut quoted_user_ids = Vec::with_capacity(quotes.len());
+    for (_, _, user_id) in &quotes {
+        if !quoted_user_ids.contains(user_id) {
+            quoted_user_ids.push(*user_id);
+        }
+    }
     let comment = s
         .comment
-        .create_comment(current.id, to_create.into_inner().target)
+        .create_comment(current.id, to_create.into_inner().target, quotes)
         .await?;
     let current_id = current.id;
     let comment_id = comment.id;
+    let (post_id, category_id) = (comment.post_id, comment.category_id);
     tokio::spawn(async move {
+        let mut tx = s.core.begin_unwrap(false).await;
+        let muted = s
+            .notification
+            .is_muted(tx.as_mut(), target_user_id, post_id, category_id)
+            .await
+            .expect("Get subscription failed.");
+        tx.commit_unwrap().await;
+        if !muted {
+            s.notification
+                .create_user_notification(
+                    current_id,
+                    UserNotificationArguments {
+                        ref_id: comment_id,
+                        target_user_id,
+                        n_type,
+                    },
+                )
+                .await
+                .expect("Create notification failed.")
+        }
         s.notification
-            .create_user_notification(
+            .notify_new_comment(
                 current_id,
-                UserNotificationArguments {
-                    ref_id: comment_id,
-                    target_user_id,
-                    n_type,
-                },
+                comment_id,
+                post_id,
+                category_id,
+                n_type == UserNotificationType::Comment,
+                &[target_user_id],
             )
             .await
-            .expect("Create notification failed.")
+            .expect("Create notification failed.");
+        notify_mentions(
+            &s,
+            current_id,
+            MentionFlag::TargetComment,
+            comment_id,
+            post_id,
+            category_id,
+            mentions,
+        )
+        .await
+        .expect("Create notification failed.");
+        notify_quotes(
+            &s,
+            current_id,
+            comment_id,
+            post_id,
+            category_id,
+            quoted_user_ids,
+            &[target_user_id],
+        )
+        .await
+        .expect("Create notification failed.")
     });
     Ok(HttpResponse::Ok().json(comment))
 }
@@ -197,13 +399,92 @@ pub async fn set_comment_status(
 
     can_manage_comment(&s, tx.as_mut(), id, user).await?;
 
-    s.comment
-        .set_status(tx.as_mut(), id, body.into_inner().status)
-        .await?;
+    let status = body.into_inner().status;
+    if status == CommentStatus::Deleted {
+        s.comment
+            .delete_comment(tx.as_mut(), id, client.get_user_unwrap().id)
+            .await?;
+    } else {
+        s.comment.set_status(tx.as_mut(), id, status).await?;
+    }
     tx.commit().await.unwrap();
     Ok(HttpResponse::Ok().finish())
 }
 
+#[delete("/comment/{id}")]
+pub async fn delete_comment(
+    s: SDW,
+    client: RequestClient,
+    path: web::Path<(usizedb,)>,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    let user = client.get_user();
+    let (id,) = path.into_inner();
+    let mut tx = s.core.begin_unwrap(true).await;
+    check_comment(&s, tx.as_mut(), id, user, WhatToDo::DeleteComment).await?;
+    if s.comment
+        .delete_comment(tx.as_mut(), id, client.get_user_unwrap().id)
+        .await?
+    {
+        tx.commit().await.unwrap();
+        Ok(HttpResponse::Ok().finish())
+    } else {
+        ApiError::deleted().to_err()
+    }
+}
+
+#[put("/comment_restore/{id}")]
+pub async fn restore_comment(
+    s: SDW,
+    client: RequestClient,
+    path: web::Path<(usizedb,)>,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    let user = client.get_user();
+    let (id,) = path.into_inner();
+    let mut tx = s.core.begin_unwrap(true).await;
+    can_manage_comment(&s, tx.as_mut(), id, user).await?;
+    if s.comment.restore_comment(tx.as_mut(), id).await? {
+        tx.commit().await.unwrap();
+        Ok(HttpResponse::Ok().finish())
+    } else {
+        ApiError::no_comment_found().to_err()
+    }
+}
+
+#[put("/comment_lock/{id}")]
+pub async fn set_comment_lock(
+    s: SDW,
+    client: RequestClient,
+    path: web::Path<(usizedb,)>,
+    body: web::Json<SetLockBody>,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    let user = client.get_user();
+    let (id,) = path.into_inner();
+    let body = body.into_inner();
+    if !body.reason.is_empty() && !LegalityVerification::is_title(&body.reason) {
+        return ApiError::illegal_content().to_err();
+    }
+    let mut tx = s.core.begin_unwrap(true).await;
+    can_manage_comment(&s, tx.as_mut(), id, user).await?;
+    if s.comment
+        .set_lock(tx.as_mut(), id, body.locked, &body.reason)
+        .await?
+    {
+        tx.commit().await.unwrap();
+        Ok(HttpResponse::Ok().finish())
+    } else {
+        ApiError::no_comment_found().to_err()
+    }
+}
+
 #[put("/comment/{id}")]
 pub async fn update_comment(
     s: SDW,
@@ -217,12 +498,19 @@ pub async fn update_comment(
     let user = client.get_user();
     let mut tx = s.core.begin_unwrap(false).await;
     check_user(&s, tx.as_mut(), user, WhatToDo::WriteComment).await?;
+    to_update.verify()?;
+    let tar = &mut to_update.target;
+    tar.content = tar.content_type.sanitize(std::mem::take(&mut tar.content));
     let (id,) = path.into_inner();
     let (post, _) = check_comment(&s, tx.as_mut(), id, user, WhatToDo::WriteComment).await?;
 
     {
         let tar = &mut to_update.target;
-        let max = s.core.get_config().top_index_max as usizedb;
+        let max = s
+            .category
+            .get_effective_policy(tx.as_mut(), post.category_id)
+            .await?
+            .get_top_index_max(&s.core.get_config());
         if tar.top_index > 0 {
             if post.created_by_id != user.unwrap().id
                 && !s
@@ -236,16 +524,52 @@ pub async fn update_comment(
             }
         }
     }
+    let quotes = to_update
+        .target
+        .content_type
+        .get_quotes(&to_update.target.content);
+    let quotes = check_quotes(&s, tx.as_mut(), user, quotes).await?;
     tx.commit_unwrap().await;
     let mut tx = s.core.begin_unwrap(true).await;
     let current = user.unwrap();
+    let mentions = to_update
+        .target
+        .content_type
+        .get_mentions(&to_update.target.content);
     match s
         .comment
         .update_comment(tx.as_mut(), id, current.id, to_update.into_inner().target)
         .await?
     {
         Some(p) => {
+            let quoted_user_ids = s.comment.set_quotes(tx.as_mut(), id, quotes).await?;
             tx.commit().await.unwrap();
+            let (created_by_id, post_id, category_id) = (current.id, p.post_id, p.category_id);
+            let s = s.clone();
+            tokio::spawn(async move {
+                notify_mentions(
+                    &s,
+                    created_by_id,
+                    MentionFlag::TargetComment,
+                    id,
+                    post_id,
+                    category_id,
+                    mentions,
+                )
+                .await
+                .expect("Create notification failed.");
+                notify_quotes(
+                    &s,
+                    created_by_id,
+                    id,
+                    post_id,
+                    category_id,
+                    quoted_user_ids,
+                    &[],
+                )
+                .await
+                .expect("Create notification failed.")
+            });
             Ok(HttpResponse::Ok().json(p))
         }
         None => ApiError::no_post_found().to_err(),
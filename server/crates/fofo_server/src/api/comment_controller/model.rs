@@ -1,13 +1,21 @@
 use serde::{Deserialize, Serialize};
 
-use comment_system::model::{CommentStatus, GetCommentsSort};
+use comment_system::model::{CommentStatus, CommentToCreate, CommentToUpdate, GetCommentsSort};
 use fofo_utils::usizedb;
 
+use crate::api::{
+    api_error::ApiError,
+    util::{LegalityVerification, VerificationTargetWrapper, Verify},
+};
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GetCommentsQuery {
     pub post_id: usizedb,
     pub parent_id: usizedb,
+    #[serde(default)]
     pub index: usizedb,
+    /// Cursor from `next_cursor` of previous page. Index is ignored if provided.
+    pub cursor: Option<String>,
     pub limit: usizedb,
     pub sort: GetCommentsSort,
     pub desc: bool,
@@ -17,6 +25,25 @@ pub struct GetCommentsQuery {
     pub top_order_enable: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetCommentTreeQuery {
+    pub post_id: usizedb,
+    /// Fetch the replies of this comment, 0 means the comments of post.
+    #[serde(default)]
+    pub root_id: usizedb,
+    /// Cursor from `next_cursor` of the root node. It applies to the first level only.
+    pub cursor: Option<String>,
+    pub depth: usizedb,
+    /// The maximum replies of each comment.
+    pub breadth: usizedb,
+    pub sort: GetCommentsSort,
+    pub desc: bool,
+    #[serde(default)]
+    pub extended: bool,
+    #[serde(default = "GetCommentsQuery::enable")]
+    pub top_order_enable: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GetCommentLikeStatusQuery {
     pub comment_id: usizedb,
@@ -28,8 +55,34 @@ pub struct SetStatusBody {
     pub status: CommentStatus,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetLockBody {
+    pub locked: bool,
+    #[serde(default)]
+    pub reason: String,
+}
+
 impl GetCommentsQuery {
     pub fn enable() -> bool {
         true
     }
 }
+
+macro_rules! impl_verify {
+    ($($t:ty),+) => {
+        $(impl Verify<Result<(), ApiError>> for $t {
+            fn verify(&self)-> Result<(), ApiError> {
+                let c = &self.target;
+                if !LegalityVerification::is_content_of(&c.content, c.content_type) {
+                    return ApiError::illegal_content().to_err()
+                }
+                Ok(())
+            }
+        })*
+    };
+}
+
+impl_verify!(
+    VerificationTargetWrapper<CommentToCreate>,
+    VerificationTargetWrapper<CommentToUpdate>
+);
@@ -0,0 +1,77 @@
+mod model;
+
+use actix_web::{get, web};
+
+use fofo_utils::usizedb;
+use leaderboard_system::model::LeaderboardEntry;
+use post_system::model::PostFilterTime;
+
+use crate::{
+    api::{
+        util::{
+            check_category, get_readable_category_ids, GetDatasExtended, GetDatasExtendedBuilder,
+            ListSlice, WhatToDo,
+        },
+        SDW,
+    },
+    request_client::RequestClient,
+};
+
+use self::model::GetLeaderboardQuery;
+
+use super::api_error::ApiError;
+
+#[get("/leaderboard")]
+pub async fn get_leaderboard(
+    s: SDW,
+    query: web::Query<GetLeaderboardQuery>,
+    client: RequestClient,
+) -> Result<web::Json<GetDatasExtended<LeaderboardEntry>>, ApiError> {
+    let limit = query.limit;
+    if limit as usize > s.core.get_config().fetch_limit {
+        return ApiError::fetch_limit().to_err();
+    }
+    let time = PostFilterTime::from_str(&query.time, query.time_num);
+    let since = if time.is_lifetime() {
+        0
+    } else {
+        time.to_timestamp(true)
+    };
+    let mut tx = s.core.begin_unwrap(false).await;
+    let category_ids = if let Some(category_id) = query.category_id {
+        check_category(
+            &s,
+            tx.as_mut(),
+            category_id,
+            client.get_user(),
+            WhatToDo::None,
+        )
+        .await?;
+        vec![category_id]
+    } else {
+        get_readable_category_ids(&s, tx.as_mut(), client.get_user()).await?
+    };
+    let items = s
+        .leaderboard
+        .get_leaderboard(
+            tx.as_mut(),
+            since,
+            &category_ids,
+            query.sort,
+            query.index,
+            limit,
+        )
+        .await?;
+    let total = s
+        .leaderboard
+        .get_leaderboard_count(tx.as_mut(), since, &category_ids, query.sort)
+        .await?;
+
+    let mut builder = GetDatasExtendedBuilder::new(&s);
+    if query.extended {
+        let user_ids: Vec<usizedb> = items.iter().map(|e| e.user_id).collect();
+        builder.extend_users(tx.as_mut(), user_ids).await?;
+    }
+    let data = builder.set_data(ListSlice { items, total }).build();
+    Ok(web::Json(data))
+}
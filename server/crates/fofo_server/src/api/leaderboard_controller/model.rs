@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+use fofo_utils::usizedb;
+use leaderboard_system::model::LeaderboardSort;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetLeaderboardQuery {
+    pub sort: LeaderboardSort,
+    pub category_id: Option<usizedb>,
+    /// Same as the time filter of posts, such as `week` and `month`.
+    #[serde(default = "GetLeaderboardQuery::default_time")]
+    pub time: String,
+    #[serde(default = "GetLeaderboardQuery::default_time_num")]
+    pub time_num: usizedb,
+    #[serde(default)]
+    pub index: usizedb,
+    pub limit: usizedb,
+    #[serde(default)]
+    pub extended: bool,
+}
+
+impl GetLeaderboardQuery {
+    pub fn default_time() -> String {
+        "lifetime".to_owned()
+    }
+    pub fn default_time_num() -> usizedb {
+        1
+    }
+}
@@ -3,15 +3,22 @@ mod model;
 use actix_web::{get, put, web, HttpResponse};
 
 use crate::{
-    api::{like_controller::model::GetLikeStatusQuery, SDW},
+    api::{
+        like_controller::model::{
+            GetLikeEventsQuery, GetLikeStatusQuery, GetReactionsQuery, GetVoteReportQuery,
+            Reactions,
+        },
+        util::{GetDatasExtended, GetDatasExtendedBuilder, ListSlice},
+        SDW,
+    },
     request_client::RequestClient,
     ServerData,
 };
 use fofo_utils::usizedb;
-use like_system::model::LikeStatusFlag;
+use like_system::model::{LikeEvent, LikeStatusFlag, VoteOrigin, VoteReportOptions};
 use notification_system::model::{UserNotificationArguments, UserNotificationType};
 
-use self::model::{LikeAction, LikeActionBody};
+use self::model::{LikeAction, LikeActionBody, ReactionBody};
 
 use super::{
     api_error::ApiError,
@@ -31,6 +38,44 @@ pub async fn get_like_status(
     Ok(HttpResponse::Ok().json(s))
 }
 
+#[get("/reactions")]
+pub async fn get_reactions(
+    s: SDW,
+    q: web::Query<GetReactionsQuery>,
+    client: RequestClient,
+) -> Result<HttpResponse, ApiError> {
+    let user = client.get_user();
+    let mut tx = s.core.begin_unwrap(false).await;
+    match q.flag {
+        LikeStatusFlag::TargetPost => {
+            check_post(&s, tx.as_mut(), q.flag_ref_id, user, WhatToDo::None).await?;
+        }
+        LikeStatusFlag::TargetComment => {
+            if !s.comment.is_exists(tx.as_mut(), q.flag_ref_id).await? {
+                return ApiError::no_comment_found().to_err();
+            }
+            check_comment(&s, tx.as_mut(), q.flag_ref_id, user, WhatToDo::None).await?;
+        }
+    }
+    let ids = [q.flag_ref_id];
+    let counts = s
+        .like
+        .get_reaction_counts(tx.as_mut(), q.flag, &ids)
+        .await?
+        .remove(&q.flag_ref_id)
+        .unwrap_or_default();
+    let reacted = match user {
+        Some(user) => s
+            .like
+            .get_user_reactions(tx.as_mut(), q.flag, &ids, user.id)
+            .await?
+            .remove(&q.flag_ref_id)
+            .unwrap_or_default(),
+        None => Vec::new(),
+    };
+    Ok(HttpResponse::Ok().json(Reactions { counts, reacted }))
+}
+
 #[put("/like_action/{id}")]
 pub async fn like_action(
     s: SDW,
@@ -76,9 +121,10 @@ pub async fn like_or_dislike(
         }
     };
     tx.commit_unwrap().await;
+    let origin = get_vote_origin(&s, &client, target_user_id);
     let status = s
         .like
-        .set_like_status(id, flag, user.unwrap().id, is_like)
+        .set_like_status(id, flag, user.unwrap().id, is_like, origin)
         .await?;
     if let Some(is_like) = is_like {
         let n_type = match flag {
@@ -114,3 +160,158 @@ pub async fn like_or_dislike(
     }
     Ok(HttpResponse::Ok().json(status))
 }
+
+#[put("/reaction/{id}")]
+pub async fn reaction_action(
+    s: SDW,
+    path: web::Path<(usizedb,)>,
+    body: web::Json<ReactionBody>,
+    client: RequestClient,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    let body = body.into_inner();
+    if !s
+        .core
+        .get_config()
+        .reactions
+        .iter()
+        .any(|r| *r == body.reaction)
+    {
+        return ApiError::reaction_not_supported().to_err();
+    }
+
+    let mut tx = s.core.begin_unwrap(false).await;
+    let w = if body.flag == LikeStatusFlag::TargetPost {
+        WhatToDo::LikePost
+    } else {
+        WhatToDo::LikeComment
+    };
+    let user = client.get_user();
+    check_user(&s, tx.as_mut(), user, w).await?;
+    let (id,) = path.into_inner();
+    let target_user_id = match body.flag {
+        LikeStatusFlag::TargetPost => {
+            let post = check_post(&s, tx.as_mut(), id, user, w).await?;
+            post.created_by_id
+        }
+        LikeStatusFlag::TargetComment => {
+            let (_, comment) = check_comment(&s, tx.as_mut(), id, user, w).await?;
+            comment.created_by_id
+        }
+    };
+    tx.commit_unwrap().await;
+    let origin = get_vote_origin(&s, &client, target_user_id);
+    s.like
+        .set_reaction(
+            id,
+            body.flag,
+            user.unwrap().id,
+            body.reaction,
+            body.reacted,
+            origin,
+        )
+        .await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[get("/like_events")]
+pub async fn get_like_events(
+    s: SDW,
+    q: web::Query<GetLikeEventsQuery>,
+    client: RequestClient,
+) -> Result<web::Json<GetDatasExtended<LikeEvent>>, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    if !client.get_user_unwrap().is_admin() {
+        return ApiError::only_admin().to_err();
+    }
+    if q.limit as usize > s.core.get_config().fetch_limit {
+        return ApiError::fetch_limit().to_err();
+    }
+    let mut tx = s.core.begin_unwrap(false).await;
+    let items = s
+        .like
+        .get_like_events(tx.as_mut(), q.created_by_id, q.index, q.limit)
+        .await?;
+    let total = s
+        .like
+        .get_like_events_count(tx.as_mut(), q.created_by_id)
+        .await?;
+    let data = GetDatasExtendedBuilder::new(&s)
+        .set_data(ListSlice { items, total })
+        .build();
+    Ok(web::Json(data))
+}
+
+#[get("/vote_report")]
+pub async fn get_vote_report(
+    s: SDW,
+    q: web::Query<GetVoteReportQuery>,
+    client: RequestClient,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    if !client.get_user_unwrap().is_admin() {
+        return ApiError::only_admin().to_err();
+    }
+    if q.start > q.end {
+        return ApiError::illegal_date_range().to_err();
+    }
+    let Some(new_account_seconds) = q.new_account_days.checked_mul(86400) else {
+        return ApiError::illegal_vote_report("New account days is too large.").to_err();
+    };
+    let options = VoteReportOptions {
+        start: q.start,
+        end: q.end,
+        min_votes: q.min_votes,
+        new_account_seconds,
+        burst_seconds: q.burst_seconds.max(1),
+    };
+    let limit = s.core.get_config().fetch_limit as usizedb;
+    let mut tx = s.core.begin_unwrap(false).await;
+    let report = s.like.get_vote_report(tx.as_mut(), options, limit).await?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+#[put("/void_votes/{user_id}")]
+pub async fn void_votes(
+    s: SDW,
+    path: web::Path<(usizedb,)>,
+    client: RequestClient,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    if !client.get_user_unwrap().is_admin() {
+        return ApiError::only_admin().to_err();
+    }
+    let (user_id,) = path.into_inner();
+    let mut tx = s.core.begin_unwrap(true).await;
+    if !s.user.is_exists(tx.as_mut(), user_id).await? {
+        return ApiError::no_user_found().to_err();
+    }
+    let voided = s.like.void_votes(tx.as_mut(), user_id).await?;
+    tx.commit_unwrap().await;
+    for status in &voided {
+        match status.flag {
+            LikeStatusFlag::TargetPost => s.post.invalidate_cache(status.flag_ref_id).await,
+            LikeStatusFlag::TargetComment => s.comment.invalidate_cache(status.flag_ref_id).await,
+        }
+    }
+    Ok(HttpResponse::Ok().json(voided.len()))
+}
+
+/// Voter ip is hashed with the secret before it is recorded.
+fn get_vote_origin(s: &ServerData, client: &RequestClient, target_user_id: usizedb) -> VoteOrigin {
+    let secret = s.like.get_vote_ip_secret();
+    VoteOrigin {
+        target_user_id,
+        ip_hash: client
+            .get_ip()
+            .map(|ip| fofo_utils::hmac_sha256(secret, ip)),
+    }
+}
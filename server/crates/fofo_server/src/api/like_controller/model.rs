@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use fofo_utils::usizedb;
 use serde::{Serialize, Deserialize};
 use serde_repr::{Serialize_repr, Deserialize_repr};
@@ -12,6 +14,20 @@ pub struct GetLikeStatusQuery {
     pub created_by_id: usizedb,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetReactionsQuery {
+    pub flag: LikeStatusFlag,
+    pub flag_ref_id: usizedb,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Reactions {
+    /// Count of each reaction.
+    pub counts: HashMap<String, usizedb>,
+    /// Reactions of current user.
+    pub reacted: Vec<String>,
+}
+
 #[derive(Serialize_repr, Deserialize_repr, PartialEq, Debug)]
 #[repr(u8)]
 pub enum LikeAction {
@@ -24,4 +40,46 @@ pub enum LikeAction {
 pub struct LikeActionBody {
     pub action: LikeAction,
     pub flag: LikeStatusFlag,
-}
\ No newline at end of file
+}
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReactionBody {
+    pub flag: LikeStatusFlag,
+    pub reaction: String,
+    /// Add the reaction if true, otherwise remove it.
+    pub reacted: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetLikeEventsQuery {
+    pub created_by_id: usizedb,
+    #[serde(default)]
+    pub index: usizedb,
+    pub limit: usizedb,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetVoteReportQuery {
+    pub start: usizedb,
+    pub end: usizedb,
+    /// The minimum votes flagged as suspicious.
+    #[serde(default = "GetVoteReportQuery::default_min_votes")]
+    pub min_votes: usizedb,
+    /// Accounts created in these days before voting are new accounts.
+    #[serde(default = "GetVoteReportQuery::default_new_account_days")]
+    pub new_account_days: usizedb,
+    /// Window of burst votes, in second.
+    #[serde(default = "GetVoteReportQuery::default_burst_seconds")]
+    pub burst_seconds: usizedb,
+}
+
+impl GetVoteReportQuery {
+    pub fn default_min_votes() -> usizedb {
+        5
+    }
+    pub fn default_new_account_days() -> usizedb {
+        7
+    }
+    pub fn default_burst_seconds() -> usizedb {
+        3600
+    }
+}
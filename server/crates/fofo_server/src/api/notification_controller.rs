@@ -1,20 +1,28 @@
 mod model;
 
-use actix_web::{get, web, HttpResponse};
+use actix_web::{get, put, web, HttpResponse};
 
 use crate::{
     api::{
-        notification_controller::model::SetUserNotificationReadedQuery,
+        notification_controller::model::{
+            GetSubscriptionQuery, GetSubscriptionsQuery, SetSubscriptionBody,
+            SetUserNotificationReadedQuery,
+        },
         util::{GetDatasExtended, GetDatasExtendedBuilder, ListSlice},
         SDW,
     },
     request_client::RequestClient,
 };
-use notification_system::model::{UserNotification, UserNotificationType};
+use notification_system::model::{
+    Subscription, SubscriptionFlag, UserNotification, UserNotificationType,
+};
 
 use self::model::GetUserNotificationsQuery;
 
-use super::api_error::ApiError;
+use super::{
+    api_error::ApiError,
+    util::{check_category, check_post, WhatToDo},
+};
 
 #[get("/user_notifications")]
 pub async fn get_user_notifications(
@@ -43,23 +51,35 @@ pub async fn get_user_notifications(
 
     let mut builder = GetDatasExtendedBuilder::new(&s);
     if query.extended {
+        let mut user_ids = Vec::with_capacity(items.len());
+        let mut post_ids = Vec::with_capacity(items.len());
+        let mut comment_ids = Vec::with_capacity(items.len());
         for un in &items {
-            builder
-                .extend_users(tx.as_mut(), vec![un.created_by_id])
-                .await?;
-            let ids = vec![un.ref_id];
+            user_ids.push(un.created_by_id);
             match un.n_type {
                 UserNotificationType::Comment
                 | UserNotificationType::ReplyComment
                 | UserNotificationType::LikeComment
-                | UserNotificationType::DislikeComment => {
-                    builder.extend_comments(tx.as_mut(), ids).await?
-                }
-                UserNotificationType::LikePost | UserNotificationType::DislikePost => {
-                    builder.extend_posts(tx.as_mut(), ids).await?
-                }
-            };
+                | UserNotificationType::DislikeComment
+                | UserNotificationType::NewComment
+                | UserNotificationType::Mention
+                | UserNotificationType::Quote
+                | UserNotificationType::AcceptedAnswer => comment_ids.push(un.ref_id),
+                UserNotificationType::LikePost
+                | UserNotificationType::DislikePost
+                | UserNotificationType::NewPost
+                | UserNotificationType::MentionPost => post_ids.push(un.ref_id),
+            }
         }
+        builder
+            .extend_users(tx.as_mut(), user_ids)
+            .await?
+            .extend_posts(tx.as_mut(), post_ids)
+            .await?
+            .extend_comments(tx.as_mut(), comment_ids.clone())
+            .await?
+            .extend_comments_quotes(tx.as_mut(), comment_ids, Some(user))
+            .await?;
     }
     tx.commit().await?;
     let data = builder.set_data(ListSlice { items, total }).build();
@@ -81,3 +101,108 @@ pub async fn set_user_notification_readed(
         .await?;
     Ok(HttpResponse::Ok().finish())
 }
+
+#[put("/subscription")]
+pub async fn set_subscription(
+    s: SDW,
+    body: web::Json<SetSubscriptionBody>,
+    client: RequestClient,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    let user = client.get_user();
+    let user_id = client.get_user_unwrap().id;
+    let mut tx = s.core.begin_unwrap(true).await;
+    match body.flag {
+        SubscriptionFlag::TargetPost => {
+            check_post(&s, tx.as_mut(), body.flag_ref_id, user, WhatToDo::None).await?;
+        }
+        SubscriptionFlag::TargetCategory => {
+            check_category(&s, tx.as_mut(), body.flag_ref_id, user, WhatToDo::None).await?;
+        }
+    }
+    let subscription = match body.level {
+        Some(level) => Some(
+            s.notification
+                .set_subscription(tx.as_mut(), user_id, body.flag, body.flag_ref_id, level)
+                .await?,
+        ),
+        None => {
+            s.notification
+                .remove_subscription(tx.as_mut(), user_id, body.flag, body.flag_ref_id)
+                .await?;
+            None
+        }
+    };
+    tx.commit_unwrap().await;
+    Ok(HttpResponse::Ok().json(subscription))
+}
+
+#[get("/subscription")]
+pub async fn get_subscription(
+    s: SDW,
+    query: web::Query<GetSubscriptionQuery>,
+    client: RequestClient,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    let mut tx = s.core.begin_unwrap(false).await;
+    let subscription = s
+        .notification
+        .get_subscription(
+            tx.as_mut(),
+            client.get_user_unwrap().id,
+            query.flag,
+            query.flag_ref_id,
+        )
+        .await?;
+    tx.commit().await?;
+    Ok(HttpResponse::Ok().json(subscription))
+}
+
+#[get("/subscriptions")]
+pub async fn get_subscriptions(
+    s: SDW,
+    query: web::Query<GetSubscriptionsQuery>,
+    client: RequestClient,
+) -> Result<web::Json<GetDatasExtended<Subscription>>, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    let limit = query.limit;
+    if limit as usize > s.core.get_config().fetch_limit {
+        return ApiError::fetch_limit().to_err();
+    }
+    let user = client.get_user_unwrap();
+    let mut tx = s.core.begin_unwrap(false).await;
+    let items = s
+        .notification
+        .get_subscriptions(tx.as_mut(), user.id, query.flag, query.index, limit)
+        .await?;
+    let total = s
+        .notification
+        .get_subscription_count(tx.as_mut(), user.id, query.flag)
+        .await?;
+
+    let mut builder = GetDatasExtendedBuilder::new(&s);
+    if query.extended {
+        let mut post_ids = Vec::with_capacity(items.len());
+        let mut category_ids = Vec::with_capacity(items.len());
+        for sub in &items {
+            match sub.flag {
+                SubscriptionFlag::TargetPost => post_ids.push(sub.flag_ref_id),
+                SubscriptionFlag::TargetCategory => category_ids.push(sub.flag_ref_id),
+            }
+        }
+        builder
+            .extend_posts(tx.as_mut(), post_ids)
+            .await?
+            .extend_categories(tx.as_mut(), category_ids)
+            .await?;
+    }
+    tx.commit().await?;
+    let data = builder.set_data(ListSlice { items, total }).build();
+    Ok(web::Json(data))
+}
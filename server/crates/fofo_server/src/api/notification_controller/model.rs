@@ -1,6 +1,7 @@
 use serde::{Serialize, Deserialize};
 
 use fofo_utils::usizedb;
+use notification_system::model::{SubscriptionFlag, SubscriptionLevel};
 
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,4 +18,28 @@ pub struct GetUserNotificationsQuery {
 pub struct SetUserNotificationReadedQuery {
     pub id: usizedb,
     pub readed: bool,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetSubscriptionBody {
+    pub flag: SubscriptionFlag,
+    pub flag_ref_id: usizedb,
+    /// None means unsubscribe.
+    pub level: Option<SubscriptionLevel>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetSubscriptionQuery {
+    pub flag: SubscriptionFlag,
+    pub flag_ref_id: usizedb,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetSubscriptionsQuery {
+    #[serde(default)]
+    pub index: usizedb,
+    pub limit: usizedb,
+    pub flag: Option<SubscriptionFlag>,
+    #[serde(default)]
+    pub extended: bool,
+}
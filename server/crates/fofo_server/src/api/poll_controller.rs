@@ -0,0 +1,79 @@
+mod model;
+
+use actix_web::{get, put, web, HttpResponse};
+use chrono::Utc;
+
+use crate::{
+    api::{poll_controller::model::VotePollBody, SDW},
+    request_client::RequestClient,
+};
+use fofo_utils::usizedb;
+
+use super::{
+    api_error::ApiError,
+    util::{check_post, check_user, WhatToDo},
+};
+
+#[get("/poll/{post_id}")]
+pub async fn get_poll(
+    s: SDW,
+    path: web::Path<(usizedb,)>,
+    client: RequestClient,
+) -> Result<HttpResponse, ApiError> {
+    let (post_id,) = path.into_inner();
+    let user = client.get_user();
+    let mut tx = s.core.begin_unwrap(false).await;
+    check_post(&s, tx.as_mut(), post_id, user, WhatToDo::None).await?;
+    match s
+        .poll
+        .get_poll(tx.as_mut(), post_id, user.map(|u| u.id))
+        .await?
+    {
+        Some(poll) => Ok(HttpResponse::Ok().json(poll)),
+        None => ApiError::no_poll_found().to_err(),
+    }
+}
+
+#[put("/poll_vote/{post_id}")]
+pub async fn vote_poll(
+    s: SDW,
+    path: web::Path<(usizedb,)>,
+    body: web::Json<VotePollBody>,
+    client: RequestClient,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    let (post_id,) = path.into_inner();
+    let user = client.get_user();
+    let mut tx = s.core.begin_unwrap(false).await;
+    check_user(&s, tx.as_mut(), user, WhatToDo::VotePoll).await?;
+    check_post(&s, tx.as_mut(), post_id, user, WhatToDo::VotePoll).await?;
+    let poll = match s
+        .poll
+        .get_poll(tx.as_mut(), post_id, user.map(|u| u.id))
+        .await?
+    {
+        Some(poll) => poll,
+        None => return ApiError::no_poll_found().to_err(),
+    };
+    tx.commit_unwrap().await;
+
+    if poll.is_closed(Utc::now().timestamp() as usizedb) {
+        return ApiError::poll_closed().to_err();
+    }
+    let mut choices = body.into_inner().choices;
+    choices.sort_unstable();
+    choices.dedup();
+    if !poll.multiple && choices.len() > 1 {
+        return ApiError::illegal_poll("Only one choice can be voted.").to_err();
+    }
+    if choices.iter().any(|c| *c as usize >= poll.options.len()) {
+        return ApiError::illegal_poll("Choice is out of options.").to_err();
+    }
+
+    s.poll
+        .vote(poll.id, client.get_user_unwrap().id, choices)
+        .await?;
+    Ok(HttpResponse::Ok().finish())
+}
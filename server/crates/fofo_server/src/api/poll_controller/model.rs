@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+use fofo_utils::usizedb;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VotePollBody {
+    /// Option indexes to vote, empty means withdraw the vote.
+    pub choices: Vec<usizedb>,
+}
@@ -3,19 +3,33 @@ mod model;
 use crate::{
     api::{
         api_error::ApiError,
-        post_controller::model::{GetPostQuery, GetPostsQuery, SetStatusBody},
+        post_controller::model::{
+            GetDailyViewsQuery, GetPendingPostsQuery, GetPostQuery, GetPostsQuery,
+            MarkPostReadBody, MergePostBody, MovePostBody, PostWithPoll, SetAnswerBody,
+            SetLockBody, SetStatusBody,
+        },
         util::{
-            can_manage_post, check_category, check_post, check_user, GetDatasExtended,
-            GetDatasExtendedBuilder, ListSlice, VerificationTargetWrapper, Verify,
+            can_manage_category, can_manage_post, check_category, check_comment, check_post,
+            check_post_policy, check_user, notify_mentions, GetDatasExtended,
+            GetDatasExtendedBuilder, LegalityVerification, ListSlice, VerificationTargetWrapper,
+            Verify, DAILY_STATS_MAX_DAYS,
         },
     },
     request_client::RequestClient,
 };
-use fofo_utils::usizedb;
-use post_system::model::{PostFilterTime, PostInfo, PostToCreate, PostToUpdate};
+use comment_system::model::CommentStatus;
+use fofo_utils::{cursor::Cursor, usizedb};
+use notification_system::model::{
+    MentionFlag, SubscriptionFlag, SubscriptionLevel, UserNotificationArguments,
+    UserNotificationType,
+};
+use post_system::model::{
+    PostFilterTime, PostInfo, PostReadFilter, PostStatus, PostToCreate, PostToSplit, PostToUpdate,
+};
 use storage::object_marker::model::ObjectFlag;
 
-use actix_web::{get, post, put, web, HttpResponse};
+use actix_web::{delete, get, post, put, web, HttpResponse};
+use chrono::Utc;
 
 use super::{util::WhatToDo, SDW};
 
@@ -32,30 +46,49 @@ pub async fn create_post(
     let mut tx = s.core.begin_unwrap(false).await;
     check_user(&s, tx.as_mut(), user, WhatToDo::WritePost).await?;
     to_create.verify()?;
+    let tar = &mut to_create.target;
+    tar.content = tar.content_type.sanitize(std::mem::take(&mut tar.content));
+    let poll = to_create.target.poll.take();
+    if let Some(poll) = &poll {
+        poll.verify()?;
+    }
 
-    check_category(
-        &s,
-        tx.as_mut(),
-        to_create.target.category_id,
-        user,
-        WhatToDo::WritePost,
-    )
-    .await?;
+    let category_id = to_create.target.category_id;
+    check_category(&s, tx.as_mut(), category_id, user, WhatToDo::WritePost).await?;
+    let manage = s
+        .category
+        .can_manage(tx.as_mut(), category_id, user)
+        .await?;
+    let policy = s
+        .category
+        .get_effective_policy(tx.as_mut(), category_id)
+        .await?;
+    {
+        let tar = &to_create.target;
+        check_post_policy(
+            &policy,
+            &tar.title,
+            &tar.content,
+            tar.content_type,
+            &tar.tags,
+        )?;
+    }
+    let slow_mode = !manage && policy.slow_mode_seconds > 0;
 
     {
         let tar = &mut to_create.target;
-        let max = s.core.get_config().top_index_max as usizedb;
+        let max = policy.get_top_index_max(&s.core.get_config());
         if tar.top_index > 0 {
-            if !s
-                .category
-                .can_manage(tx.as_mut(), tar.category_id, user)
-                .await?
-            {
+            if !manage {
                 tar.top_index = 0;
             } else if !user.unwrap().is_admin() && tar.top_index > max {
                 tar.top_index = max;
             }
         }
+        if !policy.is_custom_post_cover_supported(&s.core.get_config()) {
+            tar.cover_url = None;
+        }
+        tar.pending = policy.approval_required && !manage;
     }
     tx.commit().await.unwrap();
 
@@ -67,11 +100,78 @@ pub async fn create_post(
         to_create.target.cover_url = cover_url
     }
 
-    let post = s
-        .post
-        .create_post(user.unwrap().id, to_create.into_inner().target)
+    let mentions = to_create
+        .target
+        .content_type
+        .get_mentions(&to_create.target.content);
+    let user_id = user.unwrap().id;
+    let (post, mut tx) = if slow_mode || poll.is_some() {
+        // the post is inserted in the transaction of the slow mode check and the poll,
+        // so concurrent posts of user can't both pass and a post is never left without its poll.
+        let mut tx = s.core.begin_unwrap(true).await;
+        if slow_mode {
+            let last_post_at = s
+                .post
+                .get_last_post_at(tx.as_mut(), user_id, category_id)
+                .await?;
+            let next_post_at = last_post_at + policy.slow_mode_seconds;
+            let now = Utc::now().timestamp() as usizedb;
+            if next_post_at > now {
+                return ApiError::slow_mode(next_post_at - now).to_err();
+            }
+        }
+        let post = s
+            .post
+            .insert_post(tx.as_mut(), user_id, to_create.into_inner().target)
+            .await?;
+        (post, tx)
+    } else {
+        let post = s
+            .post
+            .create_post(user_id, to_create.into_inner().target)
+            .await?;
+        (post, s.core.begin_unwrap(true).await)
+    };
+    let poll = match poll {
+        Some(poll) => Some(s.poll.create_poll(tx.as_mut(), post.id, poll).await?),
+        None => None,
+    };
+    // authors watch their own posts.
+    s.notification
+        .set_subscription(
+            tx.as_mut(),
+            post.created_by_id,
+            SubscriptionFlag::TargetPost,
+            post.id,
+            SubscriptionLevel::Watching,
+        )
         .await?;
-    Ok(HttpResponse::Ok().json(post))
+    tx.commit_unwrap().await;
+
+    if post.status == PostStatus::Pending {
+        // notify after approved.
+        return Ok(HttpResponse::Ok().json(PostWithPoll { post, poll }));
+    }
+    let (created_by_id, post_id, category_id) = (post.created_by_id, post.id, post.category_id);
+    let s = s.clone();
+    tokio::spawn(async move {
+        s.notification
+            .notify_new_post(created_by_id, post_id, category_id)
+            .await
+            .expect("Create notification failed.");
+        notify_mentions(
+            &s,
+            created_by_id,
+            MentionFlag::TargetPost,
+            post_id,
+            post_id,
+            category_id,
+            mentions,
+        )
+        .await
+        .expect("Create notification failed.")
+    });
+    Ok(HttpResponse::Ok().json(PostWithPoll { post, poll }))
 }
 
 #[put("/post/{id}")]
@@ -88,12 +188,36 @@ pub async fn update_post(
     let mut tx = s.core.begin_unwrap(false).await;
     check_user(&s, tx.as_mut(), client.get_user(), WhatToDo::WritePost).await?;
     to_update.verify()?;
+    let tar = &mut to_update.target;
+    tar.content = tar.content_type.sanitize(std::mem::take(&mut tar.content));
     let (pid,) = path.into_inner();
     let post = check_post(&s, tx.as_mut(), pid, user, WhatToDo::WritePost).await?;
+    let move_to = match to_update.target.category_id {
+        Some(category_id) if category_id != post.category_id => {
+            can_manage_post(&s, tx.as_mut(), pid, user).await?;
+            can_manage_category(&s, tx.as_mut(), category_id, user).await?;
+            Some(category_id)
+        }
+        _ => None,
+    };
+    let policy = s
+        .category
+        .get_effective_policy(tx.as_mut(), move_to.unwrap_or(post.category_id))
+        .await?;
+    {
+        let tar = &to_update.target;
+        check_post_policy(
+            &policy,
+            &tar.title,
+            &tar.content,
+            tar.content_type,
+            &tar.tags,
+        )?;
+    }
 
     {
         let tar = &mut to_update.target;
-        let max = s.core.get_config().top_index_max as usizedb;
+        let max = policy.get_top_index_max(&s.core.get_config());
         if tar.top_index > 0 {
             if !s
                 .category
@@ -105,6 +229,9 @@ pub async fn update_post(
                 tar.top_index = max;
             }
         }
+        if !policy.is_custom_post_cover_supported(&s.core.get_config()) {
+            tar.cover_url = post.cover_url.clone();
+        }
     }
     tx.commit_unwrap().await;
 
@@ -149,6 +276,13 @@ pub async fn update_post(
     }
 
     let mut tx = s.core.begin_unwrap(true).await;
+    if let Some(category_id) = move_to {
+        s.post.move_post(tx.as_mut(), pid, category_id).await?;
+    }
+    let mentions = to_update
+        .target
+        .content_type
+        .get_mentions(&to_update.target.content);
     match s
         .post
         .update_post(
@@ -161,6 +295,21 @@ pub async fn update_post(
     {
         Some(p) => {
             tx.commit().await.unwrap();
+            let (created_by_id, category_id) = (user.unwrap().id, p.category_id);
+            let s = s.clone();
+            tokio::spawn(async move {
+                notify_mentions(
+                    &s,
+                    created_by_id,
+                    MentionFlag::TargetPost,
+                    pid,
+                    pid,
+                    category_id,
+                    mentions,
+                )
+                .await
+                .expect("Create notification failed.")
+            });
             Ok(HttpResponse::Ok().json(p))
         }
         None => ApiError::no_post_found().to_err(),
@@ -180,6 +329,15 @@ pub async fn get_postlinks_with_algorithm(
     }
     let extended = query.extended;
     let time = PostFilterTime::from_str(&query.time, query.time_num);
+    let cursor = match &query.cursor {
+        Some(token) => Some(Cursor::decode(token).ok_or_else(ApiError::illegal_cursor)?),
+        None => None,
+    };
+    let read_filter = match (query.read_filter, client.get_user()) {
+        (PostReadFilter::All, _) => None,
+        (filter, Some(user)) => Some((filter, user.id)),
+        (_, None) => return ApiError::login_required().to_err(),
+    };
     let query = query.into_inner();
     let mut tx = s.core.begin_unwrap(false).await;
     let items = s
@@ -194,8 +352,18 @@ pub async fn get_postlinks_with_algorithm(
             query.created_by_id,
             query.distinct,
             query.top_order_enable,
+            cursor,
+            read_filter,
+            query.solved,
+            query.include_descendants,
         )
         .await?;
+    let next_cursor = match items.last() {
+        Some(last) if items.len() == limit as usize => {
+            Some(s.post.get_cursor(last, query.sort).encode())
+        }
+        _ => None,
+    };
     let total = s
         .post
         .get_all_post_count(
@@ -205,10 +373,19 @@ pub async fn get_postlinks_with_algorithm(
             query.created_by_id,
             query.distinct,
             query.top_order_enable,
+            read_filter,
+            query.solved,
+            query.include_descendants,
         )
         .await?;
 
     let mut builder = GetDatasExtendedBuilder::new(&s);
+    if let Some(user) = client.get_user() {
+        let post_ids = items.iter().map(|p| p.id).collect();
+        builder
+            .extend_posts_unread_comments(tx.as_mut(), post_ids, user.id)
+            .await?;
+    }
     if extended {
         for p in &items {
             builder
@@ -220,15 +397,30 @@ pub async fn get_postlinks_with_algorithm(
                     vec![p.created_by_id, p.last_edit_by_id, p.last_comment_by_id],
                 )
                 .await?;
-            let post_ids = items.iter().map(|p| p.id.clone()).collect();
+            let post_ids: Vec<usizedb> = items.iter().map(|p| p.id.clone()).collect();
             if let Some(user) = client.get_user() {
                 builder
-                    .extend_posts_like_status(tx.as_mut(), post_ids, user.id)
+                    .extend_posts_like_status(tx.as_mut(), post_ids.clone(), user.id)
+                    .await?
+                    .extend_posts_bookmarked(tx.as_mut(), post_ids, user.id)
                     .await?;
             }
         }
+        let post_ids: Vec<usizedb> = items.iter().map(|p| p.id).collect();
+        builder
+            .extend_posts_reactions(
+                tx.as_mut(),
+                post_ids.clone(),
+                client.get_user().map(|u| u.id),
+            )
+            .await?
+            .extend_polls(tx.as_mut(), post_ids, client.get_user().map(|u| u.id))
+            .await?;
     }
-    let data = builder.set_data(ListSlice { items, total }).build();
+    let data = builder
+        .set_data(ListSlice { items, total })
+        .set_next_cursor(next_cursor)
+        .build();
     Ok(web::Json(data))
 }
 
@@ -243,12 +435,81 @@ pub async fn get_post(
     let mut tx = s.core.begin_unwrap(false).await;
     check_post(&s, tx.as_mut(), pid, client.get_user(), WhatToDo::None).await?;
     let mut post = s.post.get_post(tx.as_mut(), pid, query.full).await?.unwrap();
+    let poll = s
+        .poll
+        .get_poll(tx.as_mut(), pid, client.get_user().map(|u| u.id))
+        .await?;
+    let last_comment_id = match (client.get_user(), post.content.is_some()) {
+        (Some(_), true) => Some(s.read.get_last_comment_id(tx.as_mut(), pid).await?),
+        _ => None,
+    };
     tx.commit().await.unwrap();
+    if let (Some(user), Some(last_comment_id)) = (client.get_user(), last_comment_id) {
+        // open the full post means read all comments in it.
+        s.read.mark_post_read(pid, user.id, last_comment_id).await?;
+    }
     if post.content.is_some() {
-        s.post.increment_views(pid).await?;
-        post.views += 1;
+        // views deduplicated by user id, or hashed ip for guest. Guest without ip is always counted.
+        let viewer = match (client.get_user(), client.get_ip()) {
+            (Some(user), _) => Some(fofo_utils::calc_hash(&format!("user-{}", user.id))),
+            (None, Some(ip)) => Some(fofo_utils::calc_hash(&format!("ip-{ip}"))),
+            (None, None) => None,
+        };
+        if s.post.increment_views(pid, viewer).await? {
+            post.views += 1;
+        }
+    }
+    Ok(HttpResponse::Ok().json(PostWithPoll { post, poll }))
+}
+
+#[get("/post_daily_views/{id}")]
+pub async fn get_post_daily_views(
+    s: SDW,
+    client: RequestClient,
+    query: web::Query<GetDailyViewsQuery>,
+    path: web::Path<(usizedb,)>,
+) -> Result<HttpResponse, ApiError> {
+    if query.start > query.end {
+        return ApiError::illegal_date_range().to_err();
     }
-    Ok(HttpResponse::Ok().json(post))
+    let (pid,) = path.into_inner();
+    let mut tx = s.core.begin_unwrap(false).await;
+    check_post(&s, tx.as_mut(), pid, client.get_user(), WhatToDo::None).await?;
+    let views = s
+        .post
+        .get_daily_views(
+            tx.as_mut(),
+            pid,
+            query.start,
+            query.end,
+            DAILY_STATS_MAX_DAYS,
+        )
+        .await?;
+    Ok(HttpResponse::Ok().json(views))
+}
+
+#[put("/post_read/{id}")]
+pub async fn mark_post_read(
+    s: SDW,
+    client: RequestClient,
+    path: web::Path<(usizedb,)>,
+    body: web::Json<MarkPostReadBody>,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    let (pid,) = path.into_inner();
+    let mut tx = s.core.begin_unwrap(false).await;
+    check_post(&s, tx.as_mut(), pid, client.get_user(), WhatToDo::None).await?;
+    let last_comment_id = match body.last_comment_id {
+        Some(id) => id,
+        None => s.read.get_last_comment_id(tx.as_mut(), pid).await?,
+    };
+    tx.commit().await?;
+    s.read
+        .mark_post_read(pid, client.get_user_unwrap().id, last_comment_id)
+        .await?;
+    Ok(HttpResponse::Ok().finish())
 }
 
 #[put("/post_status/{id}")]
@@ -265,13 +526,345 @@ pub async fn set_post_status(
     let (id,) = path.into_inner();
     let mut tx = s.core.begin_unwrap(true).await;
     can_manage_post(&s, tx.as_mut(), id, user).await?;
+    let status = body.into_inner().status;
+    let updated = if status == PostStatus::Deleted {
+        s.post
+            .delete_post(tx.as_mut(), id, client.get_user_unwrap().id)
+            .await?
+    } else {
+        s.post.set_status(tx.as_mut(), id, status).await?
+    };
+    if updated {
+        tx.commit().await.unwrap();
+        Ok(HttpResponse::Ok().finish())
+    } else {
+        ApiError::no_post_found().to_err()
+    }
+}
+
+#[delete("/post/{id}")]
+pub async fn delete_post(
+    s: SDW,
+    client: RequestClient,
+    path: web::Path<(usizedb,)>,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    let user = client.get_user();
+    let (id,) = path.into_inner();
+    let mut tx = s.core.begin_unwrap(true).await;
+    check_post(&s, tx.as_mut(), id, user, WhatToDo::DeletePost).await?;
     if s.post
-        .set_status(tx.as_mut(), id, body.into_inner().status)
+        .delete_post(tx.as_mut(), id, client.get_user_unwrap().id)
         .await?
     {
         tx.commit().await.unwrap();
         Ok(HttpResponse::Ok().finish())
+    } else {
+        ApiError::deleted().to_err()
+    }
+}
+
+#[put("/post_restore/{id}")]
+pub async fn restore_post(
+    s: SDW,
+    client: RequestClient,
+    path: web::Path<(usizedb,)>,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    let user = client.get_user();
+    let (id,) = path.into_inner();
+    let mut tx = s.core.begin_unwrap(true).await;
+    can_manage_post(&s, tx.as_mut(), id, user).await?;
+    if s.post.restore_post(tx.as_mut(), id).await? {
+        tx.commit().await.unwrap();
+        Ok(HttpResponse::Ok().finish())
     } else {
         ApiError::no_post_found().to_err()
     }
 }
+
+#[put("/post_approve/{id}")]
+pub async fn approve_post(
+    s: SDW,
+    client: RequestClient,
+    path: web::Path<(usizedb,)>,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    let user = client.get_user();
+    let (id,) = path.into_inner();
+    let mut tx = s.core.begin_unwrap(true).await;
+    can_manage_post(&s, tx.as_mut(), id, user).await?;
+    if !s.post.approve_post(tx.as_mut(), id).await? {
+        return ApiError::no_post_found().to_err();
+    }
+    let post = s.post.get_post(tx.as_mut(), id, true).await?;
+    tx.commit().await.unwrap();
+
+    if let Some(post) = post {
+        let mentions = match &post.content {
+            Some(content) => post.content_type.get_mentions(content),
+            None => vec![],
+        };
+        let (created_by_id, category_id) = (post.created_by_id, post.category_id);
+        let s = s.clone();
+        tokio::spawn(async move {
+            s.notification
+                .notify_new_post(created_by_id, id, category_id)
+                .await
+                .expect("Create notification failed.");
+            notify_mentions(
+                &s,
+                created_by_id,
+                MentionFlag::TargetPost,
+                id,
+                id,
+                category_id,
+                mentions,
+            )
+            .await
+            .expect("Create notification failed.")
+        });
+    }
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[get("/pending_posts")]
+pub async fn get_pending_posts(
+    s: SDW,
+    client: RequestClient,
+    query: web::Query<GetPendingPostsQuery>,
+) -> Result<web::Json<GetDatasExtended<PostInfo>>, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    if query.limit as usize > s.core.get_config().fetch_limit {
+        return ApiError::fetch_limit().to_err();
+    }
+    let user = client.get_user();
+    let mut tx = s.core.begin_unwrap(false).await;
+    match query.category_id {
+        Some(category_id) => can_manage_category(&s, tx.as_mut(), category_id, user).await?,
+        None => {
+            if !client.get_user_unwrap().is_admin() {
+                return ApiError::only_admin().to_err();
+            }
+        }
+    }
+    let items = s
+        .post
+        .get_pending_posts(tx.as_mut(), query.category_id, query.index, query.limit)
+        .await?;
+    let total = s
+        .post
+        .get_pending_post_count(tx.as_mut(), query.category_id)
+        .await?;
+
+    let mut builder = GetDatasExtendedBuilder::new(&s);
+    if query.extended {
+        let category_ids = items.iter().map(|p| p.category_id).collect();
+        let user_ids = items.iter().map(|p| p.created_by_id).collect();
+        builder
+            .extend_categories(tx.as_mut(), category_ids)
+            .await?
+            .extend_users(tx.as_mut(), user_ids)
+            .await?;
+    }
+    Ok(web::Json(
+        builder.set_data(ListSlice { items, total }).build(),
+    ))
+}
+
+#[put("/post_lock/{id}")]
+pub async fn set_post_lock(
+    s: SDW,
+    client: RequestClient,
+    path: web::Path<(usizedb,)>,
+    body: web::Json<SetLockBody>,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    let user = client.get_user();
+    let (id,) = path.into_inner();
+    let body = body.into_inner();
+    if !body.reason.is_empty() && !LegalityVerification::is_title(&body.reason) {
+        return ApiError::illegal_content().to_err();
+    }
+    let mut tx = s.core.begin_unwrap(true).await;
+    can_manage_post(&s, tx.as_mut(), id, user).await?;
+    if s.post
+        .set_lock(tx.as_mut(), id, body.locked, &body.reason)
+        .await?
+    {
+        tx.commit().await.unwrap();
+        Ok(HttpResponse::Ok().finish())
+    } else {
+        ApiError::no_post_found().to_err()
+    }
+}
+
+#[put("/post_answer/{id}")]
+pub async fn set_post_answer(
+    s: SDW,
+    client: RequestClient,
+    path: web::Path<(usizedb,)>,
+    body: web::Json<SetAnswerBody>,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    let user = client.get_user();
+    let current = client.get_user_unwrap();
+    let (id,) = path.into_inner();
+    let comment_id = body.into_inner().comment_id;
+    let mut tx = s.core.begin_unwrap(true).await;
+    let post = check_post(&s, tx.as_mut(), id, user, WhatToDo::None).await?;
+    match s
+        .category
+        .get_category(tx.as_mut(), post.category_id)
+        .await?
+    {
+        Some(category) if category.qa_enabled => (),
+        Some(_) => return ApiError::qa_category_required().to_err(),
+        None => return ApiError::no_category_found().to_err(),
+    }
+    // the author or manager can accept answer.
+    if post.created_by_id != current.id {
+        can_manage_post(&s, tx.as_mut(), id, user).await?;
+    }
+    let mut answerer_id = 0;
+    if comment_id > 0 {
+        if !s.comment.is_exists(tx.as_mut(), comment_id).await? {
+            return ApiError::no_comment_found().to_err();
+        }
+        let (_, comment) = check_comment(&s, tx.as_mut(), comment_id, user, WhatToDo::None).await?;
+        match s.comment.get_status(tx.as_mut(), comment_id).await? {
+            CommentStatus::Active => (),
+            CommentStatus::Banned => return ApiError::banned().to_err(),
+            CommentStatus::Deleted => return ApiError::deleted().to_err(),
+        }
+        if comment.post_id != id {
+            return ApiError::same_post_comment_required().to_err();
+        }
+        if comment.reply_comment_id > 0 {
+            return ApiError::top_level_comment_required().to_err();
+        }
+        answerer_id = comment.created_by_id;
+    }
+    if !s
+        .post
+        .set_accepted_comment(tx.as_mut(), id, comment_id)
+        .await?
+    {
+        return ApiError::no_post_found().to_err();
+    }
+    tx.commit().await.unwrap();
+    s.comment.invalidate_post_cache(id).await;
+    if comment_id > 0 && comment_id != post.accepted_comment_id && answerer_id != current.id {
+        let (s, current_id) = (s.clone(), current.id);
+        tokio::spawn(async move {
+            s.notification
+                .create_user_notification(
+                    current_id,
+                    UserNotificationArguments {
+                        ref_id: comment_id,
+                        target_user_id: answerer_id,
+                        n_type: UserNotificationType::AcceptedAnswer,
+                    },
+                )
+                .await
+                .expect("Create notification failed.");
+        });
+    }
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[put("/post_move/{id}")]
+pub async fn move_post(
+    s: SDW,
+    client: RequestClient,
+    path: web::Path<(usizedb,)>,
+    body: web::Json<MovePostBody>,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    let user = client.get_user();
+    let (id,) = path.into_inner();
+    let category_id = body.into_inner().category_id;
+    let mut tx = s.core.begin_unwrap(true).await;
+    can_manage_post(&s, tx.as_mut(), id, user).await?;
+    can_manage_category(&s, tx.as_mut(), category_id, user).await?;
+    s.post.move_post(tx.as_mut(), id, category_id).await?;
+    tx.commit().await.unwrap();
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[put("/post_merge/{id}")]
+pub async fn merge_post(
+    s: SDW,
+    client: RequestClient,
+    path: web::Path<(usizedb,)>,
+    body: web::Json<MergePostBody>,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    let user = client.get_user();
+    let (id,) = path.into_inner();
+    let target_id = body.into_inner().target_post_id;
+    let mut tx = s.core.begin_unwrap(true).await;
+    can_manage_post(&s, tx.as_mut(), id, user).await?;
+    can_manage_post(&s, tx.as_mut(), target_id, user).await?;
+    if s.post
+        .merge_post(tx.as_mut(), id, target_id, user.unwrap().id)
+        .await?
+    {
+        tx.commit().await.unwrap();
+        Ok(HttpResponse::Ok().finish())
+    } else {
+        ApiError::no_post_found().to_err()
+    }
+}
+
+#[post("/post_split/{id}")]
+pub async fn split_post(
+    s: SDW,
+    client: RequestClient,
+    path: web::Path<(usizedb,)>,
+    to_split: web::Json<PostToSplit>,
+) -> Result<HttpResponse, ApiError> {
+    if !client.is_logined() {
+        return ApiError::login_required().to_err();
+    }
+    let user = client.get_user();
+    let (id,) = path.into_inner();
+    to_split.verify()?;
+    let mut tx = s.core.begin_unwrap(true).await;
+    can_manage_post(&s, tx.as_mut(), id, user).await?;
+    can_manage_category(&s, tx.as_mut(), to_split.category_id, user).await?;
+    let policy = s
+        .category
+        .get_effective_policy(tx.as_mut(), to_split.category_id)
+        .await?;
+    if to_split.tags.len() > policy.get_max_tags() as usize {
+        return ApiError::too_many_tags().to_err();
+    }
+    match s
+        .post
+        .split_post(tx.as_mut(), id, to_split.into_inner())
+        .await?
+    {
+        Some(p) => {
+            tx.commit().await.unwrap();
+            Ok(HttpResponse::Ok().json(p))
+        }
+        None => ApiError::no_comment_found().to_err(),
+    }
+}
@@ -3,19 +3,67 @@ use serde::{Deserialize, Serialize};
 use crate::api::util::VerificationTargetWrapper;
 use anyhow::Result;
 use fofo_utils::usizedb;
-use post_system::model::{PostAlgorithmOrder, PostStatus, PostToCreate, PostToUpdate};
+use poll_system::model::{PollInfo, PollToCreate};
+use post_system::model::{
+    PostAlgorithmOrder, PostInfo, PostReadFilter, PostStatus, PostToCreate, PostToSplit,
+    PostToUpdate,
+};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SetStatusBody {
     pub status: PostStatus,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetAnswerBody {
+    /// The accepted comment, 0 means cancel the answer.
+    pub comment_id: usizedb,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetLockBody {
+    pub locked: bool,
+    #[serde(default)]
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostWithPoll {
+    #[serde(flatten)]
+    pub post: PostInfo,
+    pub poll: Option<PollInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MovePostBody {
+    pub category_id: usizedb,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MergePostBody {
+    pub target_post_id: usizedb,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MarkPostReadBody {
+    /// Comments until it are read, all comments if it is None.
+    pub last_comment_id: Option<usizedb>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GetPostQuery {
     #[serde(default = "disabled")]
     pub full: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetDailyViewsQuery {
+    /// Start timestamp, include the day of it.
+    pub start: usizedb,
+    /// End timestamp.
+    pub end: usizedb,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GetPostsQuery {
     pub sort: PostAlgorithmOrder,
@@ -27,12 +75,34 @@ pub struct GetPostsQuery {
     pub time_num: usizedb,
     #[serde(default = "default_time")]
     pub time: String,
+    #[serde(default)]
     pub index: usizedb,
+    /// Cursor from `next_cursor` of previous page. Index is ignored if provided.
+    pub cursor: Option<String>,
     pub limit: usizedb,
     #[serde(default)]
     pub extended: bool,
     #[serde(default)]
     pub top_order_enable: bool,
+    /// Filter by read status of current user, login is required if it isn't `All`.
+    #[serde(default)]
+    pub read_filter: PostReadFilter,
+    /// Filter by accepted answer of Q&A posts.
+    pub solved: Option<bool>,
+    /// Include posts in the descendants of category.
+    #[serde(default)]
+    pub include_descendants: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetPendingPostsQuery {
+    /// Pending posts of all categories if not provided, only admin can do it.
+    pub category_id: Option<usizedb>,
+    #[serde(default)]
+    pub index: usizedb,
+    pub limit: usizedb,
+    #[serde(default)]
+    pub extended: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -71,14 +141,10 @@ macro_rules! impl_verify {
                     return ApiError::illegal_title().to_err()
                 }
 
-                if !LegalityVerification::is_content(&c.content) {
+                if !LegalityVerification::is_content_of(&c.content, c.content_type) {
                     return ApiError::illegal_content().to_err()
                 }
 
-                if c.tags.len() > 9 {
-                    return ApiError::too_many_tags().to_err();
-                }
-
                 for tag in &c.tags {
                     if !LegalityVerification::is_tag(tag) {
                         return ApiError::illegal_tag(tag).to_err();
@@ -94,3 +160,42 @@ impl_verify!(
     VerificationTargetWrapper<PostToCreate>,
     VerificationTargetWrapper<PostToUpdate>
 );
+
+impl crate::api::util::Verify<Result<(), crate::ApiError>> for PostToSplit {
+    fn verify(&self) -> Result<(), crate::ApiError> {
+        use crate::api::util::LegalityVerification;
+        use crate::ApiError;
+        if !LegalityVerification::is_title(&self.title) {
+            return ApiError::illegal_title().to_err();
+        }
+
+        for tag in &self.tags {
+            if !LegalityVerification::is_tag(tag) {
+                return ApiError::illegal_tag(tag).to_err();
+            }
+        }
+        Ok(())
+    }
+}
+
+impl crate::api::util::Verify<Result<(), crate::ApiError>> for PollToCreate {
+    fn verify(&self) -> Result<(), crate::ApiError> {
+        use crate::api::util::LegalityVerification;
+        use crate::ApiError;
+        if self.options.len() < 2 || self.options.len() > 20 {
+            return ApiError::illegal_poll("Poll options must be between 2 and 20.").to_err();
+        }
+
+        for option in &self.options {
+            if !LegalityVerification::is_title(option) {
+                return ApiError::illegal_poll(&format!("The option `{}` is illegal!", option))
+                    .to_err();
+            }
+        }
+
+        if self.close_at > 0 && self.close_at <= chrono::Utc::now().timestamp() as usizedb {
+            return ApiError::illegal_poll("Poll close time must be in the future.").to_err();
+        }
+        Ok(())
+    }
+}
@@ -69,7 +69,17 @@ pub async fn presign_put_url(
     match query.signed_flag {
         model::SignedFlag::UserAvatar => (),
         model::SignedFlag::PostCover => {
-            if !s.core.get_config().custom_post_cover_supported {
+            let supported = match query.category_id {
+                Some(category_id) => {
+                    let mut tx = s.core.begin_unwrap(false).await;
+                    s.category
+                        .get_effective_policy(tx.as_mut(), category_id)
+                        .await?
+                        .is_custom_post_cover_supported(&s.core.get_config())
+                }
+                None => s.core.get_config().custom_post_cover_supported,
+            };
+            if !supported {
                 return ApiError::unsupported_api().to_err();
             }
         }
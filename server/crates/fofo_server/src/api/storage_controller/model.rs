@@ -1,6 +1,7 @@
 use serde::{Serialize, Deserialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
+use fofo_utils::usizedb;
 use storage::object_marker::model::ObjectFlag;
 
 #[derive(Serialize_repr, Deserialize_repr, PartialEq, Debug)]
@@ -15,6 +16,8 @@ pub enum SignedFlag {
 pub struct GetPresignedUrlQuery {
     pub signed_flag: SignedFlag,
     pub filename: String,
+    /// Category of the post, its policy decides whether post cover is supported.
+    pub category_id: Option<usizedb>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -1,17 +1,20 @@
 use std::collections::HashMap;
 
 use anyhow::Result;
-use category_system::model::Category;
+use bookmark_system::model::{Bookmark, BookmarkFlag};
+use category_system::model::{Category, CategoryPolicy, CategorySectionGroup};
 use chrono::Utc;
-use comment_system::model::{CommentBaseInfo, CommentInfo};
+use comment_system::model::{CommentBaseInfo, CommentInfo, CommentQuote, CommentStatus, QuoteFlag};
 use fancy_regex::Regex;
-use fofo_utils::usizedb;
+use fofo_utils::{usizedb, ContentType, QuoteTarget};
 use group_system::model::Group;
 use like_system::model::{
     LikeStatus,
     LikeStatusFlag::{TargetComment, TargetPost},
 };
-use post_system::model::{PostBaseInfo, PostInfo};
+use notification_system::model::{MentionFlag, UserNotificationArguments, UserNotificationType};
+use poll_system::model::PollInfo;
+use post_system::model::{PostBaseInfo, PostInfo, PostStatus};
 use user_system::model::{SafeUserInfo, UserInfo};
 
 use lazy_static::lazy_static;
@@ -24,6 +27,12 @@ use super::{api_error::ApiError, verification_controller::model::VerificationKey
 
 pub struct LegalityVerification;
 
+/// Max chars of the snippet of quoted post or comment.
+const QUOTE_SNIPPET_LEN: usizedb = 200;
+
+/// Max days returned by the daily stats endpoints.
+pub const DAILY_STATS_MAX_DAYS: usizedb = 366;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VerificationTargetWrapper<T> {
     pub target: T,
@@ -53,6 +62,17 @@ impl<'a, T> GetDatasExtendedBuilder<'a, T> {
         self.data_extended.set_data(data);
         self
     }
+    pub fn set_next_cursor(mut self, next_cursor: Option<String>) -> Self {
+        self.data_extended.next_cursor = next_cursor;
+        self
+    }
+    pub fn set_category_sections(
+        mut self,
+        category_sections: Option<Vec<CategorySectionGroup>>,
+    ) -> Self {
+        self.data_extended.category_sections = category_sections;
+        self
+    }
 
     pub async fn extend_posts(
         &mut self,
@@ -224,18 +244,236 @@ impl<'a, T> GetDatasExtendedBuilder<'a, T> {
 
         Ok(self)
     }
+
+    pub async fn extend_posts_reactions(
+        &mut self,
+        tx: &mut SqliteConnection,
+        post_ids: Vec<usizedb>,
+        user_id: Option<usizedb>,
+    ) -> Result<&mut Self> {
+        let counts = self
+            .s
+            .like
+            .get_reaction_counts(tx, TargetPost, &post_ids)
+            .await?;
+        self.data_extended.get_posts_reactions_map().extend(counts);
+        if let Some(user_id) = user_id {
+            let reacted = self
+                .s
+                .like
+                .get_user_reactions(tx, TargetPost, &post_ids, user_id)
+                .await?;
+            self.data_extended
+                .get_posts_user_reactions_map()
+                .extend(reacted);
+        }
+
+        Ok(self)
+    }
+
+    pub async fn extend_comments_reactions(
+        &mut self,
+        tx: &mut SqliteConnection,
+        comment_ids: Vec<usizedb>,
+        user_id: Option<usizedb>,
+    ) -> Result<&mut Self> {
+        let counts = self
+            .s
+            .like
+            .get_reaction_counts(tx, TargetComment, &comment_ids)
+            .await?;
+        self.data_extended
+            .get_comments_reactions_map()
+            .extend(counts);
+        if let Some(user_id) = user_id {
+            let reacted = self
+                .s
+                .like
+                .get_user_reactions(tx, TargetComment, &comment_ids, user_id)
+                .await?;
+            self.data_extended
+                .get_comments_user_reactions_map()
+                .extend(reacted);
+        }
+
+        Ok(self)
+    }
+
+    /// Quotes of comments, quotes of deleted sources are skipped and the snippet is blanked if
+    /// the source is banned or the reader can't read it.
+    pub async fn extend_comments_quotes(
+        &mut self,
+        tx: &mut SqliteConnection,
+        comment_ids: Vec<usizedb>,
+        user: Option<&UserInfo>,
+    ) -> Result<&mut Self> {
+        let quotes = self
+            .s
+            .comment
+            .get_quotes(tx, &comment_ids, QUOTE_SNIPPET_LEN)
+            .await?;
+        // key is (quoted post or not, ref id), value is None if source is deleted.
+        let mut visible: HashMap<(bool, usizedb), Option<bool>> = HashMap::new();
+        let mut user_ids = Vec::with_capacity(quotes.len());
+        for mut quote in quotes {
+            let key = (quote.flag == QuoteFlag::TargetPost, quote.flag_ref_id);
+            let v = match visible.get(&key) {
+                Some(v) => *v,
+                None => {
+                    let v = self.is_quote_visible(tx, &quote, user).await?;
+                    visible.insert(key, v);
+                    v
+                }
+            };
+            match v {
+                None => continue,
+                Some(false) => quote.snippet = String::new(),
+                Some(true) => (),
+            }
+            user_ids.push(quote.quoted_user_id);
+            self.data_extended
+                .get_comment_quotes_map()
+                .entry(quote.comment_id)
+                .or_default()
+                .push(quote);
+        }
+        self.extend_users(tx, user_ids).await?;
+
+        Ok(self)
+    }
+
+    /// None if the source is deleted, otherwise whether the reader can see its content.
+    async fn is_quote_visible(
+        &self,
+        tx: &mut SqliteConnection,
+        quote: &CommentQuote,
+        user: Option<&UserInfo>,
+    ) -> Result<Option<bool>> {
+        let id = quote.flag_ref_id;
+        Ok(match quote.flag {
+            QuoteFlag::TargetPost => match self.s.post.get_status(tx, id).await? {
+                PostStatus::Deleted => None,
+                PostStatus::Banned => Some(false),
+                _ => Some(
+                    check_post(self.s, tx, id, user, WhatToDo::None)
+                        .await
+                        .is_ok(),
+                ),
+            },
+            QuoteFlag::TargetComment => match self.s.comment.get_status(tx, id).await? {
+                CommentStatus::Deleted => None,
+                CommentStatus::Banned => Some(false),
+                CommentStatus::Active => Some(
+                    check_comment(self.s, tx, id, user, WhatToDo::None)
+                        .await
+                        .is_ok(),
+                ),
+            },
+        })
+    }
+
+    pub async fn extend_posts_bookmarked(
+        &mut self,
+        tx: &mut SqliteConnection,
+        post_ids: Vec<usizedb>,
+        user_id: usizedb,
+    ) -> Result<&mut Self> {
+        let bookmarks = self
+            .s
+            .bookmark
+            .get_bookmarks_of_targets(tx, &post_ids, BookmarkFlag::TargetPost, user_id)
+            .await?;
+        let map = self.data_extended.get_posts_bookmarked_map();
+        for v in bookmarks {
+            map.entry(v.flag_ref_id).or_insert(v);
+        }
+
+        Ok(self)
+    }
+
+    pub async fn extend_comments_bookmarked(
+        &mut self,
+        tx: &mut SqliteConnection,
+        comment_ids: Vec<usizedb>,
+        user_id: usizedb,
+    ) -> Result<&mut Self> {
+        let bookmarks = self
+            .s
+            .bookmark
+            .get_bookmarks_of_targets(tx, &comment_ids, BookmarkFlag::TargetComment, user_id)
+            .await?;
+        let map = self.data_extended.get_comments_bookmarked_map();
+        for v in bookmarks {
+            map.entry(v.flag_ref_id).or_insert(v);
+        }
+
+        Ok(self)
+    }
+
+    pub async fn extend_posts_unread_comments(
+        &mut self,
+        tx: &mut SqliteConnection,
+        post_ids: Vec<usizedb>,
+        user_id: usizedb,
+    ) -> Result<&mut Self> {
+        let map = self
+            .s
+            .read
+            .get_unread_comments(tx, &post_ids, user_id)
+            .await?;
+        self.data_extended.get_unread_comments_map().extend(map);
+
+        Ok(self)
+    }
+
+    pub async fn extend_polls(
+        &mut self,
+        tx: &mut SqliteConnection,
+        post_ids: Vec<usizedb>,
+        user_id: Option<usizedb>,
+    ) -> Result<&mut Self> {
+        let post_ids: Vec<usizedb> = post_ids
+            .into_iter()
+            .filter(|id| !self.data_extended.get_polls_map().contains_key(id))
+            .collect();
+        if !post_ids.is_empty() {
+            let polls = self.s.poll.get_polls(tx, &post_ids, user_id).await?;
+            self.data_extended.get_polls_map().extend(polls);
+        }
+
+        Ok(self)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GetDatasExtended<T> {
     pub data: ListSlice<T>,
+    /// Cursor to fetch next page, None if it is the last page.
+    pub next_cursor: Option<String>,
+    /// Categories grouped by section, in the order of sections.
+    pub category_sections: Option<Vec<CategorySectionGroup>>,
     pub posts: Option<HashMap<usizedb, PostInfo>>,
     pub comments: Option<HashMap<usizedb, CommentInfo>>,
+    /// Quotes with snippet of the extended comments, key is comment id.
+    pub comment_quotes: Option<HashMap<usizedb, Vec<CommentQuote>>>,
     pub categories: Option<HashMap<usizedb, Category>>,
     pub groups: Option<HashMap<usizedb, Group>>,
     pub users: Option<HashMap<usizedb, SafeUserInfo>>,
     pub posts_like_status: Option<HashMap<usizedb, LikeStatus>>,
     pub comments_like_status: Option<HashMap<usizedb, LikeStatus>>,
+    /// Count of each reaction, key is post id.
+    pub posts_reactions: Option<HashMap<usizedb, HashMap<String, usizedb>>>,
+    pub comments_reactions: Option<HashMap<usizedb, HashMap<String, usizedb>>>,
+    /// Reactions of current user, key is post id.
+    pub posts_user_reactions: Option<HashMap<usizedb, Vec<String>>>,
+    pub comments_user_reactions: Option<HashMap<usizedb, Vec<String>>>,
+    /// Bookmarks of current user, post is bookmarked if its id is in the map.
+    pub posts_bookmarked: Option<HashMap<usizedb, Bookmark>>,
+    pub comments_bookmarked: Option<HashMap<usizedb, Bookmark>>,
+    /// Count of unread comments, key is post id.
+    pub unread_comments: Option<HashMap<usizedb, usizedb>>,
+    /// key is post id.
+    pub polls: Option<HashMap<usizedb, PollInfo>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -257,13 +495,24 @@ impl<T> GetDatasExtended<T> {
     pub fn empty() -> Self {
         GetDatasExtended {
             data: ListSlice::empty(),
+            next_cursor: None,
+            category_sections: None,
             posts: None,
             comments: None,
+            comment_quotes: None,
             categories: None,
             groups: None,
             users: None,
             posts_like_status: None,
             comments_like_status: None,
+            posts_reactions: None,
+            comments_reactions: None,
+            posts_user_reactions: None,
+            comments_user_reactions: None,
+            posts_bookmarked: None,
+            comments_bookmarked: None,
+            unread_comments: None,
+            polls: None,
         }
     }
 
@@ -312,6 +561,14 @@ impl<T> GetDatasExtended<T> {
         unsafe { self.comments.as_mut().unwrap_unchecked() }
     }
 
+    fn get_comment_quotes_map(&mut self) -> &mut HashMap<usizedb, Vec<CommentQuote>> {
+        if self.comment_quotes.is_none() {
+            self.comment_quotes = Some(HashMap::with_capacity(self.data.items.len()));
+        }
+
+        unsafe { self.comment_quotes.as_mut().unwrap_unchecked() }
+    }
+
     fn get_posts_like_status_map(&mut self) -> &mut HashMap<usizedb, LikeStatus> {
         if self.posts_like_status.is_none() {
             self.posts_like_status = Some(HashMap::with_capacity(self.data.items.len()));
@@ -327,6 +584,70 @@ impl<T> GetDatasExtended<T> {
 
         unsafe { self.comments_like_status.as_mut().unwrap_unchecked() }
     }
+
+    fn get_posts_reactions_map(&mut self) -> &mut HashMap<usizedb, HashMap<String, usizedb>> {
+        if self.posts_reactions.is_none() {
+            self.posts_reactions = Some(HashMap::with_capacity(self.data.items.len()));
+        }
+
+        unsafe { self.posts_reactions.as_mut().unwrap_unchecked() }
+    }
+
+    fn get_comments_reactions_map(&mut self) -> &mut HashMap<usizedb, HashMap<String, usizedb>> {
+        if self.comments_reactions.is_none() {
+            self.comments_reactions = Some(HashMap::with_capacity(self.data.items.len()));
+        }
+
+        unsafe { self.comments_reactions.as_mut().unwrap_unchecked() }
+    }
+
+    fn get_posts_user_reactions_map(&mut self) -> &mut HashMap<usizedb, Vec<String>> {
+        if self.posts_user_reactions.is_none() {
+            self.posts_user_reactions = Some(HashMap::with_capacity(self.data.items.len()));
+        }
+
+        unsafe { self.posts_user_reactions.as_mut().unwrap_unchecked() }
+    }
+
+    fn get_comments_user_reactions_map(&mut self) -> &mut HashMap<usizedb, Vec<String>> {
+        if self.comments_user_reactions.is_none() {
+            self.comments_user_reactions = Some(HashMap::with_capacity(self.data.items.len()));
+        }
+
+        unsafe { self.comments_user_reactions.as_mut().unwrap_unchecked() }
+    }
+
+    fn get_posts_bookmarked_map(&mut self) -> &mut HashMap<usizedb, Bookmark> {
+        if self.posts_bookmarked.is_none() {
+            self.posts_bookmarked = Some(HashMap::with_capacity(self.data.items.len()));
+        }
+
+        unsafe { self.posts_bookmarked.as_mut().unwrap_unchecked() }
+    }
+
+    fn get_comments_bookmarked_map(&mut self) -> &mut HashMap<usizedb, Bookmark> {
+        if self.comments_bookmarked.is_none() {
+            self.comments_bookmarked = Some(HashMap::with_capacity(self.data.items.len()));
+        }
+
+        unsafe { self.comments_bookmarked.as_mut().unwrap_unchecked() }
+    }
+
+    fn get_unread_comments_map(&mut self) -> &mut HashMap<usizedb, usizedb> {
+        if self.unread_comments.is_none() {
+            self.unread_comments = Some(HashMap::with_capacity(self.data.items.len()));
+        }
+
+        unsafe { self.unread_comments.as_mut().unwrap_unchecked() }
+    }
+
+    fn get_polls_map(&mut self) -> &mut HashMap<usizedb, PollInfo> {
+        if self.polls.is_none() {
+            self.polls = Some(HashMap::with_capacity(self.data.items.len()));
+        }
+
+        unsafe { self.polls.as_mut().unwrap_unchecked() }
+    }
 }
 
 lazy_static! {
@@ -358,9 +679,17 @@ impl LegalityVerification {
         v.len() <= 65535
     }
 
+    pub fn is_content_of(v: &str, content_type: ContentType) -> bool {
+        Self::is_content(v) && content_type.is_well_formed(v)
+    }
+
     pub fn is_tag(v: &str) -> bool {
         TITLE_AND_TAG_REGEX.is_match(v).unwrap()
     }
+
+    pub fn is_note(v: &str) -> bool {
+        v.chars().count() <= 1024
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
@@ -370,6 +699,9 @@ pub enum WhatToDo {
     ReplyComment,
     LikePost,
     LikeComment,
+    DeletePost,
+    DeleteComment,
+    VotePoll,
     None,
 }
 
@@ -441,7 +773,11 @@ pub async fn check_post(
     match s.post.get_status(tx, post_id).await? {
         post_system::model::PostStatus::Active => {
             if w == WhatToDo::WritePost {
-                let editable_seconds = s.core.get_config().editable_seconds as usizedb;
+                let editable_seconds = s
+                    .category
+                    .get_effective_policy(tx, post.category_id)
+                    .await?
+                    .get_editable_seconds(&s.core.get_config());
                 let manage = s.category.can_manage(tx, post.category_id, user).await?;
                 if !manage && post.created_at + editable_seconds < Utc::now().timestamp() as usizedb
                 {
@@ -450,6 +786,19 @@ pub async fn check_post(
                 if user.is_none() || (post.created_by_id != user.unwrap().id && !manage) {
                     return ApiError::no_permission("You not have permission to post.").to_err();
                 }
+            } else if w == WhatToDo::DeletePost {
+                let deletable_seconds = s.core.get_config().deletable_seconds as usizedb;
+                let manage = s.category.can_manage(tx, post.category_id, user).await?;
+                if user.is_none() || (post.created_by_id != user.unwrap().id && !manage) {
+                    return ApiError::no_permission("You not have permission to delete post.")
+                        .to_err();
+                }
+                if !manage
+                    && deletable_seconds > 0
+                    && post.created_at + deletable_seconds < Utc::now().timestamp() as usizedb
+                {
+                    return ApiError::undeletable_time().to_err();
+                }
             }
         }
         post_system::model::PostStatus::Archived => {
@@ -462,6 +811,23 @@ pub async fn check_post(
                 return ApiError::banned().to_err();
             }
         }
+        post_system::model::PostStatus::Deleted => {
+            if !s.category.can_manage(tx, post.category_id, user).await? {
+                return ApiError::deleted().to_err();
+            }
+        }
+        post_system::model::PostStatus::Pending => {
+            // author can read, edit and delete the pending post.
+            let author = match w {
+                WhatToDo::None | WhatToDo::WritePost | WhatToDo::DeletePost => {
+                    user.is_some_and(|user| user.id == post.created_by_id)
+                }
+                _ => false,
+            };
+            if !author && !s.category.can_manage(tx, post.category_id, user).await? {
+                return ApiError::post_pending().to_err();
+            }
+        }
     }
     Ok(post)
 }
@@ -475,9 +841,15 @@ pub async fn check_comment(
 ) -> Result<(PostBaseInfo, CommentBaseInfo), ApiError> {
     if !s.comment.is_exists(tx, comment_id).await? {
         return match w {
-            WhatToDo::WriteComment | WhatToDo::LikeComment => ApiError::no_comment_found().to_err(),
+            WhatToDo::WriteComment | WhatToDo::LikeComment | WhatToDo::DeleteComment => {
+                ApiError::no_comment_found().to_err()
+            }
             WhatToDo::ReplyComment => ApiError::reply_comment_missing().to_err(),
-            WhatToDo::LikePost | WhatToDo::WritePost | WhatToDo::None => {
+            WhatToDo::LikePost
+            | WhatToDo::WritePost
+            | WhatToDo::DeletePost
+            | WhatToDo::VotePoll
+            | WhatToDo::None => {
                 panic!("Check commant but provide other action type.")
             }
         };
@@ -487,7 +859,11 @@ pub async fn check_comment(
     match s.comment.get_status(tx, comment_id).await? {
         comment_system::model::CommentStatus::Active => {
             if w == WhatToDo::WriteComment {
-                let editable_seconds = s.core.get_config().editable_seconds as usizedb;
+                let editable_seconds = s
+                    .category
+                    .get_effective_policy(tx, post.category_id)
+                    .await?
+                    .get_editable_seconds(&s.core.get_config());
                 let manage = s.category.can_manage(tx, post.category_id, user).await?;
                 if !manage
                     && comment.created_at + editable_seconds < Utc::now().timestamp() as usizedb
@@ -497,6 +873,19 @@ pub async fn check_comment(
                 if user.is_none() || (comment.created_by_id != user.unwrap().id && !manage) {
                     return ApiError::no_permission("You not have permission to comment.").to_err();
                 }
+            } else if w == WhatToDo::DeleteComment {
+                let deletable_seconds = s.core.get_config().deletable_seconds as usizedb;
+                let manage = s.category.can_manage(tx, post.category_id, user).await?;
+                if user.is_none() || (comment.created_by_id != user.unwrap().id && !manage) {
+                    return ApiError::no_permission("You not have permission to delete comment.")
+                        .to_err();
+                }
+                if !manage
+                    && deletable_seconds > 0
+                    && comment.created_at + deletable_seconds < Utc::now().timestamp() as usizedb
+                {
+                    return ApiError::undeletable_time().to_err();
+                }
             }
         }
         comment_system::model::CommentStatus::Banned => {
@@ -504,6 +893,11 @@ pub async fn check_comment(
                 return ApiError::banned().to_err();
             }
         }
+        comment_system::model::CommentStatus::Deleted => {
+            if w != WhatToDo::None && !s.category.can_manage(tx, comment.category_id, user).await? {
+                return ApiError::deleted().to_err();
+            }
+        }
     }
     Ok((post, comment))
 }
@@ -521,7 +915,10 @@ pub async fn check_category(
 
     match s.category.get_status(tx, category_id).await? {
         category_system::model::CategoryStatus::Active => match w {
-            WhatToDo::LikePost | WhatToDo::LikeComment | WhatToDo::WritePost => {
+            WhatToDo::LikePost
+            | WhatToDo::LikeComment
+            | WhatToDo::VotePoll
+            | WhatToDo::WritePost => {
                 if !s.category.can_write(tx, category_id, user).await? {
                     return ApiError::no_permission("Can't post under category.").to_err();
                 }
@@ -531,7 +928,7 @@ pub async fn check_category(
                     return ApiError::no_permission("Can't comment under category.").to_err();
                 }
             }
-            WhatToDo::None => (),
+            WhatToDo::DeletePost | WhatToDo::DeleteComment | WhatToDo::None => (),
         },
         category_system::model::CategoryStatus::Archived => {
             if w != WhatToDo::None && !s.category.can_manage(tx, category_id, user).await? {
@@ -548,6 +945,65 @@ pub async fn check_category(
     Ok(())
 }
 
+/// Ids of categories readable by user, stopped categories are excluded for non-admin users.
+pub async fn get_readable_category_ids(
+    s: &ServerData,
+    tx: &mut SqliteConnection,
+    user: Option<&UserInfo>,
+) -> Result<Vec<usizedb>, ApiError> {
+    let mut ids = Vec::new();
+    for category_id in s.category.get_ids(tx).await? {
+        if check_category(s, tx, category_id, user, WhatToDo::None)
+            .await
+            .is_ok()
+            && s.category.can_read(tx, category_id, user).await?
+        {
+            ids.push(category_id);
+        }
+    }
+    Ok(ids)
+}
+
+/// Check the post to write against the policy of its category.
+pub fn check_post_policy(
+    policy: &CategoryPolicy,
+    title: &str,
+    content: &str,
+    content_type: ContentType,
+    tags: &[String],
+) -> Result<(), ApiError> {
+    if title.chars().count() < policy.min_title_length as usize {
+        return ApiError::policy_violation(&format!(
+            "The title must be at least {} chars.",
+            policy.min_title_length
+        ))
+        .to_err();
+    }
+    // markup is not counted.
+    let text = content_type.to_plain_text(content);
+    if text.trim().chars().count() < policy.min_content_length as usize {
+        return ApiError::policy_violation(&format!(
+            "The content must be at least {} chars.",
+            policy.min_content_length
+        ))
+        .to_err();
+    }
+    if !policy.is_content_type_allowed(content_type) {
+        return ApiError::policy_violation("The content type is not allowed in category.").to_err();
+    }
+    if tags.len() > policy.get_max_tags() as usize {
+        return ApiError::too_many_tags().to_err();
+    }
+    if !policy.required_tags.is_empty() && !tags.iter().any(|t| policy.required_tags.contains(t)) {
+        return ApiError::policy_violation(&format!(
+            "One of the tags `{}` is required.",
+            policy.required_tags.join("`, `")
+        ))
+        .to_err();
+    }
+    Ok(())
+}
+
 pub async fn check_verification_and_pass_it(
     s: &ServerData,
     tx: &mut SqliteConnection,
@@ -591,6 +1047,22 @@ pub async fn can_manage_post(
     }
 }
 
+pub async fn can_manage_category(
+    s: &ServerData,
+    tx: &mut SqliteConnection,
+    category_id: usizedb,
+    user: Option<&UserInfo>,
+) -> Result<(), ApiError> {
+    if !s.category.is_exists(tx, category_id).await? {
+        return ApiError::no_category_found().to_err();
+    }
+    if !s.category.can_manage(tx, category_id, user).await? {
+        ApiError::no_permission("You not the manager.").to_err()
+    } else {
+        Ok(())
+    }
+}
+
 pub async fn can_manage_comment(
     s: &ServerData,
     tx: &mut SqliteConnection,
@@ -607,3 +1079,125 @@ pub async fn can_manage_comment(
         Ok(())
     }
 }
+
+/// Notify users mentioned in post or comment, users who can't read the category or muted the post are skipped.
+pub async fn notify_mentions(
+    s: &ServerData,
+    created_by_id: usizedb,
+    flag: MentionFlag,
+    flag_ref_id: usizedb,
+    post_id: usizedb,
+    category_id: usizedb,
+    usernames: Vec<String>,
+) -> Result<()> {
+    if usernames.is_empty() {
+        return Ok(());
+    }
+    let limit = s.core.get_config().mention_limit as usize;
+    let mut tx = s.core.begin_unwrap(true).await;
+    let mut user_ids = Vec::with_capacity(limit);
+    for username in usernames {
+        if user_ids.len() >= limit {
+            break;
+        }
+        let user = match s.user.get_user_by_username(tx.as_mut(), &username).await? {
+            Some(user) if user.id != created_by_id => user,
+            _ => continue,
+        };
+        if s.category
+            .can_read(tx.as_mut(), category_id, Some(&user))
+            .await?
+            && !s
+                .notification
+                .is_muted(tx.as_mut(), user.id, post_id, category_id)
+                .await?
+        {
+            user_ids.push(user.id);
+        }
+    }
+    let user_ids = s
+        .notification
+        .create_mentions(
+            tx.as_mut(),
+            created_by_id,
+            flag,
+            flag_ref_id,
+            user_ids,
+            limit,
+        )
+        .await?;
+    tx.commit_unwrap().await;
+    s.notification
+        .notify_mentioned(created_by_id, flag, flag_ref_id, user_ids)
+        .await
+}
+
+/// Check quoted posts and comments are readable by user, return list of flag, ref id and quoted user id.
+pub async fn check_quotes(
+    s: &ServerData,
+    tx: &mut SqliteConnection,
+    user: Option<&UserInfo>,
+    quotes: Vec<QuoteTarget>,
+) -> Result<Vec<(QuoteFlag, usizedb, usizedb)>, ApiError> {
+    if quotes.len() > 10 {
+        return ApiError::illegal_quote("Too many quotes.").to_err();
+    }
+    let mut arr = Vec::with_capacity(quotes.len());
+    for quote in quotes {
+        arr.push(match quote {
+            QuoteTarget::Post(id) => {
+                if !s.post.is_exists(tx, id).await? {
+                    return ApiError::illegal_quote("Quoted post is not exists.").to_err();
+                }
+                let post = check_post(s, tx, id, user, WhatToDo::None).await?;
+                (QuoteFlag::TargetPost, id, post.created_by_id)
+            }
+            QuoteTarget::Comment(id) => {
+                if !s.comment.is_exists(tx, id).await? {
+                    return ApiError::illegal_quote("Quoted comment is not exists.").to_err();
+                }
+                let (_, comment) = check_comment(s, tx, id, user, WhatToDo::None).await?;
+                (QuoteFlag::TargetComment, id, comment.created_by_id)
+            }
+        });
+    }
+    Ok(arr)
+}
+
+/// Notify authors newly quoted by comment, users muted the post or in `exclude` are skipped.
+pub async fn notify_quotes(
+    s: &ServerData,
+    created_by_id: usizedb,
+    comment_id: usizedb,
+    post_id: usizedb,
+    category_id: usizedb,
+    user_ids: Vec<usizedb>,
+    exclude: &[usizedb],
+) -> Result<()> {
+    let mut tx = s.core.begin_unwrap(false).await;
+    let mut targets = Vec::with_capacity(user_ids.len());
+    for user_id in user_ids {
+        if !exclude.contains(&user_id)
+            && !s
+                .notification
+                .is_muted(tx.as_mut(), user_id, post_id, category_id)
+                .await?
+        {
+            targets.push(user_id);
+        }
+    }
+    tx.commit_unwrap().await;
+    for target_user_id in targets {
+        s.notification
+            .create_user_notification(
+                created_by_id,
+                UserNotificationArguments {
+                    ref_id: comment_id,
+                    target_user_id,
+                    n_type: UserNotificationType::Quote,
+                },
+            )
+            .await?;
+    }
+    Ok(())
+}
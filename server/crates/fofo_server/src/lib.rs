@@ -5,19 +5,23 @@ use actix_governor::{
 };
 use actix_web::{
     dev::ServiceRequest,
-    http::{header, Method},
+    http::Method,
     web::{self, PayloadConfig},
     App, HttpServer,
 };
 use anyhow::Result;
 use api::{api_error::ApiError, SDW};
+use bookmark_system::BookmarkSystem;
 use category_system::CategorySystem;
 use comment_system::CommentSystem;
 use group_system::GroupSystem;
+use leaderboard_system::LeaderboardSystem;
 use like_system::LikeSystem;
 use notification_system::NotificationSystem;
+use poll_system::PollSystem;
 use post_system::PostSystem;
-use request_client::get_auth_from_request;
+use read_system::ReadSystem;
+use request_client::{get_auth_from_request, get_ip_from_request};
 use shared_core::SharedCore;
 use storage::S3Ref;
 use tracing::{error, info};
@@ -37,6 +41,10 @@ pub struct ServerData {
     pub post: PostSystem,
     pub comment: CommentSystem,
     pub like: LikeSystem,
+    pub poll: PollSystem,
+    pub bookmark: BookmarkSystem,
+    pub read: ReadSystem,
+    pub leaderboard: LeaderboardSystem,
     pub category: CategorySystem,
     pub verification: VerificationSystem,
     pub notification: NotificationSystem,
@@ -44,33 +52,6 @@ pub struct ServerData {
 
 #[derive(Clone)]
 struct CustomKeyExtractor;
-impl CustomKeyExtractor {
-    fn get_ip_str_from_request(&self, req: &ServiceRequest) -> Result<String, ApiError> {
-        let config = req.app_data::<SDW>().unwrap().core.get_config();
-        let ip = if config.forwarded_ip {
-            match req.headers().get(header::X_FORWARDED_FOR) {
-                Some(v) => match v.to_str() {
-                    Ok(vstr) => vstr.to_owned(),
-                    Err(_) => {
-                        return ApiError::internal_error("Can't parse the header value to str.")
-                            .to_err()
-                    }
-                },
-                None => req
-                    .peer_addr()
-                    .map(|socket| socket.ip())
-                    .ok_or_else(|| ApiError::no_ip_address_found())?
-                    .to_string(),
-            }
-        } else {
-            req.peer_addr()
-                .map(|socket| socket.ip())
-                .ok_or_else(|| ApiError::no_ip_address_found())?
-                .to_string()
-        };
-        Ok(ip)
-    }
-}
 
 impl KeyExtractor for CustomKeyExtractor {
     type Key = String;
@@ -99,14 +80,14 @@ impl KeyExtractor for CustomKeyExtractor {
             | "/api/update_user"
             | "/api/verification"
             | "/api/presign_put_url" => {
-                let ip = self.get_ip_str_from_request(req)?;
+                let ip = get_ip_from_request(req.request())?;
                 Ok(format!("{ip}-{req_path}"))
             }
             _ => match req.method() {
                 &Method::PUT | &Method::POST => match get_auth_from_request(req.request()) {
                     Some(authorization) => Ok(format!("{authorization}-{req_path}")),
                     None => {
-                        let ip = self.get_ip_str_from_request(req)?;
+                        let ip = get_ip_from_request(req.request())?;
                         Ok(format!("{ip}-{req_path}"))
                     }
                 },
@@ -144,6 +125,10 @@ pub async fn run(core: SharedCore, address: &str, port: u16) -> Result<()> {
     let post = PostSystem::new(core.clone(), storage.clone()).await;
     let comment = CommentSystem::new(core.clone()).await;
     let like = LikeSystem::new(core.clone()).await;
+    let poll = PollSystem::new(core.clone()).await;
+    let bookmark = BookmarkSystem::new(core.clone()).await;
+    let read = ReadSystem::new(core.clone()).await;
+    let leaderboard = LeaderboardSystem::new(core.clone()).await;
     let verification = VerificationSystem::new(core.clone(), storage.clone()).await;
     let notification = NotificationSystem::new(core.clone()).await;
 
@@ -155,6 +140,10 @@ pub async fn run(core: SharedCore, address: &str, port: u16) -> Result<()> {
         post,
         comment,
         like,
+        poll,
+        bookmark,
+        read,
+        leaderboard,
         category,
         verification,
         notification,
@@ -250,6 +239,13 @@ async fn debug_data_initial(s: ServerData) {
             moderator_ids: vec![mod1.id, mod2.id],
             group_ids: vec![],
             cover_url: None,
+            qa_enabled: false,
+            parent_id: 0,
+            inherit_levels: false,
+            inherit_groups: false,
+            inherit_moderators: false,
+            section_id: 0,
+            policy: None,
         })
         .await
         .unwrap();
@@ -267,6 +263,13 @@ async fn debug_data_initial(s: ServerData) {
             moderator_ids: vec![mod1.id, mod2.id],
             group_ids: vec![],
             cover_url: None,
+            qa_enabled: false,
+            parent_id: 0,
+            inherit_levels: false,
+            inherit_groups: false,
+            inherit_moderators: false,
+            section_id: 0,
+            policy: None,
         })
         .await
         .unwrap();
@@ -296,6 +299,8 @@ async fn debug_data_initial(s: ServerData) {
                             ],
                             cover_url: None,
                             top_index: 0,
+                            poll: None,
+                            pending: false,
                         },
                     )
                     .await
@@ -335,6 +340,8 @@ async fn debug_data_initial(s: ServerData) {
                             ],
                             cover_url: None,
                             top_index: 0,
+                            poll: None,
+                            pending: false,
                         },
                     )
                     .await
@@ -368,6 +375,7 @@ async fn debug_data_initial(s: ServerData) {
                                         parent_id: 0,
                                         top_index: 0,
                                     },
+                                    vec![],
                                 )
                                 .await
                                 .unwrap();
@@ -391,6 +399,7 @@ async fn debug_data_initial(s: ServerData) {
                                         parent_id: comment.id,
                                         top_index: 0,
                                     },
+                                    vec![],
                                 )
                                 .await
                                 .unwrap();
@@ -414,6 +423,7 @@ async fn debug_data_initial(s: ServerData) {
                                         parent_id: comment.parent_id,
                                         top_index: 0,
                                     },
+                                    vec![],
                                 )
                                 .await
                                 .unwrap();
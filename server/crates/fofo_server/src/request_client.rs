@@ -1,13 +1,13 @@
 use std::pin::Pin;
 
-use actix_web::{dev::Payload, web, FromRequest, HttpRequest};
+use actix_web::{dev::Payload, http::header, web, FromRequest, HttpRequest};
 use anyhow::Result;
 use futures::Future;
 use serde::{Deserialize, Serialize};
 
 use user_system::model::UserInfo;
 
-use super::api::SDW;
+use super::api::{api_error::ApiError, SDW};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct AuthQuery {
@@ -16,6 +16,7 @@ struct AuthQuery {
 
 pub struct RequestClient {
     user: Option<UserInfo>,
+    ip: Option<String>,
 }
 
 impl RequestClient {
@@ -30,6 +31,10 @@ impl RequestClient {
     pub fn is_logined(&self) -> bool {
         self.user.is_some()
     }
+
+    pub fn get_ip(&self) -> Option<&str> {
+        self.ip.as_deref()
+    }
 }
 
 impl FromRequest for RequestClient {
@@ -38,6 +43,7 @@ impl FromRequest for RequestClient {
 
     fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
         let auth = get_auth_from_request(req);
+        let ip = get_ip_from_request(req).ok();
         let s = req.app_data::<SDW>().unwrap().clone();
         Box::pin(async move {
             let user = match auth {
@@ -47,7 +53,7 @@ impl FromRequest for RequestClient {
                 },
                 None => None,
             };
-            Ok(RequestClient { user })
+            Ok(RequestClient { user, ip })
         })
     }
 }
@@ -63,3 +69,29 @@ pub fn get_auth_from_request(req: &HttpRequest) -> Option<String> {
     };
     auth
 }
+
+pub fn get_ip_from_request(req: &HttpRequest) -> Result<String, ApiError> {
+    let config = req.app_data::<SDW>().unwrap().core.get_config();
+    let ip = if config.forwarded_ip {
+        match req.headers().get(header::X_FORWARDED_FOR) {
+            Some(v) => match v.to_str() {
+                Ok(vstr) => vstr.to_owned(),
+                Err(_) => {
+                    return ApiError::internal_error("Can't parse the header value to str.")
+                        .to_err()
+                }
+            },
+            None => req
+                .peer_addr()
+                .map(|socket| socket.ip())
+                .ok_or_else(|| ApiError::no_ip_address_found())?
+                .to_string(),
+        }
+    } else {
+        req.peer_addr()
+            .map(|socket| socket.ip())
+            .ok_or_else(|| ApiError::no_ip_address_found())?
+            .to_string()
+    };
+    Ok(ip)
+}
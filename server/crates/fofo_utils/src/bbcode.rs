@@ -0,0 +1,344 @@
+use fancy_regex::Regex;
+use lazy_static::lazy_static;
+
+use crate::LINK_REL;
+
+lazy_static! {
+    static ref TAG_REGEX: Regex =
+        Regex::new(r"\[(?P<close>/?)(?P<name>[a-zA-Z]+|\*)(?:=(?P<arg>[^\]\[]{1,256}))?\]")
+            .unwrap();
+}
+
+/// Schemes of links and images, the others are rendered as text.
+const URL_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+/// Tag of BBCode which converted to HTML, the others are kept as text.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Tag {
+    Bold,
+    Italic,
+    Underline,
+    Strike,
+    Url,
+    Img,
+    Quote,
+    Code,
+    List,
+    Item,
+}
+
+impl Tag {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "b" => Tag::Bold,
+            "i" => Tag::Italic,
+            "u" => Tag::Underline,
+            "s" => Tag::Strike,
+            "url" => Tag::Url,
+            "img" => Tag::Img,
+            "quote" => Tag::Quote,
+            "code" => Tag::Code,
+            "list" => Tag::List,
+            "*" => Tag::Item,
+            _ => return None,
+        })
+    }
+
+    /// Tag whose inner text is raw and not parsed.
+    fn is_raw(&self) -> bool {
+        matches!(self, Tag::Url | Tag::Img | Tag::Code)
+    }
+
+    fn close_html(&self, ordered: bool) -> &'static str {
+        match self {
+            Tag::Bold => "</strong>",
+            Tag::Italic => "</em>",
+            Tag::Underline => "</u>",
+            Tag::Strike => "</del>",
+            Tag::Quote => "</blockquote>",
+            Tag::List if ordered => "</ol>",
+            Tag::List => "</ul>",
+            Tag::Item => "</li>",
+            Tag::Url | Tag::Img | Tag::Code => "",
+        }
+    }
+}
+
+struct Token<'a> {
+    start: usize,
+    end: usize,
+    close: bool,
+    tag: Tag,
+    arg: Option<&'a str>,
+}
+
+fn next_token(content: &str, from: usize) -> Option<Token> {
+    let mut pos = from;
+    while let Ok(Some(caps)) = TAG_REGEX.captures_from_pos(content, pos) {
+        let m = caps.get(0).unwrap();
+        if let Some(tag) = Tag::from_name(&caps["name"]) {
+            return Some(Token {
+                start: m.start(),
+                end: m.end(),
+                close: !caps["close"].is_empty(),
+                tag,
+                arg: caps.name("arg").map(|arg| arg.as_str()),
+            });
+        }
+        pos = m.end();
+    }
+    None
+}
+
+/// Find the close tag of a raw tag, return (start, end) of the close tag.
+fn find_close(content: &str, from: usize, tag: Tag) -> Option<(usize, usize)> {
+    let mut pos = from;
+    while let Some(token) = next_token(content, pos) {
+        if token.close && token.tag == tag {
+            return Some((token.start, token.end));
+        }
+        pos = token.end;
+    }
+    None
+}
+
+/// Relative urls and the urls of `URL_SCHEMES` are safe.
+fn is_safe_url(url: &str) -> bool {
+    match url.find([':', '/', '?', '#']) {
+        Some(i) if url[i..].starts_with(':') => {
+            URL_SCHEMES.contains(&url[..i].to_ascii_lowercase().as_str())
+        }
+        _ => true,
+    }
+}
+
+/// Escape text the same as the sanitizer serializes it, so the sanitized HTML is unchanged.
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '\u{a0}' => escaped.push_str("&nbsp;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escape value of attribute the same as the sanitizer serializes it.
+fn escape_attr(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\u{a0}' => escaped.push_str("&nbsp;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn push_text(html: &mut String, text: &str) {
+    html.push_str(&escape_text(text).replace('\n', "<br>\n"));
+}
+
+/// Convert BBCode to HTML, unknown tags are kept as text and unclosed tags are closed at the end.
+/// Links and images with unsafe url are kept as text, so the result passes the sanitizer unchanged.
+pub fn to_html(content: &str) -> String {
+    let mut html = String::with_capacity(content.len() * 3 / 2);
+    // (tag, ordered list)
+    let mut stack: Vec<(Tag, bool)> = Vec::new();
+    let mut pos = 0;
+    while let Some(token) = next_token(content, pos) {
+        push_text(&mut html, &content[pos..token.start]);
+        pos = token.end;
+        if token.close {
+            if let Some(i) = stack.iter().rposition(|(tag, _)| *tag == token.tag) {
+                for (tag, ordered) in stack.drain(i..).rev() {
+                    html.push_str(tag.close_html(ordered));
+                }
+            } else {
+                push_text(&mut html, &content[token.start..token.end]);
+            }
+            continue;
+        }
+        if token.tag.is_raw() {
+            let (inner, end) = match find_close(content, token.end, token.tag) {
+                Some((start, end)) => (&content[token.end..start], end),
+                None => (&content[token.end..], content.len()),
+            };
+            pos = end;
+            match token.tag {
+                Tag::Url => {
+                    let href = token.arg.unwrap_or(inner).trim();
+                    if is_safe_url(href) {
+                        html.push_str(&format!(
+                            "<a href=\"{}\" rel=\"{LINK_REL}\">{}</a>",
+                            escape_attr(href),
+                            escape_text(inner)
+                        ));
+                    } else {
+                        push_text(&mut html, inner);
+                    }
+                }
+                Tag::Img => {
+                    let src = inner.trim();
+                    if is_safe_url(src) {
+                        html.push_str(&format!("<img src=\"{}\">", escape_attr(src)));
+                    } else {
+                        push_text(&mut html, inner);
+                    }
+                }
+                _ => {
+                    html.push_str(&format!(
+                        "<pre><code>{}</code></pre>",
+                        escape_text(inner.trim_matches('\n'))
+                    ));
+                }
+            }
+            continue;
+        }
+        match token.tag {
+            Tag::Bold => html.push_str("<strong>"),
+            Tag::Italic => html.push_str("<em>"),
+            Tag::Underline => html.push_str("<u>"),
+            Tag::Strike => html.push_str("<del>"),
+            Tag::Quote => html.push_str("<blockquote>"),
+            Tag::List => {
+                let ordered = token.arg.is_some();
+                html.push_str(if ordered { "<ol>" } else { "<ul>" });
+                stack.push((Tag::List, ordered));
+                continue;
+            }
+            Tag::Item => {
+                // an item closes the previous item of the same list.
+                if let Some(i) = stack.iter().rposition(|(tag, _)| *tag == Tag::List) {
+                    if let Some(j) = stack[i..].iter().position(|(tag, _)| *tag == Tag::Item) {
+                        for (tag, ordered) in stack.drain(i + j..).rev() {
+                            html.push_str(tag.close_html(ordered));
+                        }
+                    }
+                } else {
+                    push_text(&mut html, &content[token.start..token.end]);
+                    continue;
+                }
+                html.push_str("<li>");
+            }
+            Tag::Url | Tag::Img | Tag::Code => unreachable!(),
+        }
+        stack.push((token.tag, false));
+    }
+    push_text(&mut html, &content[pos..]);
+    for (tag, ordered) in stack.into_iter().rev() {
+        html.push_str(tag.close_html(ordered));
+    }
+    html
+}
+
+/// Check the known tags are closed in order, `[*]` needs no close tag.
+pub fn is_well_formed(content: &str) -> bool {
+    let mut stack: Vec<Tag> = Vec::new();
+    let mut pos = 0;
+    while let Some(token) = next_token(content, pos) {
+        pos = token.end;
+        if token.tag == Tag::Item {
+            if token.close || !stack.contains(&Tag::List) {
+                return false;
+            }
+        } else if token.close {
+            if stack.pop() != Some(token.tag) {
+                return false;
+            }
+        } else if token.tag.is_raw() {
+            match find_close(content, token.end, token.tag) {
+                Some((_, end)) => pos = end,
+                None => return false,
+            }
+        } else {
+            stack.push(token.tag);
+        }
+    }
+    stack.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HTML_SANITIZER;
+
+    fn assert_html(content: &str, expected: &str) {
+        let html = to_html(content);
+        assert_eq!(html, expected);
+        assert_eq!(HTML_SANITIZER.clean(&html).to_string(), html);
+    }
+
+    #[test]
+    fn nested_tags() {
+        assert_html(
+            "[b]bold [i]both[/i][/b]",
+            "<strong>bold <em>both</em></strong>",
+        );
+        assert_html(
+            "[quote][list=1][*]one[*][u]two[/u][/list][/quote]",
+            "<blockquote><ol><li>one</li><li><u>two</u></li></ol></blockquote>",
+        );
+        assert!(is_well_formed("[b]bold [i]both[/i][/b]"));
+    }
+
+    #[test]
+    fn misnested_and_unclosed_tags() {
+        // closing outer tag closes the inner ones.
+        assert_html("[b]a [i]b[/b] c[/i]", "<strong>a <em>b</em></strong> c[/i]");
+        assert_html("[b][s]open", "<strong><del>open</del></strong>");
+        assert_html("[code]a < b", "<pre><code>a &lt; b</code></pre>");
+        assert_html("[*]item[/x]", "[*]item[/x]");
+        assert!(!is_well_formed("[b]a [i]b[/b] c[/i]"));
+        assert!(!is_well_formed("[b][s]open"));
+        assert!(!is_well_formed("[code]a < b"));
+        assert!(!is_well_formed("[*]item"));
+    }
+
+    #[test]
+    fn url() {
+        assert_html(
+            "[url]https://example.com/?a=1&b=2[/url]",
+            "<a href=\"https://example.com/?a=1&amp;b=2\" rel=\"noopener noreferrer nofollow ugc\">https://example.com/?a=1&amp;b=2</a>",
+        );
+        assert_html(
+            "[url=/post/1]post[/url]",
+            "<a href=\"/post/1\" rel=\"noopener noreferrer nofollow ugc\">post</a>",
+        );
+        assert_html(
+            "[url=https://example.com/\"onclick=\"alert(1)]quoted[/url]",
+            "<a href=\"https://example.com/&quot;onclick=&quot;alert(1)\" rel=\"noopener noreferrer nofollow ugc\">quoted</a>",
+        );
+    }
+
+    #[test]
+    fn unsafe_url_is_text() {
+        assert_html("[url=javascript:alert(1)]click[/url]", "click");
+        assert_html("[url= JavaScript:alert(1)]click[/url]", "click");
+        assert_html("[url]javascript:alert(1)[/url]", "javascript:alert(1)");
+        assert_html(
+            "[img]javascript:alert(\"x\")[/img]",
+            "javascript:alert(\"x\")",
+        );
+        assert_html(
+            "[img]https://example.com/a.png[/img]",
+            "<img src=\"https://example.com/a.png\">",
+        );
+    }
+
+    #[test]
+    fn escaping() {
+        assert_html(
+            "<script>alert(\"a\" & 'b')</script>",
+            "&lt;script&gt;alert(\"a\" &amp; 'b')&lt;/script&gt;",
+        );
+        assert_html("[b]<i>[/b]\nline", "<strong>&lt;i&gt;</strong><br>\nline");
+        assert_html("[unknown]<[/unknown]", "[unknown]&lt;[/unknown]");
+    }
+}
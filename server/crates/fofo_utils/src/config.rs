@@ -14,6 +14,8 @@ pub struct Config {
     pub buffer_size: usize,
     /// Request pagination maximum limit.
     pub fetch_limit: usize,
+    /// The maximum depth of comment tree in one request.
+    pub comment_tree_max_depth: usize,
     /// All task trigger interval in millisecond.
     pub task_trigger_ms: usize,
     /// Users logined active duration in day.
@@ -36,6 +38,42 @@ pub struct Config {
     pub editable_seconds: u64,
     /// Post and comment top index maximum, ignore admin.
     pub top_index_max: u64,
+    /// Post and comment deletable duration of author in second. 0 means unlimited.
+    pub deletable_seconds: u64,
+    /// Deleted post and comment purge duration in day. 0 means never purge.
+    pub deleted_purge_days: u64,
+    /// Lock post without new comment in day automatically. 0 means never lock.
+    pub auto_lock_days: u64,
+    /// Hot ranking weight of likes and dislikes.
+    pub hot_likes_weight: f64,
+    /// Hot ranking weight of views.
+    pub hot_views_weight: f64,
+    /// Hot ranking weight of comments.
+    pub hot_comments_weight: f64,
+    /// Hot ranking gravity, the higher the faster old posts sink.
+    pub hot_gravity: f64,
+    /// Hot ranking weight of last comment time against created time, between 0 and 1.
+    pub hot_activity_weight: f64,
+    /// Hot score recompute interval in second, at least 1.
+    pub hot_recompute_seconds: u64,
+    /// Posts without activity in day are not hot anymore, their hot score is reset to 0. 0 means recompute all posts.
+    pub hot_window_days: u64,
+    /// Leaderboard recompute interval in second, at least 1.
+    pub leaderboard_recompute_seconds: u64,
+    /// Views of post from same user or ip in this duration are counted once, in second. 0 means no deduplication.
+    pub view_dedup_seconds: u64,
+    /// The maximum capacity of viewers remembered for views deduplication.
+    pub view_dedup_capacity: u64,
+    /// The maximum users notified by mentions in a post or comment.
+    pub mention_limit: u64,
+    /// Render post and comment content to sanitized HTML in `rendered_html`.
+    pub render_html: bool,
+    /// The maximum chars of post excerpt.
+    pub excerpt_length: u64,
+    /// Words read per minute used to estimate the reading time of post.
+    pub reading_words_per_minute: u64,
+    /// Emoji reactions which users can react to post and comment with.
+    pub reactions: Vec<Cow<'static, str>>,
     /// Auto fetch the post cover from article if post created cover is empty.
     pub auto_fetch_post_cover: bool,
     /// User can upload the post cover or not.
@@ -50,6 +88,8 @@ pub struct Config {
     pub forwarded_ip: bool,
     /// The bypass key for rate limit. If request with header `x-bypass-key` equals to this key will bypass it. Default is none.
     pub bypass_key: Option<Cow<'static, str>>,
+    /// The secret key to hash the ip of voters. Default is none, a random key is generated once and stored in the database then.
+    pub vote_ip_secret: Option<Cow<'static, str>>,
     /// Console log level.
     pub log_level: Cow<'static, str>,
     /// Image format. Example, using when generate captcha. Go to https://docs.rs/image/latest/image/enum.ImageFormat.html see more.
@@ -105,6 +145,7 @@ impl Default for Config {
         Config {
             buffer_size: 999,
             fetch_limit: 30,
+            comment_tree_max_depth: 5,
             task_trigger_ms: 500,
             auth_active_days: 180,
             resource_expiry_seconds: 3600,
@@ -116,12 +157,34 @@ impl Default for Config {
             tti_seconds: 5,
             editable_seconds: 30 * 60,
             top_index_max: 9,
+            deletable_seconds: 0,
+            deleted_purge_days: 30,
+            auto_lock_days: 0,
+            hot_likes_weight: 500.0,
+            hot_views_weight: 1.0,
+            hot_comments_weight: 100.0,
+            hot_gravity: 1.8,
+            hot_activity_weight: 0.5,
+            hot_recompute_seconds: 600,
+            hot_window_days: 30,
+            leaderboard_recompute_seconds: 600,
+            view_dedup_seconds: 3600,
+            view_dedup_capacity: 100000,
+            mention_limit: 10,
+            render_html: true,
+            excerpt_length: 200,
+            reading_words_per_minute: 200,
+            reactions: ["👍", "❤️", "😂", "🎉", "👀"]
+                .into_iter()
+                .map(Cow::Borrowed)
+                .collect(),
             auto_fetch_post_cover: true,
             custom_post_cover_supported: false,
             open_register: true,
             local: LocalStorageConfig::default(),
             s3: None,
             bypass_key: None,
+            vote_ip_secret: None,
             forwarded_ip: false,
             log_level: "info".into(),
             image_format: "jpeg".into(),
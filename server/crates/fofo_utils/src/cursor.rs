@@ -0,0 +1,122 @@
+use crate::usizedb;
+
+const SPLIT_CHAR: char = '~';
+
+/// Keyset pagination cursor. It encodes the top index, sort key and id of the last item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor {
+    pub top_index: usizedb,
+    pub key: f64,
+    pub id: usizedb,
+}
+
+impl Cursor {
+    pub fn new(top_index: usizedb, key: f64, id: usizedb) -> Self {
+        Self { top_index, key, id }
+    }
+
+    /// Encode cursor to an opaque token.
+    pub fn encode(&self) -> String {
+        let raw = format!(
+            "{}{SPLIT_CHAR}{}{SPLIT_CHAR}{}",
+            self.top_index, self.key, self.id
+        );
+        raw.bytes().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Decode cursor from token, return None if token is illegal.
+    pub fn decode(token: &str) -> Option<Self> {
+        if token.len() % 2 != 0 || !token.is_ascii() {
+            return None;
+        }
+        let bytes = (0..token.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&token[i..i + 2], 16).ok())
+            .collect::<Option<Vec<u8>>>()?;
+        let raw = String::from_utf8(bytes).ok()?;
+        let mut parts = raw.split(SPLIT_CHAR);
+        let cursor = Self {
+            top_index: parts.next()?.parse().ok()?,
+            key: parts.next()?.parse().ok()?,
+            id: parts.next()?.parse().ok()?,
+        };
+        if parts.next().is_some() || !cursor.key.is_finite() {
+            return None;
+        }
+        Some(cursor)
+    }
+
+    /// SQL condition of items after cursor, ordered by `top_index DESC` (if enabled), `key_col` and `id`.
+    pub fn to_condition(&self, key_col: &str, desc: bool, top_order_enable: bool) -> String {
+        let (key, id) = (self.key, self.id);
+        let op = if desc { "<" } else { ">" };
+        let key_cond = format!("({key_col} {op} {key} OR ({key_col} = {key} AND id {op} {id}))");
+        if top_order_enable {
+            let top_index = self.top_index;
+            format!("(top_index < {top_index} OR (top_index = {top_index} AND {key_cond}))")
+        } else {
+            key_cond
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_integer_key() {
+        let cursor = Cursor::new(2, 42.0, 7);
+        assert_eq!(Cursor::decode(&cursor.encode()), Some(cursor));
+    }
+
+    #[test]
+    fn round_trip_f64_key() {
+        for key in [0.123456789, -3.5, 1e-12, f64::MAX] {
+            let cursor = Cursor::new(0, key, 1);
+            assert_eq!(Cursor::decode(&cursor.encode()), Some(cursor));
+        }
+    }
+
+    #[test]
+    fn reject_malformed_tokens() {
+        let encode = |raw: &str| {
+            raw.bytes()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>()
+        };
+        assert_eq!(Cursor::decode(&encode("1~2")), None);
+        assert_eq!(Cursor::decode(&encode("1~2~3~4")), None);
+        assert_eq!(Cursor::decode(&encode("a~2~3")), None);
+        assert_eq!(Cursor::decode(&encode("1~inf~3")), None);
+        assert_eq!(Cursor::decode(&encode("1~NaN~3")), None);
+        assert_eq!(Cursor::decode(""), None);
+    }
+
+    #[test]
+    fn reject_truncated_tokens() {
+        let token = Cursor::new(1, 2.5, 3).encode();
+        assert_eq!(Cursor::decode(&token[..token.len() - 1]), None);
+        assert_eq!(Cursor::decode(&token[..token.len() - 2]), None);
+    }
+
+    #[test]
+    fn reject_non_hex_tokens() {
+        assert_eq!(Cursor::decode("zz"), None);
+        assert_eq!(Cursor::decode("317e327e33g0"), None);
+        assert_eq!(Cursor::decode("éé"), None);
+    }
+
+    #[test]
+    fn condition_with_top_order() {
+        let cursor = Cursor::new(2, 10.0, 5);
+        assert_eq!(
+            cursor.to_condition("likes", true, true),
+            "(top_index < 2 OR (top_index = 2 AND (likes < 10 OR (likes = 10 AND id < 5))))"
+        );
+        assert_eq!(
+            cursor.to_condition("likes", false, false),
+            "(likes > 10 OR (likes = 10 AND id > 5))"
+        );
+    }
+}
@@ -1,17 +1,23 @@
+use ammonia::Builder;
 use anyhow::{bail, Result};
 use chrono::{Duration, Utc};
+use crypto::{hmac::Hmac, mac::Mac, sha2::Sha256};
 use fancy_regex::Regex;
 use moka::future::{Cache, CacheBuilder};
+use pulldown_cmark::{html, Event, Options, Parser, Tag};
 use serde_repr::{Deserialize_repr, Serialize_repr};
-use sqlx::SqliteConnection;
+use sqlx::{Row, SqliteConnection};
 use std::{
+    borrow::Cow,
     collections::hash_map::DefaultHasher,
     hash::{Hash, Hasher},
     str::FromStr,
 };
 use tracing::error;
 
+pub mod bbcode;
 pub mod config;
+pub mod cursor;
 pub mod meta;
 
 use crate::config::SafeConfig;
@@ -20,28 +26,272 @@ use lazy_static::lazy_static;
 #[allow(non_camel_case_types)]
 pub type usizedb = u32;
 
+/// `rel` of links in the rendered HTML.
+const LINK_REL: &str = "noopener noreferrer nofollow ugc";
+
 lazy_static! {
     static ref IMAGE_URL_REGEX: Regex = Regex::new(r"!\[.*?\]\((?P<url>https?\:\/\/\S+).*\)").unwrap();
+    static ref HTML_IMAGE_URL_REGEX: Regex = Regex::new(r#"(?i)<img\s[^>]*?src\s*=\s*["']?(?P<url>https?://[^"'\s>]+)"#).unwrap();
+    static ref BBCODE_IMAGE_URL_REGEX: Regex = Regex::new(r"(?i)\[img(?:=[^\]]*)?\]\s*(?P<url>https?://[^\[\s]+)\s*\[/img\]").unwrap();
+    static ref CODE_BLOCK_REGEX: Regex = Regex::new(r"(?ms)^ {0,3}(?P<fence>`{3,}|~{3,}).*?(^ {0,3}(?P=fence)|\z)").unwrap();
+    static ref INLINE_CODE_REGEX: Regex = Regex::new(r"(?P<tick>`+)[^`].*?(?P=tick)").unwrap();
+    static ref HTML_CODE_REGEX: Regex = Regex::new(r"(?is)<(?P<tag>pre|code)\b.*?(</(?P=tag)\s*>|\z)").unwrap();
+    static ref BBCODE_CODE_REGEX: Regex = Regex::new(r"(?is)\[code\].*?(\[/code\]|\z)").unwrap();
+    static ref MENTION_REGEX: Regex = Regex::new(r"(?<![\w@./])@(?P<name>[a-z0-9]{5,128})(?![\w@])").unwrap();
+    static ref QUOTE_REGEX: Regex = Regex::new(r"(?m)^ {0,3}:::quote (?P<flag>post|comment):(?P<id>\d{1,10})[ \t]*$").unwrap();
+    static ref HTML_TAG_REGEX: Regex = Regex::new(r"(?s)<[^>]*>").unwrap();
+    static ref HTML_SANITIZER: Builder<'static> = {
+        let mut builder = Builder::default();
+        builder
+            .add_tags(&["input"])
+            .add_tag_attributes("input", &["checked", "disabled"])
+            .add_tag_attribute_values("input", "type", &["checkbox"])
+            .link_rel(Some(LINK_REL));
+        builder
+    };
+}
+
+/// Metadata of content shown in lists without the content.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ContentMeta {
+    /// Plain text of the beginning of content.
+    pub excerpt: String,
+    pub word_count: usizedb,
+    /// Estimated reading time in minutes.
+    pub reading_time: usizedb,
+}
+
+/// Source of quote in content.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum QuoteTarget {
+    Post(usizedb),
+    Comment(usizedb),
 }
 
 #[derive(Debug, Deserialize_repr, Serialize_repr, PartialEq, Clone, Copy, sqlx::Type)]
 #[repr(u8)]
 pub enum ContentType {
     Markdown,
+    PlainText,
+    /// Html is sanitized before saved.
+    Html,
+    BBCode,
 }
 
 impl ContentType {
     pub fn get_first_image_url(&self, content: &str) -> Option<String> {
+        let regex: &Regex = match self {
+            ContentType::Markdown => &IMAGE_URL_REGEX,
+            ContentType::Html => &HTML_IMAGE_URL_REGEX,
+            ContentType::BBCode => &BBCODE_IMAGE_URL_REGEX,
+            ContentType::PlainText => return None,
+        };
+        let caps = regex.captures(content).unwrap();
+        match caps {
+            Some(caps) => caps.name("url").map(|m| m.as_str().to_string()),
+            None => None,
+        }
+    }
+
+    /// Remove code from content, so the syntax inside code is not parsed.
+    fn strip_code<'a>(&self, content: &'a str) -> Cow<'a, str> {
+        match self {
+            ContentType::Markdown => {
+                let content = CODE_BLOCK_REGEX.replace_all(content, "");
+                match content {
+                    Cow::Borrowed(content) => INLINE_CODE_REGEX.replace_all(content, ""),
+                    Cow::Owned(content) => {
+                        Cow::Owned(INLINE_CODE_REGEX.replace_all(&content, "").into_owned())
+                    }
+                }
+            }
+            ContentType::Html => HTML_CODE_REGEX.replace_all(content, ""),
+            ContentType::BBCode => BBCODE_CODE_REGEX.replace_all(content, ""),
+            ContentType::PlainText => Cow::Borrowed(content),
+        }
+    }
+
+    /// Get distinct usernames mentioned by `@username`, mentions inside code are skipped.
+    pub fn get_mentions(&self, content: &str) -> Vec<String> {
+        let content = self.strip_code(content);
+        let mut names: Vec<String> = Vec::new();
+        for caps in MENTION_REGEX.captures_iter(&content) {
+            if let Some(name) = caps.ok().and_then(|c| c.name("name")) {
+                let name = name.as_str().to_owned();
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+        names
+    }
+
+    /// Get distinct quote sources, the quote is a line `:::quote comment:<id>` (or `post:<id>`)
+    /// followed by quoted text and closed by `:::`. Quotes inside code are skipped.
+    pub fn get_quotes(&self, content: &str) -> Vec<QuoteTarget> {
+        let content = self.strip_code(content);
+        let mut quotes: Vec<QuoteTarget> = Vec::new();
+        for caps in QUOTE_REGEX.captures_iter(&content) {
+            let caps = match caps {
+                Ok(caps) => caps,
+                Err(_) => continue,
+            };
+            let id = match caps["id"].parse() {
+                Ok(id) => id,
+                Err(_) => continue,
+            };
+            let quote = if &caps["flag"] == "post" {
+                QuoteTarget::Post(id)
+            } else {
+                QuoteTarget::Comment(id)
+            };
+            if !quotes.contains(&quote) {
+                quotes.push(quote);
+            }
+        }
+        quotes
+    }
+
+    /// Check the content can be parsed as this type.
+    pub fn is_well_formed(&self, content: &str) -> bool {
+        match self {
+            ContentType::BBCode => bbcode::is_well_formed(content),
+            ContentType::Markdown | ContentType::PlainText | ContentType::Html => {
+                !content.contains('\0')
+            }
+        }
+    }
+
+    /// Sanitize content before saved, only Html is changed.
+    pub fn sanitize(&self, content: String) -> String {
         match self {
+            ContentType::Html => HTML_SANITIZER.clean(&content).to_string(),
+            ContentType::Markdown | ContentType::PlainText | ContentType::BBCode => content,
+        }
+    }
+
+    /// Get plain text of content, the syntax, tags and line breaks are removed.
+    pub fn to_plain_text(&self, content: &str) -> String {
+        let text = match self {
             ContentType::Markdown => {
-                let caps = IMAGE_URL_REGEX.captures(content).unwrap();
-                match caps {
-                    Some(caps) => caps.name("url").map(|m| m.as_str().to_string()),
-                    None => None,
+                let mut text = String::with_capacity(content.len());
+                for event in Parser::new_ext(content, Options::ENABLE_TABLES) {
+                    match event {
+                        Event::Text(t) | Event::Code(t) => text.push_str(&t),
+                        Event::SoftBreak
+                        | Event::HardBreak
+                        | Event::End(
+                            Tag::Paragraph
+                            | Tag::Heading(..)
+                            | Tag::CodeBlock(_)
+                            | Tag::Item
+                            | Tag::TableCell,
+                        ) => text.push(' '),
+                        _ => (),
+                    }
                 }
+                text
+            }
+            ContentType::PlainText => content.to_owned(),
+            ContentType::Html => html_to_text(content),
+            ContentType::BBCode => html_to_text(&bbcode::to_html(content)),
+        };
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// Get the excerpt with at most `excerpt_length` chars, word count and reading time of content.
+    pub fn get_meta(
+        &self,
+        content: &str,
+        excerpt_length: usize,
+        words_per_minute: usizedb,
+    ) -> ContentMeta {
+        let text = self.to_plain_text(content);
+        let word_count = count_words(&text);
+        let excerpt = match text.char_indices().nth(excerpt_length) {
+            Some((end, _)) => format!("{}…", text[..end].trim_end()),
+            None => text,
+        };
+        ContentMeta {
+            excerpt,
+            word_count,
+            reading_time: word_count.div_ceil(words_per_minute.max(1)),
+        }
+    }
+
+    /// Render content to HTML which is safe to embed, scripts, styles and unknown attributes are removed.
+    pub fn render_html(&self, content: &str) -> String {
+        let unsafe_html = match self {
+            ContentType::Markdown => {
+                let options = Options::ENABLE_TABLES
+                    | Options::ENABLE_STRIKETHROUGH
+                    | Options::ENABLE_TASKLISTS
+                    | Options::ENABLE_FOOTNOTES;
+                let mut unsafe_html = String::with_capacity(content.len() * 3 / 2);
+                html::push_html(&mut unsafe_html, Parser::new_ext(content, options));
+                Cow::Owned(unsafe_html)
             }
+            ContentType::PlainText => {
+                let paragraphs: Vec<_> = content
+                    .split("\n\n")
+                    .map(str::trim)
+                    .filter(|p| !p.is_empty())
+                    .map(|p| format!("<p>{}</p>", escape_html(p).replace('\n', "<br>\n")))
+                    .collect();
+                return paragraphs.join("\n");
+            }
+            ContentType::Html => Cow::Borrowed(content),
+            ContentType::BBCode => Cow::Owned(bbcode::to_html(content)),
+        };
+        HTML_SANITIZER.clean(&unsafe_html).to_string()
+    }
+}
+
+/// Remove tags of HTML and unescape the common entities.
+fn html_to_text(html: &str) -> String {
+    HTML_TAG_REGEX
+        .replace_all(html, " ")
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Count words of text, every CJK character is a word.
+pub fn count_words(text: &str) -> usizedb {
+    let mut words = 0;
+    let mut in_word = false;
+    for c in text.chars() {
+        if matches!(c, '\u{3040}'..='\u{30FF}' | '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}')
+        {
+            words += 1;
+            in_word = false;
+        } else if c.is_whitespace() {
+            in_word = false;
+        } else if !in_word {
+            words += 1;
+            in_word = true;
         }
     }
+    words
+}
+
+/// Escape `&`, `<`, `>`, `"` and `'` of text in HTML.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
 }
 
 const SPLIT_CHAR: &'static str = "~./*\\.~";
@@ -73,6 +323,17 @@ pub fn calc_hash<T: Hash + Sized>(obj: &T) -> u64 {
     hasher.finish()
 }
 
+/// HMAC-SHA256 of content keyed with `key` in hex.
+pub fn hmac_sha256(key: &str, content: &str) -> String {
+    let mut mac = Hmac::new(Sha256::new(), key.as_bytes());
+    mac.input(content.as_bytes());
+    mac.result()
+        .code()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
 pub async fn get_last_active_time(active_duration: Duration) -> usizedb {
     Utc::now()
         .checked_sub_signed(active_duration)
@@ -117,6 +378,45 @@ pub async fn exists_table(tx: &mut SqliteConnection, table_name: &str) -> bool {
     }
 }
 
+/// The maximum depth walked up or down the category tree.
+pub const MAX_CATEGORY_DEPTH: usizedb = 32;
+
+/// The category and its descendants as `down(id, depth)`, `id` is the SQL expression of category id.
+pub fn category_descendants_cte(id: &str) -> String {
+    format!(
+        "WITH RECURSIVE down(id, depth) AS (
+            SELECT {id}, 0
+            UNION ALL
+            SELECT c.id, down.depth + 1 FROM categories c
+            JOIN down ON c.parent_id = down.id
+            WHERE down.depth < {MAX_CATEGORY_DEPTH}
+        )"
+    )
+}
+
+/// Add the column to table created by older version, `definition` is the type and constraints of column.
+pub async fn add_column_if_not_exists(
+    tx: &mut SqliteConnection,
+    table_name: &str,
+    column: &str,
+    definition: &str,
+) -> Result<()> {
+    let exists = sqlx::query("SELECT 1 FROM pragma_table_info(?) WHERE name = ?")
+        .bind(table_name)
+        .bind(column)
+        .fetch_optional(&mut *tx)
+        .await?
+        .is_some();
+    if !exists {
+        sqlx::query(&format!(
+            "ALTER TABLE {table_name} ADD COLUMN {column} {definition}"
+        ))
+        .execute(&mut *tx)
+        .await?;
+    }
+    Ok(())
+}
+
 pub async fn increment_category_total_post(
     tx: &mut SqliteConnection,
     category_id: usizedb,
@@ -218,15 +518,38 @@ pub async fn increment_comment_like(
         .bind(likes)
         .bind(dislikes)
         .bind(id)
-        .execute(tx)
+        .execute(&mut *tx)
         .await?;
     if r.rows_affected() == 0 {
         error!("Can't increment comment likes and dislikes!");
+        return Ok(());
     }
 
+    let r = sqlx::query("SELECT likes, dislikes FROM comments WHERE id = ?")
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE comments SET best_score = ? WHERE id = ?")
+        .bind(wilson_score(r.get("likes"), r.get("dislikes")))
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
     Ok(())
 }
 
+/// Lower bound of Wilson score interval at 95% confidence, likes are the positive ratings.
+pub fn wilson_score(likes: usizedb, dislikes: usizedb) -> f64 {
+    let n = (likes + dislikes) as f64;
+    if n == 0.0 {
+        return 0.0;
+    }
+    let z = 1.96;
+    let p = likes as f64 / n;
+    (p + z * z / (2.0 * n) - z * ((p * (1.0 - p) + z * z / (4.0 * n)) / n).sqrt())
+        / (1.0 + z * z / n)
+}
+
 pub async fn increment_user_total_post(
     tx: &mut SqliteConnection,
     user_id: usizedb,
@@ -259,4 +582,210 @@ pub async fn increment_user_total_comment(
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+pub async fn decrement_category_total_post(
+    tx: &mut SqliteConnection,
+    category_id: usizedb,
+    count: usizedb,
+) -> Result<()> {
+    let r = sqlx::query("UPDATE categories SET total_post = MAX(total_post - ?, 0) WHERE id=?")
+        .bind(count)
+        .bind(category_id)
+        .execute(&mut *tx)
+        .await?;
+    if r.rows_affected() == 0 {
+        error!("Can't decrement total post!");
+    }
+
+    Ok(())
+}
+
+pub async fn decrement_post_total_comment(
+    tx: &mut SqliteConnection,
+    id: usizedb,
+    total_comment: usizedb,
+    total_comment_post: usizedb,
+) -> Result<()> {
+    let r = sqlx::query(
+        "UPDATE posts SET 
+        total_comment = MAX(total_comment - ?, 0),
+        total_comment_post = MAX(total_comment_post - ?, 0) WHERE id = ?",
+    )
+    .bind(total_comment)
+    .bind(total_comment_post)
+    .bind(id)
+    .execute(tx)
+    .await?;
+    if r.rows_affected() == 0 {
+        error!("Can't decrement total comment of post!");
+    }
+
+    Ok(())
+}
+
+pub async fn decrement_comment_total_sub_comments(
+    tx: &mut SqliteConnection,
+    id: usizedb,
+    count: usizedb,
+) -> Result<()> {
+    let r =
+        sqlx::query("UPDATE comments SET total_comment = MAX(total_comment - ?, 0) WHERE id = ?")
+            .bind(count)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    if r.rows_affected() == 0 {
+        error!("Can't decrement comment!");
+    }
+
+    Ok(())
+}
+
+pub async fn decrement_user_total_post(
+    tx: &mut SqliteConnection,
+    user_id: usizedb,
+    count: usizedb,
+) -> Result<()> {
+    let r = sqlx::query("UPDATE users SET total_post = MAX(total_post - ?, 0) WHERE id=?")
+        .bind(count)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    if r.rows_affected() == 0 {
+        error!("Can't decrement total post!");
+    }
+
+    Ok(())
+}
+
+pub async fn decrement_user_total_comment(
+    tx: &mut SqliteConnection,
+    user_id: usizedb,
+    count: usizedb,
+) -> Result<()> {
+    let r = sqlx::query("UPDATE users SET total_comment = MAX(total_comment - ?, 0) WHERE id=?")
+        .bind(count)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    if r.rows_affected() == 0 {
+        error!("Can't decrement total comment!");
+    }
+
+    Ok(())
+}
+
+/// Recount the comment counters of a post and its comments, used after comments moved between posts.
+pub async fn recount_post_comments(tx: &mut SqliteConnection, post_id: usizedb) -> Result<()> {
+    sqlx::query(
+        "UPDATE comments SET 
+        total_comment = (SELECT COUNT(*) FROM comments r WHERE r.reply_comment_id = comments.id) 
+        WHERE post_id = ?",
+    )
+    .bind(post_id)
+    .execute(&mut *tx)
+    .await?;
+    let r = sqlx::query(
+        "UPDATE posts SET 
+        total_comment = (SELECT COUNT(*) FROM comments WHERE post_id = posts.id),
+        total_comment_post = (SELECT COUNT(*) FROM comments WHERE post_id = posts.id AND reply_comment_id = 0),
+        last_comment_at = COALESCE((SELECT created_at FROM comments WHERE post_id = posts.id ORDER BY created_at DESC LIMIT 1), 0),
+        last_comment_by_id = COALESCE((SELECT created_by_id FROM comments WHERE post_id = posts.id ORDER BY created_at DESC LIMIT 1), posts.created_by_id) 
+        WHERE id = ?",
+    )
+    .bind(post_id)
+    .execute(&mut *tx)
+    .await?;
+    if r.rows_affected() == 0 {
+        error!("Can't recount post comments!");
+    }
+
+    Ok(())
+}
+
+/// Mark the day of `since` timestamp as dirty, the daily stats from that day are recomputed.
+pub async fn mark_stats_dirty(tx: &mut SqliteConnection, since: usizedb) -> Result<()> {
+    sqlx::query("INSERT OR IGNORE INTO stats_dirty_days (day) VALUES (? / 86400)")
+        .bind(since)
+        .execute(&mut *tx)
+        .await?;
+    Ok(())
+}
+
+/// Mark the created day of post as dirty, called when the post stops or starts being counted.
+pub async fn mark_post_stats_dirty(tx: &mut SqliteConnection, post_id: usizedb) -> Result<()> {
+    sqlx::query(
+        "INSERT OR IGNORE INTO stats_dirty_days (day) SELECT created_at / 86400 FROM posts WHERE id = ?",
+    )
+    .bind(post_id)
+    .execute(&mut *tx)
+    .await?;
+    Ok(())
+}
+
+/// Mark the created day of comment as dirty, called when the comment stops or starts being counted.
+pub async fn mark_comment_stats_dirty(
+    tx: &mut SqliteConnection,
+    comment_id: usizedb,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT OR IGNORE INTO stats_dirty_days (day) SELECT created_at / 86400 FROM comments WHERE id = ?",
+    )
+    .bind(comment_id)
+    .execute(&mut *tx)
+    .await?;
+    Ok(())
+}
+
+/// Add the counts to today's stats of category, day is the days since epoch.
+pub async fn increment_category_daily_stats(
+    tx: &mut SqliteConnection,
+    category_id: usizedb,
+    posts: usizedb,
+    comments: usizedb,
+    views: usizedb,
+    likes: usizedb,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO category_daily_stats (category_id, day, posts, comments, views, likes) VALUES (?, ?, ?, ?, ?, ?)
+        ON CONFLICT(category_id, day) DO UPDATE SET posts = posts + excluded.posts, comments = comments + excluded.comments,
+        views = views + excluded.views, likes = likes + excluded.likes",
+    )
+    .bind(category_id)
+    .bind(Utc::now().timestamp() as usizedb / 86400)
+    .bind(posts)
+    .bind(comments)
+    .bind(views)
+    .bind(likes)
+    .execute(&mut *tx)
+    .await?;
+    Ok(())
+}
+
+/// Count the user as an active poster of category today, the user is counted once per day.
+pub async fn add_category_daily_poster(
+    tx: &mut SqliteConnection,
+    category_id: usizedb,
+    user_id: usizedb,
+) -> Result<()> {
+    let day = Utc::now().timestamp() as usizedb / 86400;
+    let r = sqlx::query(
+        "INSERT OR IGNORE INTO category_daily_posters (category_id, day, user_id) VALUES (?, ?, ?)",
+    )
+    .bind(category_id)
+    .bind(day)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    if r.rows_affected() > 0 {
+        sqlx::query(
+            "INSERT INTO category_daily_stats (category_id, day, active_posters) VALUES (?, ?, 1)
+            ON CONFLICT(category_id, day) DO UPDATE SET active_posters = active_posters + 1",
+        )
+        .bind(category_id)
+        .bind(day)
+        .execute(&mut *tx)
+        .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,382 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::SqliteConnection;
+use tokio::task::JoinHandle;
+
+use comment_system::model::CommentStatus;
+use fofo_utils::usizedb;
+use like_system::model::LikeStatusFlag;
+use post_system::model::PostStatus;
+use shared_core::SharedCore;
+
+use model::{CategoryDailyStats, LeaderboardEntry, LeaderboardSort};
+
+pub mod model;
+
+const DAY_SECONDS: usizedb = 86400;
+
+/// Leaderboard of users and stats of categories. The user stats are materialized from posts, comments
+/// and likes, the category stats are counted by the batch tasks of posts, comments, views and likes.
+#[derive(Debug, Clone)]
+pub struct LeaderboardSystem {
+    core: SharedCore,
+    materialize_task: Option<Arc<JoinHandle<()>>>,
+}
+
+impl LeaderboardSystem {
+    pub async fn new(core: SharedCore) -> Self {
+        let mut tx = core.begin_unwrap(true).await;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS user_daily_stats(
+                user_id INT NOT NULL,
+                category_id INT NOT NULL,
+                day INT NOT NULL,
+                posts INT NOT NULL DEFAULT 0,
+                comments INT NOT NULL DEFAULT 0,
+                likes_received INT NOT NULL DEFAULT 0,
+
+                PRIMARY KEY(user_id, category_id, day)
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS category_daily_stats(
+                category_id INT NOT NULL,
+                day INT NOT NULL,
+                posts INT NOT NULL DEFAULT 0,
+                comments INT NOT NULL DEFAULT 0,
+                active_posters INT NOT NULL DEFAULT 0,
+                views INT NOT NULL DEFAULT 0,
+                likes INT NOT NULL DEFAULT 0,
+
+                PRIMARY KEY(category_id, day)
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
+        // users posted or commented in the category on the day, for counting active posters once.
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS category_daily_posters(
+                category_id INT NOT NULL,
+                day INT NOT NULL,
+                user_id INT NOT NULL,
+
+                PRIMARY KEY(category_id, day, user_id)
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS user_daily_stats_day_index
+            on user_daily_stats (day, category_id);",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap(); // create indexes.
+
+        // days to recompute, the task recomputes from the earliest one up to today.
+        sqlx::query("CREATE TABLE IF NOT EXISTS stats_dirty_days(day INTEGER PRIMARY KEY)")
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+
+        // build the history once, from the first post.
+        let built: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM user_daily_stats)")
+            .fetch_one(tx.as_mut())
+            .await
+            .unwrap();
+        if !built {
+            sqlx::query(
+                "INSERT OR IGNORE INTO stats_dirty_days (day) SELECT MIN(created_at) / ? FROM posts HAVING COUNT(*) > 0",
+            )
+            .bind(DAY_SECONDS)
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+        }
+
+        // build the history of categories once, the batch tasks count the later activity.
+        let built: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM category_daily_stats)")
+            .fetch_one(tx.as_mut())
+            .await
+            .unwrap();
+        if !built {
+            LeaderboardSystem::build_category_stats(tx.as_mut())
+                .await
+                .unwrap();
+        }
+
+        tx.commit().await.unwrap();
+        let config = core.get_config();
+        let mut this = LeaderboardSystem {
+            core,
+            materialize_task: None,
+        };
+
+        let t = this.clone();
+        let recompute_seconds = config.leaderboard_recompute_seconds.max(1);
+        let materialize_task = tokio::spawn(async move {
+            loop {
+                if let Err(e) = t.materialize_dirty_days().await {
+                    tracing::error!("Can't materialize daily stats: {e}");
+                }
+                tokio::time::sleep(std::time::Duration::from_secs(recompute_seconds)).await;
+            }
+        });
+        this.materialize_task = Some(Arc::new(materialize_task));
+        this
+    }
+
+    /// Recompute the dirty days one day per transaction, from the earliest dirty day up to today.
+    /// Yesterday is always recomputed since its likes and views may arrive after the last run.
+    pub async fn materialize_dirty_days(&self) -> Result<()> {
+        let today = Utc::now().timestamp() as usizedb / DAY_SECONDS;
+        let mut tx = self.core.begin_unwrap(true).await;
+        sqlx::query("INSERT OR IGNORE INTO stats_dirty_days (day) VALUES (?)")
+            .bind(today - 1)
+            .execute(tx.as_mut())
+            .await?;
+        tx.commit().await?;
+
+        loop {
+            let mut tx = self.core.begin_unwrap(true).await;
+            let day: Option<usizedb> = sqlx::query_scalar("SELECT MIN(day) FROM stats_dirty_days")
+                .fetch_one(tx.as_mut())
+                .await?;
+            let Some(day) = day else {
+                return Ok(());
+            };
+            self.materialize(tx.as_mut(), day).await?;
+            sqlx::query("DELETE FROM stats_dirty_days WHERE day = ?")
+                .bind(day)
+                .execute(tx.as_mut())
+                .await?;
+            if day < today {
+                sqlx::query("INSERT OR IGNORE INTO stats_dirty_days (day) VALUES (?)")
+                    .bind(day + 1)
+                    .execute(tx.as_mut())
+                    .await?;
+            }
+            tx.commit().await?;
+        }
+    }
+
+    /// Recompute the daily stats of users and the likes of categories on `day`, day is the days since epoch.
+    /// Likes of categories are recounted since unlikes, voided votes and purges don't decrement them.
+    pub async fn materialize(&self, tx: &mut SqliteConnection, day: usizedb) -> Result<()> {
+        let from = day * DAY_SECONDS;
+        let to = from + DAY_SECONDS;
+        sqlx::query("DELETE FROM user_daily_stats WHERE day = ?")
+            .bind(day)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "INSERT INTO user_daily_stats (user_id, category_id, day, posts, comments, likes_received)
+            SELECT user_id, category_id, ?1, SUM(posts), SUM(comments), SUM(likes) FROM (
+                SELECT created_by_id AS user_id, category_id, 1 AS posts, 0 AS comments, 0 AS likes
+                FROM posts WHERE status NOT IN (?2, ?3) AND created_at >= ?5 AND created_at < ?6
+                UNION ALL
+                SELECT created_by_id, category_id, 0, 1, 0
+                FROM comments WHERE status != ?4 AND created_at >= ?5 AND created_at < ?6
+                UNION ALL
+                SELECT p.created_by_id, p.category_id, 0, 0, 1
+                FROM all_like_status l JOIN posts p ON p.id = l.flag_ref_id
+                WHERE l.flag = ?7 AND l.is_like = 1 AND l.reaction = '' AND p.status NOT IN (?2, ?3)
+                AND l.created_at >= ?5 AND l.created_at < ?6
+                UNION ALL
+                SELECT c.created_by_id, c.category_id, 0, 0, 1
+                FROM all_like_status l JOIN comments c ON c.id = l.flag_ref_id
+                WHERE l.flag = ?8 AND l.is_like = 1 AND l.reaction = '' AND c.status != ?4
+                AND l.created_at >= ?5 AND l.created_at < ?6
+            ) GROUP BY user_id, category_id",
+        )
+        .bind(day)
+        .bind(PostStatus::Deleted)
+        .bind(PostStatus::Pending)
+        .bind(CommentStatus::Deleted)
+        .bind(from)
+        .bind(to)
+        .bind(LikeStatusFlag::TargetPost)
+        .bind(LikeStatusFlag::TargetComment)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE category_daily_stats SET likes = 0 WHERE day = ?")
+            .bind(day)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "INSERT INTO category_daily_stats (category_id, day, likes)
+            SELECT category_id, ?1, COUNT(*) FROM (
+                SELECT p.category_id FROM all_like_status l JOIN posts p ON p.id = l.flag_ref_id
+                WHERE l.flag = ?7 AND l.is_like = 1 AND l.reaction = '' AND p.status NOT IN (?2, ?3)
+                AND l.created_at >= ?5 AND l.created_at < ?6
+                UNION ALL
+                SELECT c.category_id FROM all_like_status l JOIN comments c ON c.id = l.flag_ref_id
+                WHERE l.flag = ?8 AND l.is_like = 1 AND l.reaction = '' AND c.status != ?4
+                AND l.created_at >= ?5 AND l.created_at < ?6
+            ) WHERE true GROUP BY category_id
+            ON CONFLICT(category_id, day) DO UPDATE SET likes = excluded.likes",
+        )
+        .bind(day)
+        .bind(PostStatus::Deleted)
+        .bind(PostStatus::Pending)
+        .bind(CommentStatus::Deleted)
+        .bind(from)
+        .bind(to)
+        .bind(LikeStatusFlag::TargetPost)
+        .bind(LikeStatusFlag::TargetComment)
+        .execute(&mut *tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Count the daily stats of categories from the existing posts, comments, views and likes.
+    async fn build_category_stats(tx: &mut SqliteConnection) -> Result<()> {
+        let today = Utc::now().timestamp() as usizedb / DAY_SECONDS;
+        sqlx::query(
+            "INSERT OR IGNORE INTO category_daily_posters (category_id, day, user_id)
+            SELECT category_id, ?1, created_by_id FROM posts WHERE status NOT IN (?2, ?3) AND created_at >= ?5
+            UNION
+            SELECT category_id, ?1, created_by_id FROM comments WHERE status != ?4 AND created_at >= ?5",
+        )
+        .bind(today)
+        .bind(PostStatus::Deleted)
+        .bind(PostStatus::Pending)
+        .bind(CommentStatus::Deleted)
+        .bind(today * DAY_SECONDS)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO category_daily_stats (category_id, day, posts, comments, active_posters, views, likes)
+            SELECT category_id, day, SUM(posts), SUM(comments), SUM(active_posters), SUM(views), SUM(likes) FROM (
+                SELECT category_id, day, SUM(is_post) AS posts, SUM(1 - is_post) AS comments,
+                COUNT(DISTINCT user_id) AS active_posters, 0 AS views, 0 AS likes FROM (
+                    SELECT category_id, created_at / ?1 AS day, created_by_id AS user_id, 1 AS is_post
+                    FROM posts WHERE status NOT IN (?2, ?3)
+                    UNION ALL
+                    SELECT category_id, created_at / ?1, created_by_id, 0
+                    FROM comments WHERE status != ?4
+                ) GROUP BY category_id, day
+                UNION ALL
+                SELECT p.category_id, v.day / ?1, 0, 0, 0, SUM(v.views), 0
+                FROM post_daily_views v JOIN posts p ON p.id = v.post_id
+                WHERE p.status NOT IN (?2, ?3)
+                GROUP BY p.category_id, v.day / ?1
+                UNION ALL
+                SELECT p.category_id, l.created_at / ?1, 0, 0, 0, 0, COUNT(*)
+                FROM all_like_status l JOIN posts p ON p.id = l.flag_ref_id
+                WHERE l.flag = ?5 AND l.is_like = 1 AND l.reaction = '' AND p.status NOT IN (?2, ?3)
+                GROUP BY p.category_id, l.created_at / ?1
+                UNION ALL
+                SELECT c.category_id, l.created_at / ?1, 0, 0, 0, 0, COUNT(*)
+                FROM all_like_status l JOIN comments c ON c.id = l.flag_ref_id
+                WHERE l.flag = ?6 AND l.is_like = 1 AND l.reaction = '' AND c.status != ?4
+                GROUP BY c.category_id, l.created_at / ?1
+            ) GROUP BY category_id, day",
+        )
+        .bind(DAY_SECONDS)
+        .bind(PostStatus::Deleted)
+        .bind(PostStatus::Pending)
+        .bind(CommentStatus::Deleted)
+        .bind(LikeStatusFlag::TargetPost)
+        .bind(LikeStatusFlag::TargetComment)
+        .execute(&mut *tx)
+        .await?;
+        Ok(())
+    }
+
+    /// Daily stats of category between `start` and `end` timestamp, days without activity are omitted.
+    pub async fn get_category_daily_stats(
+        &self,
+        tx: &mut SqliteConnection,
+        category_id: usizedb,
+        start: usizedb,
+        end: usizedb,
+        limit: usizedb,
+    ) -> Result<Vec<CategoryDailyStats>> {
+        Ok(sqlx::query_as(
+            "SELECT day * ?1 AS day, posts, comments, active_posters, views, likes
+            FROM category_daily_stats WHERE category_id = ?2 AND day >= ?3 AND day <= ?4
+            ORDER BY day LIMIT ?5",
+        )
+        .bind(DAY_SECONDS)
+        .bind(category_id)
+        .bind(start / DAY_SECONDS)
+        .bind(end / DAY_SECONDS)
+        .bind(limit)
+        .fetch_all(&mut *tx)
+        .await?)
+    }
+
+    /// Users ranked by `sort` since the timestamp in the categories, users without any count of `sort` are excluded.
+    pub async fn get_leaderboard(
+        &self,
+        tx: &mut SqliteConnection,
+        since: usizedb,
+        category_ids: &[usizedb],
+        sort: LeaderboardSort,
+        index: usizedb,
+        limit: usizedb,
+    ) -> Result<Vec<LeaderboardEntry>> {
+        if category_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        let col = sort.to_col();
+        let ids = category_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+        Ok(sqlx::query_as::<_, LeaderboardEntry>(&format!(
+            "SELECT user_id, SUM(posts) AS posts, SUM(comments) AS comments, SUM(likes_received) AS likes_received
+            FROM user_daily_stats WHERE day >= ? AND category_id IN ({ids})
+            GROUP BY user_id HAVING SUM({col}) > 0
+            ORDER BY SUM({col}) DESC, user_id LIMIT ? OFFSET ?"
+        ))
+        .bind(since / DAY_SECONDS)
+        .bind(limit)
+        .bind(index * limit)
+        .fetch_all(&mut *tx)
+        .await?)
+    }
+
+    pub async fn get_leaderboard_count(
+        &self,
+        tx: &mut SqliteConnection,
+        since: usizedb,
+        category_ids: &[usizedb],
+        sort: LeaderboardSort,
+    ) -> Result<usizedb> {
+        if category_ids.is_empty() {
+            return Ok(0);
+        }
+        let col = sort.to_col();
+        let ids = category_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+        let (count,): (usizedb,) = sqlx::query_as(&format!(
+            "SELECT COUNT(*) FROM (
+                SELECT user_id FROM user_daily_stats WHERE day >= ? AND category_id IN ({ids})
+                GROUP BY user_id HAVING SUM({col}) > 0
+            )"
+        ))
+        .bind(since / DAY_SECONDS)
+        .fetch_one(&mut *tx)
+        .await?;
+        Ok(count)
+    }
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+use sqlx::FromRow;
+
+use fofo_utils::usizedb;
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct LeaderboardEntry {
+    pub user_id: usizedb,
+    pub posts: usizedb,
+    pub comments: usizedb,
+    pub likes_received: usizedb,
+}
+
+/// Aggregated activity of category in a day.
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct CategoryDailyStats {
+    /// Timestamp of the day start in UTC.
+    pub day: usizedb,
+    pub posts: usizedb,
+    pub comments: usizedb,
+    /// Users who posted or commented in the day.
+    pub active_posters: usizedb,
+    pub views: usizedb,
+    pub likes: usizedb,
+}
+
+#[derive(Debug, Serialize_repr, Deserialize_repr, PartialEq, Clone, Copy)]
+#[repr(u8)]
+pub enum LeaderboardSort {
+    Posts,
+    Comments,
+    LikesReceived,
+}
+
+impl LeaderboardSort {
+    pub fn to_col(&self) -> &'static str {
+        match self {
+            LeaderboardSort::Posts => "posts",
+            LeaderboardSort::Comments => "comments",
+            LeaderboardSort::LikesReceived => "likes_received",
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use anyhow::{bail, Result};
 use chrono::Utc;
@@ -8,7 +8,10 @@ use channel_cache::ChannelCacheTask;
 use fofo_utils::usizedb;
 use shared_core::SharedCore;
 
-use model::{LikeStatus, LikeStatusFlag};
+use model::{
+    LikeEvent, LikeEventAction, LikeStatus, LikeStatusFlag, MutualVotePair, SharedIpVoters,
+    VoteBurst, VoteOrigin, VoteReport, VoteReportOptions,
+};
 
 pub mod model;
 
@@ -16,6 +19,7 @@ pub mod model;
 pub struct LikeSystem {
     core: SharedCore,
     like_status_task: Option<ChannelCacheTask<SetLikeStatusBundle, ()>>,
+    vote_ip_secret: Arc<str>,
 }
 
 #[derive(Debug, Clone)]
@@ -24,6 +28,9 @@ struct SetLikeStatusBundle {
     flag: LikeStatusFlag,
     user_id: usizedb,
     is_like: Option<bool>,
+    /// Empty if it is like or dislike, otherwise `is_like` is some when the reaction is added.
+    reaction: String,
+    origin: VoteOrigin,
 }
 
 impl LikeSystem {
@@ -37,6 +44,7 @@ impl LikeSystem {
                 created_at INT NOT NULL,
                 created_by_id INT NOT NULL,
                 is_like BOOLEAN NOT NULL,
+                reaction TEXT NOT NULL DEFAULT '',
 
                 FOREIGN KEY(created_by_id) REFERENCES users(id)
             )",
@@ -45,21 +53,82 @@ impl LikeSystem {
         .await
         .unwrap();
 
+        // column added after the table is created.
+        fofo_utils::add_column_if_not_exists(
+            tx.as_mut(),
+            "all_like_status",
+            "reaction",
+            "TEXT NOT NULL DEFAULT ''",
+        )
+        .await
+        .unwrap();
+
         sqlx::query(
             "CREATE INDEX IF NOT EXISTS like_status_m1_index
             on all_like_status (flag_ref_id, flag, created_by_id);
             CREATE INDEX IF NOT EXISTS like_status_m2_index
-            on all_like_status (flag_ref_id, flag, created_by_id, is_like);",
+            on all_like_status (flag_ref_id, flag, created_by_id, is_like);
+            CREATE INDEX IF NOT EXISTS like_status_m3_index
+            on all_like_status (flag_ref_id, flag, reaction);",
         )
         .execute(tx.as_mut())
         .await
         .unwrap(); // create indexes.
 
-        tx.commit().await.unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS like_events(
+                id INTEGER PRIMARY KEY,
+                flag INT NOT NULL,
+                flag_ref_id INT NOT NULL,
+                created_by_id INT NOT NULL,
+                target_user_id INT NOT NULL,
+                ip_hash TEXT NULL,
+                action INT NOT NULL,
+                reaction TEXT NOT NULL DEFAULT '',
+                created_at INT NOT NULL
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS like_events_created_by_id_index
+            on like_events (created_by_id, created_at);
+            CREATE INDEX IF NOT EXISTS like_events_created_at_index
+            on like_events (created_at, action);",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap(); // create indexes.
+
+        // generated once and kept, so ip hashes stay comparable across restarts.
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS vote_ip_secret(
+                id INTEGER PRIMARY KEY,
+                secret TEXT NOT NULL
+            );
+            INSERT OR IGNORE INTO vote_ip_secret (id, secret) VALUES (1, lower(hex(randomblob(32))));",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
         let config = core.get_config();
+        let vote_ip_secret: Arc<str> = match config.vote_ip_secret.as_deref() {
+            Some(secret) if !secret.is_empty() => secret.into(),
+            _ => sqlx::query_scalar::<_, String>("SELECT secret FROM vote_ip_secret WHERE id = 1")
+                .fetch_one(tx.as_mut())
+                .await
+                .unwrap()
+                .into(),
+        };
+
+        tx.commit().await.unwrap();
         let mut this = LikeSystem {
             core,
             like_status_task: None,
+            vote_ip_secret,
         };
 
         let t = this.clone();
@@ -81,6 +150,11 @@ impl LikeSystem {
         this
     }
 
+    /// The configured `vote_ip_secret`, or the one generated and stored in the database.
+    pub fn get_vote_ip_secret(&self) -> &str {
+        &self.vote_ip_secret
+    }
+
     pub async fn get_like_status(
         &self,
         tx: &mut SqliteConnection,
@@ -89,7 +163,7 @@ impl LikeSystem {
         user_id: usizedb,
     ) -> Result<Option<LikeStatus>> {
         Ok(sqlx::query_as::<_, LikeStatus>(
-            "SELECT * FROM all_like_status WHERE flag_ref_id = ? AND flag = ? AND created_by_id = ? LIMIT 1",
+            "SELECT * FROM all_like_status WHERE flag_ref_id = ? AND flag = ? AND created_by_id = ? AND reaction = '' LIMIT 1",
         )
         .bind(flag_ref_id)
         .bind(flag)
@@ -104,6 +178,7 @@ impl LikeSystem {
         flag: LikeStatusFlag,
         user_id: usizedb,
         is_like: Option<bool>,
+        origin: VoteOrigin,
     ) -> Result<()> {
         match self.like_status_task.as_ref() {
             Some(task) => {
@@ -113,6 +188,8 @@ impl LikeSystem {
                         flag,
                         user_id,
                         is_like,
+                        reaction: String::new(),
+                        origin,
                     })
                     .await?;
                 Ok(like_status)
@@ -121,6 +198,327 @@ impl LikeSystem {
         }
     }
 
+    /// Add or remove the reaction of user, it is sent to the same task as like status.
+    pub async fn set_reaction(
+        &self,
+        flag_ref_id: usizedb,
+        flag: LikeStatusFlag,
+        user_id: usizedb,
+        reaction: String,
+        reacted: bool,
+        origin: VoteOrigin,
+    ) -> Result<()> {
+        match self.like_status_task.as_ref() {
+            Some(task) => {
+                task.send(SetLikeStatusBundle {
+                    flag_ref_id,
+                    flag,
+                    user_id,
+                    is_like: reacted.then_some(true),
+                    reaction,
+                    origin,
+                })
+                .await?;
+                Ok(())
+            }
+            None => bail!("Don't have the task."),
+        }
+    }
+
+    /// Count of each reaction, key is the target id.
+    pub async fn get_reaction_counts(
+        &self,
+        tx: &mut SqliteConnection,
+        flag: LikeStatusFlag,
+        flag_ref_ids: &[usizedb],
+    ) -> Result<HashMap<usizedb, HashMap<String, usizedb>>> {
+        let mut map: HashMap<usizedb, HashMap<String, usizedb>> = HashMap::new();
+        if flag_ref_ids.is_empty() {
+            return Ok(map);
+        }
+        let ids = flag_ref_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+        let rows: Vec<(usizedb, String, usizedb)> = sqlx::query_as(&format!(
+            "SELECT flag_ref_id, reaction, COUNT(*) FROM all_like_status
+            WHERE flag = ? AND flag_ref_id IN ({ids}) AND reaction != ''
+            GROUP BY flag_ref_id, reaction"
+        ))
+        .bind(flag)
+        .fetch_all(&mut *tx)
+        .await?;
+        for (flag_ref_id, reaction, count) in rows {
+            map.entry(flag_ref_id).or_default().insert(reaction, count);
+        }
+        Ok(map)
+    }
+
+    /// Reactions of user, key is the target id.
+    pub async fn get_user_reactions(
+        &self,
+        tx: &mut SqliteConnection,
+        flag: LikeStatusFlag,
+        flag_ref_ids: &[usizedb],
+        user_id: usizedb,
+    ) -> Result<HashMap<usizedb, Vec<String>>> {
+        let mut map: HashMap<usizedb, Vec<String>> = HashMap::new();
+        if flag_ref_ids.is_empty() {
+            return Ok(map);
+        }
+        let ids = flag_ref_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+        let rows: Vec<(usizedb, String)> = sqlx::query_as(&format!(
+            "SELECT flag_ref_id, reaction FROM all_like_status
+            WHERE flag = ? AND flag_ref_id IN ({ids}) AND created_by_id = ? AND reaction != ''
+            ORDER BY id"
+        ))
+        .bind(flag)
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        for (flag_ref_id, reaction) in rows {
+            map.entry(flag_ref_id).or_default().push(reaction);
+        }
+        Ok(map)
+    }
+
+    /// Vote events of user, the newest first.
+    pub async fn get_like_events(
+        &self,
+        tx: &mut SqliteConnection,
+        user_id: usizedb,
+        index: usizedb,
+        limit: usizedb,
+    ) -> Result<Vec<LikeEvent>> {
+        Ok(sqlx::query_as::<_, LikeEvent>(
+            "SELECT * FROM like_events WHERE created_by_id = ? ORDER BY id DESC LIMIT ? OFFSET ?",
+        )
+        .bind(user_id)
+        .bind(limit)
+        .bind(index * limit)
+        .fetch_all(&mut *tx)
+        .await?)
+    }
+
+    pub async fn get_like_events_count(
+        &self,
+        tx: &mut SqliteConnection,
+        user_id: usizedb,
+    ) -> Result<usizedb> {
+        let (count,): (usizedb,) =
+            sqlx::query_as("SELECT COUNT(*) FROM like_events WHERE created_by_id = ?")
+                .bind(user_id)
+                .fetch_one(&mut *tx)
+                .await?;
+        Ok(count)
+    }
+
+    /// Find suspicious votes in the events, each part of report is limited by `limit`.
+    pub async fn get_vote_report(
+        &self,
+        tx: &mut SqliteConnection,
+        options: VoteReportOptions,
+        limit: usizedb,
+    ) -> Result<VoteReport> {
+        // distinct targets liked, flag is 0 or 1.
+        let mutual_pairs = sqlx::query_as::<_, MutualVotePair>(
+            "WITH given AS (
+                SELECT created_by_id AS user_id, target_user_id,
+                COUNT(DISTINCT flag_ref_id * 2 + flag) AS total
+                FROM like_events
+                WHERE action = ? AND created_at >= ? AND created_at < ?
+                AND created_by_id != target_user_id
+                GROUP BY created_by_id, target_user_id
+            ), total_given AS (
+                SELECT user_id, SUM(total) AS total FROM given GROUP BY user_id
+            )
+            SELECT a.user_id, a.target_user_id AS other_user_id,
+            a.total AS likes_given, b.total AS likes_received,
+            ta.total AS total_given, tb.total AS other_total_given
+            FROM given a
+            JOIN given b ON b.user_id = a.target_user_id AND b.target_user_id = a.user_id
+            JOIN total_given ta ON ta.user_id = a.user_id
+            JOIN total_given tb ON tb.user_id = b.user_id
+            WHERE a.user_id < a.target_user_id AND a.total >= ? AND b.total >= ?
+            ORDER BY a.total + b.total DESC LIMIT ?",
+        )
+        .bind(LikeEventAction::Like)
+        .bind(options.start)
+        .bind(options.end)
+        .bind(options.min_votes)
+        .bind(options.min_votes)
+        .bind(limit)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let rows: Vec<(LikeStatusFlag, usizedb, usizedb, usizedb, String)> = sqlx::query_as(
+            "SELECT e.flag, e.flag_ref_id, e.created_at / ? * ? AS started_at,
+            COUNT(DISTINCT e.created_by_id) AS total, GROUP_CONCAT(DISTINCT e.created_by_id)
+            FROM like_events e JOIN users u ON u.id = e.created_by_id
+            WHERE e.action IN (?, ?) AND e.created_at >= ? AND e.created_at < ?
+            AND u.created_at >= e.created_at - ?
+            GROUP BY e.flag, e.flag_ref_id, started_at
+            HAVING total >= ?
+            ORDER BY total DESC LIMIT ?",
+        )
+        .bind(options.burst_seconds)
+        .bind(options.burst_seconds)
+        .bind(LikeEventAction::Like)
+        .bind(LikeEventAction::Dislike)
+        .bind(options.start)
+        .bind(options.end)
+        .bind(options.new_account_seconds)
+        .bind(options.min_votes)
+        .bind(limit)
+        .fetch_all(&mut *tx)
+        .await?;
+        let bursts = rows
+            .into_iter()
+            .map(
+                |(flag, flag_ref_id, started_at, total, user_ids)| VoteBurst {
+                    flag,
+                    flag_ref_id,
+                    started_at,
+                    total,
+                    user_ids: parse_ids(&user_ids),
+                },
+            )
+            .collect();
+
+        let rows: Vec<(String, usizedb, String)> = sqlx::query_as(
+            "SELECT ip_hash, COUNT(*) AS total, GROUP_CONCAT(DISTINCT created_by_id)
+            FROM like_events
+            WHERE ip_hash IS NOT NULL AND action IN (?, ?) AND created_at >= ? AND created_at < ?
+            GROUP BY ip_hash
+            HAVING COUNT(DISTINCT created_by_id) > 1 AND total >= ?
+            ORDER BY total DESC LIMIT ?",
+        )
+        .bind(LikeEventAction::Like)
+        .bind(LikeEventAction::Dislike)
+        .bind(options.start)
+        .bind(options.end)
+        .bind(options.min_votes)
+        .bind(limit)
+        .fetch_all(&mut *tx)
+        .await?;
+        let shared_ips = rows
+            .into_iter()
+            .map(|(ip_hash, total, user_ids)| SharedIpVoters {
+                ip_hash,
+                total,
+                user_ids: parse_ids(&user_ids),
+            })
+            .collect();
+
+        Ok(VoteReport {
+            mutual_pairs,
+            bursts,
+            shared_ips,
+        })
+    }
+
+    /// Remove all likes and dislikes of user and recompute the likes and dislikes of targets.
+    /// Reactions are kept. Return the voided votes.
+    pub async fn void_votes(
+        &self,
+        tx: &mut SqliteConnection,
+        user_id: usizedb,
+    ) -> Result<Vec<LikeStatus>> {
+        let statuses = sqlx::query_as::<_, LikeStatus>(
+            "SELECT * FROM all_like_status WHERE created_by_id = ? AND reaction = ''",
+        )
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        if statuses.is_empty() {
+            return Ok(statuses);
+        }
+
+        sqlx::query(
+            "INSERT INTO like_events (flag, flag_ref_id, created_by_id, target_user_id, ip_hash, action, reaction, created_at)
+            SELECT s.flag, s.flag_ref_id, s.created_by_id, COALESCE(CASE s.flag
+                WHEN ? THEN (SELECT created_by_id FROM posts WHERE id = s.flag_ref_id)
+                ELSE (SELECT created_by_id FROM comments WHERE id = s.flag_ref_id) END, 0),
+            NULL, ?, '', ?
+            FROM all_like_status s WHERE s.created_by_id = ? AND s.reaction = ''",
+        )
+        .bind(LikeStatusFlag::TargetPost)
+        .bind(LikeEventAction::Void)
+        .bind(Utc::now().timestamp() as usizedb)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        if let Some(earliest) = statuses
+            .iter()
+            .filter(|s| s.is_like)
+            .map(|s| s.created_at)
+            .min()
+        {
+            fofo_utils::mark_stats_dirty(tx, earliest).await?;
+        }
+        sqlx::query("DELETE FROM all_like_status WHERE created_by_id = ? AND reaction = ''")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for status in &statuses {
+            let (likes, dislikes) = if status.is_like { (-1, 0) } else { (0, -1) };
+            match status.flag {
+                LikeStatusFlag::TargetPost => {
+                    fofo_utils::increment_post_like(tx, status.flag_ref_id, likes, dislikes)
+                        .await?;
+                }
+                LikeStatusFlag::TargetComment => {
+                    fofo_utils::increment_comment_like(tx, status.flag_ref_id, likes, dislikes)
+                        .await?;
+                }
+            }
+        }
+
+        Ok(statuses)
+    }
+
+    async fn add_like_events(
+        &self,
+        tx: &mut SqliteConnection,
+        arr: &[SetLikeStatusBundle],
+        now: usizedb,
+    ) -> Result<()> {
+        if arr.is_empty() {
+            return Ok(());
+        }
+        QueryBuilder::new(
+            "INSERT INTO like_events (flag, flag_ref_id, created_by_id, target_user_id, ip_hash, action, reaction, created_at) ",
+        )
+        .push_values(arr, |mut b, bundle| {
+            let action = match (bundle.reaction.is_empty(), bundle.is_like) {
+                (true, Some(true)) => LikeEventAction::Like,
+                (true, Some(false)) => LikeEventAction::Dislike,
+                (true, None) => LikeEventAction::Cancel,
+                (false, Some(_)) => LikeEventAction::React,
+                (false, None) => LikeEventAction::Unreact,
+            };
+            b.push_bind(bundle.flag)
+                .push_bind(bundle.flag_ref_id)
+                .push_bind(bundle.user_id)
+                .push_bind(bundle.origin.target_user_id)
+                .push_bind(bundle.origin.ip_hash.clone())
+                .push_bind(action)
+                .push_bind(bundle.reaction.clone())
+                .push_bind(now);
+        })
+        .build()
+        .execute(&mut *tx)
+        .await?;
+
+        Ok(())
+    }
+
     // The function to set the post likes status
     async fn set_like_status_all(
         &self,
@@ -135,36 +533,66 @@ impl LikeSystem {
             list
         };
 
+        let now = Utc::now().timestamp() as usizedb;
+        // all actions are recorded, including the overwritten ones.
+        self.add_like_events(tx, &arr, now).await?;
+
         let mut like_statuses = Vec::with_capacity(arr.len());
         let bundles_by_map = {
             // Check if array exists data is same action from user.
             arr.reverse();
             let mut map = HashMap::with_capacity(arr.len());
             for bundle in arr {
-                map.entry((bundle.flag, bundle.flag_ref_id, bundle.user_id))
-                    .and_modify(|v| *v = bundle.clone())
-                    .or_insert(bundle);
+                map.entry((
+                    bundle.flag,
+                    bundle.flag_ref_id,
+                    bundle.user_id,
+                    bundle.reaction.clone(),
+                ))
+                .and_modify(|v| *v = bundle.clone())
+                .or_insert(bundle);
             }
             let bundles: Vec<SetLikeStatusBundle> =
                 map.into_iter().map(|(_key, bundle)| bundle).collect();
             let mut map = HashMap::with_capacity(bundles.len());
             for b in bundles {
-                map.entry((b.flag, b.flag_ref_id))
+                map.entry((b.flag, b.flag_ref_id, b.reaction.clone()))
                     .and_modify(|v: &mut Vec<SetLikeStatusBundle>| v.push(b.clone()))
                     .or_insert(vec![b]);
             }
             map
         };
 
-        let now = Utc::now().timestamp() as usizedb;
-        for ((flag, flag_ref_id), bundles) in bundles_by_map {
+        for ((flag, flag_ref_id, reaction), bundles) in bundles_by_map {
             let ids = bundles
                 .iter()
                 .map(|b| b.user_id.to_string())
                 .collect::<Vec<String>>()
                 .join(",");
-            let likes_deleted = sqlx::query(&format!("DELETE FROM all_like_status WHERE flag=? AND flag_ref_id=? AND created_by_id IN ({ids}) AND is_like=1")).bind(flag).bind(flag_ref_id).execute(&mut *tx).await?.rows_affected();
-            let dislikes_deleted = sqlx::query(&format!("DELETE FROM all_like_status WHERE flag=? AND flag_ref_id=? AND created_by_id IN ({ids}) AND is_like=0")).bind(flag).bind(flag_ref_id).execute(&mut *tx).await?.rows_affected();
+            if !reaction.is_empty() {
+                // reactions are not counted to likes and dislikes.
+                sqlx::query(&format!("DELETE FROM all_like_status WHERE flag=? AND flag_ref_id=? AND created_by_id IN ({ids}) AND reaction=?")).bind(flag).bind(flag_ref_id).bind(&reaction).execute(&mut *tx).await?;
+                for bundle in bundles {
+                    if bundle.is_like.is_some() {
+                        like_statuses.push(LikeStatus {
+                            flag: bundle.flag,
+                            flag_ref_id: bundle.flag_ref_id,
+                            created_at: now,
+                            created_by_id: bundle.user_id,
+                            is_like: true,
+                            reaction: bundle.reaction,
+                        })
+                    }
+                }
+                continue;
+            }
+            // likes of the past days are counted in daily stats.
+            let earliest: Option<usizedb> = sqlx::query_scalar(&format!("SELECT MIN(created_at) FROM all_like_status WHERE flag=? AND flag_ref_id=? AND created_by_id IN ({ids}) AND is_like=1 AND reaction=''")).bind(flag).bind(flag_ref_id).fetch_one(&mut *tx).await?;
+            if let Some(earliest) = earliest {
+                fofo_utils::mark_stats_dirty(tx, earliest).await?;
+            }
+            let likes_deleted = sqlx::query(&format!("DELETE FROM all_like_status WHERE flag=? AND flag_ref_id=? AND created_by_id IN ({ids}) AND is_like=1 AND reaction=''")).bind(flag).bind(flag_ref_id).execute(&mut *tx).await?.rows_affected();
+            let dislikes_deleted = sqlx::query(&format!("DELETE FROM all_like_status WHERE flag=? AND flag_ref_id=? AND created_by_id IN ({ids}) AND is_like=0 AND reaction=''")).bind(flag).bind(flag_ref_id).execute(&mut *tx).await?.rows_affected();
             let mut total_like = 0; // like of this bundle effect to likes_map;
             let mut total_dislike = 0; // dislike of this bundle effect to dislikes_map;
 
@@ -182,43 +610,67 @@ impl LikeSystem {
                         created_at: now,
                         created_by_id: bundle.user_id.to_owned(),
                         is_like: is_like.to_owned(),
+                        reaction: String::new(),
                     })
                 }
             }
             let total_like_insert = total_like - likes_deleted as i64;
             let total_dislike_insert = total_dislike - dislikes_deleted as i64;
+            if total_like_insert > 0 {
+                let table = match flag {
+                    LikeStatusFlag::TargetPost => "posts",
+                    LikeStatusFlag::TargetComment => "comments",
+                };
+                let category_id: Option<usizedb> =
+                    sqlx::query_scalar(&format!("SELECT category_id FROM {table} WHERE id = ?"))
+                        .bind(flag_ref_id)
+                        .fetch_optional(&mut *tx)
+                        .await?;
+                if let Some(category_id) = category_id {
+                    fofo_utils::increment_category_daily_stats(
+                        tx,
+                        category_id,
+                        0,
+                        0,
+                        0,
+                        total_like_insert as usizedb,
+                    )
+                    .await?;
+                }
+            }
             match flag {
                 LikeStatusFlag::TargetPost => {
                     fofo_utils::increment_post_like(
-                            tx,
-                            flag_ref_id,
-                            total_like_insert,
-                            total_dislike_insert,
-                        )
-                        .await?;
+                        tx,
+                        flag_ref_id,
+                        total_like_insert,
+                        total_dislike_insert,
+                    )
+                    .await?;
                 }
                 LikeStatusFlag::TargetComment => {
                     fofo_utils::increment_comment_like(
-                            tx,
-                            flag_ref_id,
-                            total_like_insert,
-                            total_dislike_insert,
-                        )
-                        .await?;
+                        tx,
+                        flag_ref_id,
+                        total_like_insert,
+                        total_dislike_insert,
+                    )
+                    .await?;
                 }
             }
         }
 
         if like_statuses.len() > 0 {
             QueryBuilder::new(
-                "INSERT INTO all_like_status (flag_ref_id, flag, created_at, created_by_id, is_like) ",
+                "INSERT INTO all_like_status (flag_ref_id, flag, created_at, created_by_id, is_like, reaction) ",
             )
             .push_values(like_statuses, |mut b, bundle| {
                 b.push_bind(bundle.flag_ref_id)
                     .push_bind(bundle.flag)
                     .push_bind(bundle.created_at)
                     .push_bind(bundle.created_by_id)
-                    .push_bind(bundle.is_like);
+                    .push_bind(bundle.is_like)
+                    .push_bind(bundle.reaction);
             })
             .build()
             .execute(&mut *tx)
@@ -229,3 +681,7 @@ impl LikeSystem {
         Ok(list)
     }
 }
+
+fn parse_ids(ids: &str) -> Vec<usizedb> {
+    ids.split(',').filter_map(|id| id.parse().ok()).collect()
+}
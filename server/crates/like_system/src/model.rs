@@ -12,6 +12,10 @@ pub struct LikeStatus {
     pub created_by_id: usizedb,
     pub created_at: usizedb,
     pub is_like: bool,
+    /// Emoji of reaction, empty if it is like or dislike.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub reaction: String,
 }
 
 #[derive(Debug, Deserialize_repr, Serialize_repr, PartialEq, Clone, Copy, sqlx::Type, Eq, PartialOrd, Ord, Hash)]
@@ -19,4 +23,91 @@ pub struct LikeStatus {
 pub enum LikeStatusFlag {
     TargetPost,
     TargetComment,
-}
\ No newline at end of file
+}
+/// Origin of the vote, it is recorded to the vote events.
+#[derive(Debug, Clone, Default)]
+pub struct VoteOrigin {
+    /// Author of the post or comment voted.
+    pub target_user_id: usizedb,
+    /// Hashed ip of the voter.
+    pub ip_hash: Option<String>,
+}
+
+/// Append-only event of like, dislike and reaction.
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct LikeEvent {
+    pub id: usizedb,
+    pub flag: LikeStatusFlag,
+    pub flag_ref_id: usizedb,
+    pub created_by_id: usizedb,
+    pub target_user_id: usizedb,
+    pub ip_hash: Option<String>,
+    pub action: LikeEventAction,
+    /// Emoji of reaction, empty if it is not a reaction.
+    pub reaction: String,
+    pub created_at: usizedb,
+}
+
+#[derive(Debug, Deserialize_repr, Serialize_repr, PartialEq, Clone, Copy, sqlx::Type)]
+#[repr(u8)]
+pub enum LikeEventAction {
+    Like,
+    Dislike,
+    /// Like or dislike is canceled.
+    Cancel,
+    React,
+    Unreact,
+    /// Like or dislike is voided by moderator.
+    Void,
+}
+
+#[derive(Debug, Clone)]
+pub struct VoteReportOptions {
+    pub start: usizedb,
+    pub end: usizedb,
+    /// The minimum votes flagged as suspicious.
+    pub min_votes: usizedb,
+    /// Accounts created in this duration before voting are new accounts, in second.
+    pub new_account_seconds: usizedb,
+    /// Window of burst votes, in second.
+    pub burst_seconds: usizedb,
+}
+
+/// Accounts liked the posts and comments of each other.
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct MutualVotePair {
+    pub user_id: usizedb,
+    pub other_user_id: usizedb,
+    /// Likes from `user_id` to `other_user_id`.
+    pub likes_given: usizedb,
+    /// Likes from `other_user_id` to `user_id`.
+    pub likes_received: usizedb,
+    /// All likes from `user_id`, the pair always upvotes each other if it is close to `likes_given`.
+    pub total_given: usizedb,
+    pub other_total_given: usizedb,
+}
+
+/// Votes to the same target from new accounts in a short window.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VoteBurst {
+    pub flag: LikeStatusFlag,
+    pub flag_ref_id: usizedb,
+    pub started_at: usizedb,
+    pub total: usizedb,
+    pub user_ids: Vec<usizedb>,
+}
+
+/// Different accounts voted from the same ip.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SharedIpVoters {
+    pub ip_hash: String,
+    pub total: usizedb,
+    pub user_ids: Vec<usizedb>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VoteReport {
+    pub mutual_pairs: Vec<MutualVotePair>,
+    pub bursts: Vec<VoteBurst>,
+    pub shared_ips: Vec<SharedIpVoters>,
+}
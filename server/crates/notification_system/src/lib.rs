@@ -3,10 +3,13 @@ use fofo_utils::usizedb;
 use shared_core::SharedCore;
 use anyhow::{bail, Result};
 use chrono::Utc;
-use futures::TryStreamExt;
+use futures::{future, TryStreamExt};
 use sqlx::{sqlite::SqliteRow, QueryBuilder, Row, SqliteConnection};
 
-use self::model::{UserNotification, UserNotificationArguments, UserNotificationType};
+use self::model::{
+    MentionFlag, Subscription, SubscriptionFlag, SubscriptionLevel, UserNotification,
+    UserNotificationArguments, UserNotificationType,
+};
 
 pub mod model;
 
@@ -32,12 +35,40 @@ impl NotificationSystem {
 
                 FOREIGN KEY(created_by_id) REFERENCES users(id),
                 FOREIGN KEY(target_user_id) REFERENCES users(id)
+            );
+            CREATE TABLE IF NOT EXISTS subscriptions(
+                id INTEGER PRIMARY KEY,
+                flag INT NOT NULL,
+                flag_ref_id INT NOT NULL,
+                user_id INT NOT NULL,
+                level INT NOT NULL,
+                created_at INT NOT NULL,
+
+                UNIQUE(user_id, flag, flag_ref_id),
+                FOREIGN KEY(user_id) REFERENCES users(id)
+            );
+            CREATE TABLE IF NOT EXISTS mentions(
+                flag INT NOT NULL,
+                flag_ref_id INT NOT NULL,
+                user_id INT NOT NULL,
+                created_at INT NOT NULL,
+
+                PRIMARY KEY(flag, flag_ref_id, user_id),
+                FOREIGN KEY(user_id) REFERENCES users(id)
             )",
         )
         .execute(tx.as_mut())
         .await
         .unwrap();
 
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS subscriptions_ref_index
+            on subscriptions (flag_ref_id, flag);",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap(); // create indexes.
+
         tx.commit().await.unwrap();
         let config = core.get_config();
         let mut this = NotificationSystem {
@@ -269,4 +300,275 @@ impl NotificationSystem {
             bail!("Update failed!")
         }
     }
+
+    pub async fn set_subscription(
+        &self,
+        tx: &mut SqliteConnection,
+        user_id: usizedb,
+        flag: SubscriptionFlag,
+        flag_ref_id: usizedb,
+        level: SubscriptionLevel,
+    ) -> Result<Subscription> {
+        let now = Utc::now().timestamp() as usizedb;
+        sqlx::query(
+            "INSERT INTO subscriptions (flag, flag_ref_id, user_id, level, created_at) VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(user_id, flag, flag_ref_id) DO UPDATE SET level = excluded.level",
+        )
+        .bind(flag)
+        .bind(flag_ref_id)
+        .bind(user_id)
+        .bind(level)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        Ok(self
+            .get_subscription(tx, user_id, flag, flag_ref_id)
+            .await?
+            .unwrap())
+    }
+
+    pub async fn remove_subscription(
+        &self,
+        tx: &mut SqliteConnection,
+        user_id: usizedb,
+        flag: SubscriptionFlag,
+        flag_ref_id: usizedb,
+    ) -> Result<bool> {
+        let r = sqlx::query(
+            "DELETE FROM subscriptions WHERE user_id = ? AND flag = ? AND flag_ref_id = ?",
+        )
+        .bind(user_id)
+        .bind(flag)
+        .bind(flag_ref_id)
+        .execute(&mut *tx)
+        .await?;
+        Ok(r.rows_affected() == 1)
+    }
+
+    pub async fn get_subscription(
+        &self,
+        tx: &mut SqliteConnection,
+        user_id: usizedb,
+        flag: SubscriptionFlag,
+        flag_ref_id: usizedb,
+    ) -> Result<Option<Subscription>> {
+        Ok(sqlx::query_as::<_, Subscription>(
+            "SELECT * FROM subscriptions WHERE user_id = ? AND flag = ? AND flag_ref_id = ? LIMIT 1",
+        )
+        .bind(user_id)
+        .bind(flag)
+        .bind(flag_ref_id)
+        .fetch_optional(&mut *tx)
+        .await?)
+    }
+
+    pub async fn get_subscriptions(
+        &self,
+        tx: &mut SqliteConnection,
+        user_id: usizedb,
+        flag: Option<SubscriptionFlag>,
+        index: usizedb,
+        limit: usizedb,
+    ) -> Result<Vec<Subscription>> {
+        let offset = index * limit;
+        let flag_q = match flag {
+            Some(flag) => format!("AND flag={}", flag as u8),
+            None => String::new(),
+        };
+        let q = format!("SELECT * FROM subscriptions WHERE user_id=? {flag_q} ORDER BY created_at DESC, id DESC LIMIT ? OFFSET ?");
+        Ok(sqlx::query_as::<_, Subscription>(&q)
+            .bind(user_id)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&mut *tx)
+            .await?)
+    }
+
+    pub async fn get_subscription_count(
+        &self,
+        tx: &mut SqliteConnection,
+        user_id: usizedb,
+        flag: Option<SubscriptionFlag>,
+    ) -> Result<usizedb> {
+        let flag_q = match flag {
+            Some(flag) => format!("AND flag={}", flag as u8),
+            None => String::new(),
+        };
+        let q = format!("SELECT COUNT(*) FROM subscriptions WHERE user_id=? {flag_q}");
+        let count: usizedb = sqlx::query_scalar(&q)
+            .bind(user_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        Ok(count)
+    }
+
+    /// Whether user muted the post, or its category without subscribing the post.
+    pub async fn is_muted(
+        &self,
+        tx: &mut SqliteConnection,
+        user_id: usizedb,
+        post_id: usizedb,
+        category_id: usizedb,
+    ) -> Result<bool> {
+        let level: Option<SubscriptionLevel> = sqlx::query_scalar(
+            "SELECT level FROM subscriptions WHERE user_id = ? AND ((flag = ? AND flag_ref_id = ?) OR (flag = ? AND flag_ref_id = ?)) ORDER BY flag LIMIT 1",
+        )
+        .bind(user_id)
+        .bind(SubscriptionFlag::TargetPost)
+        .bind(post_id)
+        .bind(SubscriptionFlag::TargetCategory)
+        .bind(category_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        Ok(level == Some(SubscriptionLevel::Muted))
+    }
+
+    /// Notify subscribers of the post and its category about new comment, users in `exclude` are skipped.
+    pub async fn notify_new_comment(
+        &self,
+        created_by_id: usizedb,
+        comment_id: usizedb,
+        post_id: usizedb,
+        category_id: usizedb,
+        top_level: bool,
+        exclude: &[usizedb],
+    ) -> Result<()> {
+        let mut tx = self.core.begin_unwrap(false).await;
+        let user_ids: Vec<usizedb> = sqlx::query_scalar(
+            "SELECT user_id FROM subscriptions WHERE flag = ? AND flag_ref_id = ? AND (level = ? OR (level = ? AND ?))
+            UNION
+            SELECT s.user_id FROM subscriptions s WHERE s.flag = ? AND s.flag_ref_id = ? AND s.level = ?
+            AND NOT EXISTS (SELECT 1 FROM subscriptions p WHERE p.user_id = s.user_id AND p.flag = ? AND p.flag_ref_id = ?)",
+        )
+        .bind(SubscriptionFlag::TargetPost)
+        .bind(post_id)
+        .bind(SubscriptionLevel::Watching)
+        .bind(SubscriptionLevel::Tracking)
+        .bind(top_level)
+        .bind(SubscriptionFlag::TargetCategory)
+        .bind(category_id)
+        .bind(SubscriptionLevel::Watching)
+        .bind(SubscriptionFlag::TargetPost)
+        .bind(post_id)
+        .fetch_all(tx.as_mut())
+        .await?;
+        tx.commit().await?;
+        self.fan_out(
+            created_by_id,
+            comment_id,
+            UserNotificationType::NewComment,
+            user_ids,
+            exclude,
+        )
+        .await
+    }
+
+    /// Notify subscribers of the category about new post.
+    pub async fn notify_new_post(
+        &self,
+        created_by_id: usizedb,
+        post_id: usizedb,
+        category_id: usizedb,
+    ) -> Result<()> {
+        let mut tx = self.core.begin_unwrap(false).await;
+        let user_ids: Vec<usizedb> = sqlx::query_scalar(
+            "SELECT user_id FROM subscriptions WHERE flag = ? AND flag_ref_id = ? AND level != ?",
+        )
+        .bind(SubscriptionFlag::TargetCategory)
+        .bind(category_id)
+        .bind(SubscriptionLevel::Muted)
+        .fetch_all(tx.as_mut())
+        .await?;
+        tx.commit().await?;
+        self.fan_out(
+            created_by_id,
+            post_id,
+            UserNotificationType::NewPost,
+            user_ids,
+            &[],
+        )
+        .await
+    }
+
+    async fn fan_out(
+        &self,
+        created_by_id: usizedb,
+        ref_id: usizedb,
+        n_type: UserNotificationType,
+        user_ids: Vec<usizedb>,
+        exclude: &[usizedb],
+    ) -> Result<()> {
+        // sent together, they are inserted in one batch by the task.
+        let sends = user_ids
+            .into_iter()
+            .filter(|id| !exclude.contains(id))
+            .map(|target_user_id| {
+                self.create_user_notification(
+                    created_by_id,
+                    UserNotificationArguments {
+                        ref_id,
+                        target_user_id,
+                        n_type,
+                    },
+                )
+            });
+        future::try_join_all(sends).await?;
+        Ok(())
+    }
+
+    /// Record mentioned users of post or comment, return the users newly recorded. Users mentioned
+    /// before are skipped, and at most `limit` users are recorded for a post or comment.
+    pub async fn create_mentions(
+        &self,
+        tx: &mut SqliteConnection,
+        created_by_id: usizedb,
+        flag: MentionFlag,
+        flag_ref_id: usizedb,
+        user_ids: Vec<usizedb>,
+        limit: usize,
+    ) -> Result<Vec<usizedb>> {
+        let mentioned: Vec<usizedb> =
+            sqlx::query_scalar("SELECT user_id FROM mentions WHERE flag = ? AND flag_ref_id = ?")
+                .bind(flag)
+                .bind(flag_ref_id)
+                .fetch_all(&mut *tx)
+                .await?;
+        let user_ids: Vec<usizedb> = user_ids
+            .into_iter()
+            .filter(|id| *id != created_by_id && !mentioned.contains(id))
+            .take(limit.saturating_sub(mentioned.len()))
+            .collect();
+        if user_ids.is_empty() {
+            return Ok(user_ids);
+        }
+
+        let now = Utc::now().timestamp() as usizedb;
+        QueryBuilder::new("INSERT INTO mentions (flag, flag_ref_id, user_id, created_at) ")
+            .push_values(&user_ids, |mut b, user_id| {
+                b.push_bind(flag)
+                    .push_bind(flag_ref_id)
+                    .push_bind(user_id)
+                    .push_bind(now);
+            })
+            .build()
+            .execute(&mut *tx)
+            .await?;
+        Ok(user_ids)
+    }
+
+    /// Notify users recorded by `create_mentions`, it must be called after the transaction committed.
+    pub async fn notify_mentioned(
+        &self,
+        created_by_id: usizedb,
+        flag: MentionFlag,
+        flag_ref_id: usizedb,
+        user_ids: Vec<usizedb>,
+    ) -> Result<()> {
+        let n_type = match flag {
+            MentionFlag::TargetPost => UserNotificationType::MentionPost,
+            MentionFlag::TargetComment => UserNotificationType::Mention,
+        };
+        self.fan_out(created_by_id, flag_ref_id, n_type, user_ids, &[])
+            .await
+    }
 }
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
+use sqlx::FromRow;
 
 use fofo_utils::usizedb;
 
@@ -30,4 +31,52 @@ pub enum UserNotificationType {
     DislikePost,
     LikeComment,
     DislikeComment,
+    /// New post in subscribed category, ref id is post id.
+    NewPost,
+    /// New comment in subscribed post or category, ref id is comment id.
+    NewComment,
+    /// Mentioned in comment, ref id is comment id.
+    Mention,
+    /// Mentioned in post, ref id is post id.
+    MentionPost,
+    /// Post or comment quoted by comment, ref id is id of the quoting comment.
+    Quote,
+    /// Comment accepted as the answer of post, ref id is comment id.
+    AcceptedAnswer,
+}
+
+#[derive(Debug, Deserialize_repr, Serialize_repr, PartialEq, Clone, Copy, sqlx::Type)]
+#[repr(u8)]
+pub enum MentionFlag {
+    TargetPost,
+    TargetComment,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, FromRow)]
+pub struct Subscription {
+    pub id: usizedb,
+    pub flag: SubscriptionFlag,
+    pub flag_ref_id: usizedb,
+    pub user_id: usizedb,
+    pub level: SubscriptionLevel,
+    pub created_at: usizedb,
+}
+
+#[derive(Debug, Deserialize_repr, Serialize_repr, PartialEq, Clone, Copy, sqlx::Type)]
+#[repr(u8)]
+pub enum SubscriptionFlag {
+    TargetPost,
+    TargetCategory,
+}
+
+/// Subscription of post overrides the one of its category.
+#[derive(Debug, Deserialize_repr, Serialize_repr, PartialEq, Clone, Copy, sqlx::Type)]
+#[repr(u8)]
+pub enum SubscriptionLevel {
+    /// Notified of all new comments, and new posts for category.
+    Watching,
+    /// Notified of new top level comments for post, new posts for category.
+    Tracking,
+    /// Not notified, even comments and replies to user.
+    Muted,
 }
@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use sqlx::{QueryBuilder, Row, SqliteConnection};
+
+use channel_cache::ChannelCacheTask;
+use fofo_utils::usizedb;
+use shared_core::SharedCore;
+
+use model::{PollInfo, PollOption, PollResultVisibility, PollToCreate};
+
+pub mod model;
+
+#[derive(Debug, Clone)]
+pub struct PollSystem {
+    core: SharedCore,
+    vote_task: Option<ChannelCacheTask<PollVoteBundle, ()>>,
+}
+
+#[derive(Debug, Clone)]
+struct PollVoteBundle {
+    poll_id: usizedb,
+    user_id: usizedb,
+    /// Empty means withdraw the vote.
+    choices: Vec<usizedb>,
+}
+
+impl PollSystem {
+    pub async fn new(core: SharedCore) -> Self {
+        let mut tx = core.begin_unwrap(true).await;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS polls(
+                id INTEGER PRIMARY KEY,
+                post_id INT NOT NULL UNIQUE,
+                multiple BOOLEAN NOT NULL,
+                close_at INT NOT NULL,
+                result_visibility INT NOT NULL,
+                total_voters INT NOT NULL,
+                created_at INT NOT NULL,
+
+                FOREIGN KEY(post_id) REFERENCES posts(id)
+            );
+            CREATE TABLE IF NOT EXISTS poll_options(
+                id INTEGER PRIMARY KEY,
+                poll_id INT NOT NULL,
+                option_index INT NOT NULL,
+                title TEXT NOT NULL,
+                votes INT NOT NULL,
+
+                FOREIGN KEY(poll_id) REFERENCES polls(id)
+            );
+            CREATE TABLE IF NOT EXISTS poll_votes(
+                id INTEGER PRIMARY KEY,
+                poll_id INT NOT NULL,
+                option_index INT NOT NULL,
+                created_at INT NOT NULL,
+                created_by_id INT NOT NULL,
+
+                FOREIGN KEY(poll_id) REFERENCES polls(id),
+                FOREIGN KEY(created_by_id) REFERENCES users(id)
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS poll_options_poll_id_index
+            on poll_options (poll_id, option_index);
+            CREATE INDEX IF NOT EXISTS poll_votes_m1_index
+            on poll_votes (poll_id, created_by_id);",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap(); // create indexes.
+
+        tx.commit().await.unwrap();
+        let config = core.get_config();
+        let mut this = PollSystem {
+            core,
+            vote_task: None,
+        };
+
+        let t = this.clone();
+        this.vote_task = Some(ChannelCacheTask::new(
+            "set_poll_votes".into(),
+            config.buffer_size,
+            config.task_trigger_ms,
+            move |arr| {
+                let t = t.clone();
+                async move {
+                    let mut tx = t.core.begin_unwrap(true).await;
+                    let r = t.vote_all(tx.as_mut(), arr).await.unwrap();
+                    tx.commit_unwrap().await;
+                    r
+                }
+            },
+        ));
+
+        this
+    }
+
+    pub async fn create_poll(
+        &self,
+        tx: &mut SqliteConnection,
+        post_id: usizedb,
+        poll: PollToCreate,
+    ) -> Result<PollInfo> {
+        let now = Utc::now().timestamp() as usizedb;
+        let r = sqlx::query("INSERT INTO polls (post_id, multiple, close_at, result_visibility, total_voters, created_at) VALUES (?, ?, ?, ?, 0, ?)")
+            .bind(post_id)
+            .bind(poll.multiple)
+            .bind(poll.close_at)
+            .bind(poll.result_visibility)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        let id = r.last_insert_rowid() as usizedb;
+        QueryBuilder::new("INSERT INTO poll_options (poll_id, option_index, title, votes) ")
+            .push_values(poll.options.iter().enumerate(), |mut b, (i, title)| {
+                b.push_bind(id)
+                    .push_bind(i as usizedb)
+                    .push_bind(title)
+                    .push_bind(0);
+            })
+            .build()
+            .execute(&mut *tx)
+            .await?;
+
+        Ok(PollInfo {
+            id,
+            post_id,
+            multiple: poll.multiple,
+            close_at: poll.close_at,
+            result_visibility: poll.result_visibility,
+            total_voters: Some(0),
+            created_at: now,
+            options: poll
+                .options
+                .into_iter()
+                .enumerate()
+                .map(|(i, title)| PollOption {
+                    option_index: i as usizedb,
+                    title,
+                    votes: Some(0),
+                })
+                .collect(),
+            voted: vec![],
+        })
+    }
+
+    /// Get poll of post, results are hidden if they are invisible to user.
+    pub async fn get_poll(
+        &self,
+        tx: &mut SqliteConnection,
+        post_id: usizedb,
+        user_id: Option<usizedb>,
+    ) -> Result<Option<PollInfo>> {
+        Ok(self
+            .get_polls(tx, &[post_id], user_id)
+            .await?
+            .remove(&post_id))
+    }
+
+    /// Get polls of posts, key is the post id. Results are hidden if they are invisible to user.
+    pub async fn get_polls(
+        &self,
+        tx: &mut SqliteConnection,
+        post_ids: &[usizedb],
+        user_id: Option<usizedb>,
+    ) -> Result<HashMap<usizedb, PollInfo>> {
+        let mut map = HashMap::new();
+        if post_ids.is_empty() {
+            return Ok(map);
+        }
+        let ids = post_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+        let rows = sqlx::query(&format!("SELECT * FROM polls WHERE post_id IN ({ids})"))
+            .fetch_all(&mut *tx)
+            .await?;
+        if rows.is_empty() {
+            return Ok(map);
+        }
+        let mut polls: HashMap<usizedb, PollInfo> = rows
+            .into_iter()
+            .map(|row| {
+                let id: usizedb = row.get("id");
+                let poll = PollInfo {
+                    id,
+                    post_id: row.get("post_id"),
+                    multiple: row.get("multiple"),
+                    close_at: row.get("close_at"),
+                    result_visibility: row.get("result_visibility"),
+                    total_voters: row.get("total_voters"),
+                    created_at: row.get("created_at"),
+                    options: vec![],
+                    voted: vec![],
+                };
+                (id, poll)
+            })
+            .collect();
+        let poll_ids = polls
+            .keys()
+            .map(|id| id.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+        let options = sqlx::query(&format!(
+            "SELECT poll_id, option_index, title, votes FROM poll_options WHERE poll_id IN ({poll_ids}) ORDER BY option_index"
+        ))
+        .fetch_all(&mut *tx)
+        .await?;
+        for row in options {
+            if let Some(poll) = polls.get_mut(&row.get::<usizedb, _>("poll_id")) {
+                poll.options.push(PollOption {
+                    option_index: row.get("option_index"),
+                    title: row.get("title"),
+                    votes: row.get("votes"),
+                });
+            }
+        }
+        if let Some(user_id) = user_id {
+            let voted = sqlx::query(&format!(
+                "SELECT poll_id, option_index FROM poll_votes WHERE poll_id IN ({poll_ids}) AND created_by_id = ? ORDER BY option_index"
+            ))
+            .bind(user_id)
+            .fetch_all(&mut *tx)
+            .await?;
+            for row in voted {
+                if let Some(poll) = polls.get_mut(&row.get::<usizedb, _>("poll_id")) {
+                    poll.voted.push(row.get("option_index"));
+                }
+            }
+        }
+
+        let now = Utc::now().timestamp() as usizedb;
+        for (_id, mut poll) in polls {
+            if poll.result_visibility == PollResultVisibility::AfterVote
+                && poll.voted.is_empty()
+                && !poll.is_closed(now)
+            {
+                poll.total_voters = None;
+                for option in &mut poll.options {
+                    option.votes = None;
+                }
+            }
+            map.insert(poll.post_id, poll);
+        }
+        Ok(map)
+    }
+
+    /// Vote poll, the previous vote of user is replaced.
+    pub async fn vote(
+        &self,
+        poll_id: usizedb,
+        user_id: usizedb,
+        choices: Vec<usizedb>,
+    ) -> Result<()> {
+        match self.vote_task.as_ref() {
+            Some(task) => {
+                task.send(PollVoteBundle {
+                    poll_id,
+                    user_id,
+                    choices,
+                })
+                .await?;
+                Ok(())
+            }
+            None => bail!("Don't have the task."),
+        }
+    }
+
+    async fn vote_all(
+        &self,
+        tx: &mut SqliteConnection,
+        arr: Vec<PollVoteBundle>,
+    ) -> Result<Vec<()>> {
+        let mut list = Vec::with_capacity(arr.len());
+        list.resize(arr.len(), ());
+
+        let bundles_by_poll = {
+            // Only the latest vote of user is kept.
+            let mut map = HashMap::with_capacity(arr.len());
+            for bundle in arr {
+                map.insert((bundle.poll_id, bundle.user_id), bundle);
+            }
+            let mut by_poll: HashMap<usizedb, Vec<PollVoteBundle>> = HashMap::new();
+            for (_key, bundle) in map {
+                by_poll.entry(bundle.poll_id).or_default().push(bundle);
+            }
+            by_poll
+        };
+
+        let now = Utc::now().timestamp() as usizedb;
+        let mut votes = Vec::new();
+        for (poll_id, bundles) in bundles_by_poll {
+            let ids = bundles
+                .iter()
+                .map(|b| b.user_id.to_string())
+                .collect::<Vec<String>>()
+                .join(",");
+            let mut delta: HashMap<usizedb, i64> = HashMap::new(); // key is option index, value is votes changed.
+            let old = sqlx::query(&format!("SELECT option_index, COUNT(*) AS total FROM poll_votes WHERE poll_id=? AND created_by_id IN ({ids}) GROUP BY option_index")).bind(poll_id).fetch_all(&mut *tx).await?;
+            for row in old {
+                *delta.entry(row.get("option_index")).or_insert(0) -= row.get::<i64, _>("total");
+            }
+            let old_voters: i64 = sqlx::query(&format!("SELECT COUNT(DISTINCT created_by_id) AS total FROM poll_votes WHERE poll_id=? AND created_by_id IN ({ids})")).bind(poll_id).fetch_one(&mut *tx).await?.get("total");
+            sqlx::query(&format!(
+                "DELETE FROM poll_votes WHERE poll_id=? AND created_by_id IN ({ids})"
+            ))
+            .bind(poll_id)
+            .execute(&mut *tx)
+            .await?;
+
+            let mut new_voters = 0;
+            for bundle in bundles {
+                if !bundle.choices.is_empty() {
+                    new_voters += 1;
+                }
+                for option_index in bundle.choices {
+                    *delta.entry(option_index).or_insert(0) += 1;
+                    votes.push((poll_id, option_index, bundle.user_id));
+                }
+            }
+            for (option_index, count) in delta {
+                if count != 0 {
+                    sqlx::query("UPDATE poll_options SET votes = MAX(votes + ?, 0) WHERE poll_id = ? AND option_index = ?")
+                        .bind(count)
+                        .bind(poll_id)
+                        .bind(option_index)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+            }
+            sqlx::query("UPDATE polls SET total_voters = MAX(total_voters + ?, 0) WHERE id = ?")
+                .bind(new_voters - old_voters)
+                .bind(poll_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        if !votes.is_empty() {
+            QueryBuilder::new(
+                "INSERT INTO poll_votes (poll_id, option_index, created_at, created_by_id) ",
+            )
+            .push_values(votes, |mut b, (poll_id, option_index, user_id)| {
+                b.push_bind(poll_id)
+                    .push_bind(option_index)
+                    .push_bind(now)
+                    .push_bind(user_id);
+            })
+            .build()
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        Ok(list)
+    }
+}
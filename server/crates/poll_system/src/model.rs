@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+use sqlx::FromRow;
+
+use fofo_utils::usizedb;
+
+#[derive(Debug, Deserialize_repr, Serialize_repr, PartialEq, Clone, Copy, sqlx::Type, Default)]
+#[repr(u8)]
+pub enum PollResultVisibility {
+    /// Results can be seen before voting.
+    #[default]
+    Always,
+    /// Results can be seen after voting or poll closed.
+    AfterVote,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PollToCreate {
+    pub options: Vec<String>,
+    #[serde(default)]
+    pub multiple: bool,
+    /// 0 means never close.
+    #[serde(default)]
+    pub close_at: usizedb,
+    #[serde(default)]
+    pub result_visibility: PollResultVisibility,
+}
+
+#[derive(Debug, Deserialize, Serialize, FromRow, Clone)]
+pub struct PollOption {
+    pub option_index: usizedb,
+    pub title: String,
+    /// None if results are invisible to user.
+    pub votes: Option<usizedb>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PollInfo {
+    pub id: usizedb,
+    pub post_id: usizedb,
+    pub multiple: bool,
+    pub close_at: usizedb,
+    pub result_visibility: PollResultVisibility,
+    /// None if results are invisible to user.
+    pub total_voters: Option<usizedb>,
+    pub created_at: usizedb,
+    pub options: Vec<PollOption>,
+    /// Option indexes voted by current user.
+    pub voted: Vec<usizedb>,
+}
+
+impl PollInfo {
+    pub fn is_closed(&self, now: usizedb) -> bool {
+        self.close_at > 0 && self.close_at <= now
+    }
+}
@@ -1,20 +1,28 @@
 pub mod model;
 
-use std::{borrow::Cow, collections::HashMap};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use anyhow::{bail, Result};
-use chrono::Utc;
+use chrono::{Duration, Utc};
 use futures::TryStreamExt;
 use moka::future::Cache;
 use sqlx::{sqlite::SqliteRow, QueryBuilder, Row, SqliteConnection};
+use tokio::task::JoinHandle;
 use tracing::error;
 
 use self::model::{
-    PostAlgorithmOrder, PostBaseInfo, PostFilterTime, PostInfo, PostStatus, PostToCreate,
-    PostToUpdate,
+    PostAlgorithmOrder, PostBaseInfo, PostDailyViews, PostFilterTime, PostInfo, PostReadFilter,
+    PostStatus, PostToCreate, PostToSplit, PostToUpdate,
 };
+use bookmark_system::model::BookmarkFlag;
 use channel_cache::ChannelCacheTask;
-use fofo_utils::usizedb;
+use comment_system::model::CommentStatus;
+use fofo_utils::{config::SafeConfig, cursor::Cursor, usizedb, ContentMeta, ContentType};
+use like_system::model::LikeStatusFlag;
+use notification_system::model::{MentionFlag, SubscriptionFlag};
 use shared_core::SharedCore;
 use storage::S3Ref;
 
@@ -25,8 +33,12 @@ pub struct PostSystem {
     cached_posts: Cache<usizedb, PostInfo>,
     cached_posts_array: Cache<PostArrayKey, Vec<PostInfo>>,
     cached_posts_count: Cache<PostArrayKey, usizedb>,
+    /// key is (post id, viewer hash).
+    viewed_posts: Cache<(usizedb, u64), ()>,
     create_task: Option<ChannelCacheTask<(usizedb, PostToCreate), PostInfo>>,
     views_task: Option<ChannelCacheTask<usizedb, ()>>,
+    maintain_task: Option<Arc<JoinHandle<()>>>,
+    hot_task: Option<Arc<JoinHandle<()>>>,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -60,6 +72,16 @@ impl PostSystem {
                 last_comment_by_id INT NOT NULL,
                 cover_url TEXT NULL,
                 top_index INT NOT NULL DEFAULT 0,
+                deleted_at INT NOT NULL DEFAULT 0,
+                deleted_by_id INT NOT NULL DEFAULT 0,
+                locked INT NOT NULL DEFAULT 0,
+                lock_reason TEXT NOT NULL DEFAULT '',
+                locked_at INT NOT NULL DEFAULT 0,
+                hot_score REAL NOT NULL DEFAULT 0,
+                excerpt TEXT NULL,
+                word_count INT NOT NULL DEFAULT 0,
+                reading_time INT NOT NULL DEFAULT 0,
+                accepted_comment_id INT NOT NULL DEFAULT 0,
 
                 FOREIGN KEY(created_by_id) REFERENCES users(id),
                 FOREIGN KEY(category_id) REFERENCES categories(id),
@@ -70,28 +92,77 @@ impl PostSystem {
         .await
         .unwrap();
 
+        // columns added after the table is created.
+        for (column, definition) in [
+            ("deleted_at", "INT NOT NULL DEFAULT 0"),
+            ("deleted_by_id", "INT NOT NULL DEFAULT 0"),
+            ("locked", "INT NOT NULL DEFAULT 0"),
+            ("lock_reason", "TEXT NOT NULL DEFAULT ''"),
+            ("locked_at", "INT NOT NULL DEFAULT 0"),
+            ("hot_score", "REAL NOT NULL DEFAULT 0"),
+            ("excerpt", "TEXT NULL"),
+            ("word_count", "INT NOT NULL DEFAULT 0"),
+            ("reading_time", "INT NOT NULL DEFAULT 0"),
+            ("accepted_comment_id", "INT NOT NULL DEFAULT 0"),
+        ] {
+            fofo_utils::add_column_if_not_exists(tx.as_mut(), "posts", column, definition)
+                .await
+                .unwrap();
+        }
+
         sqlx::query(
             "CREATE INDEX IF NOT EXISTS posts_created_at_index
-            on posts (created_at);",
+            on posts (created_at);
+            CREATE INDEX IF NOT EXISTS posts_hot_score_index
+            on posts (hot_score);",
         )
         .execute(tx.as_mut())
         .await
         .unwrap(); // create indexes.
 
-        tx.commit().await.unwrap();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS post_daily_views(
+                post_id INT NOT NULL,
+                day INT NOT NULL,
+                views INT NOT NULL,
+
+                PRIMARY KEY(post_id, day),
+                FOREIGN KEY(post_id) REFERENCES posts(id)
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
         let config = core.get_config();
+        PostSystem::fill_content_meta(tx.as_mut(), &config)
+            .await
+            .unwrap();
+
+        tx.commit().await.unwrap();
         let mut this = PostSystem {
             core,
             s3,
             cached_posts: fofo_utils::get_cache_instance(config.clone()).await,
             cached_posts_array: fofo_utils::get_cache_instance(config.clone()).await,
             cached_posts_count: fofo_utils::get_cache_instance(config.clone()).await,
+            viewed_posts: {
+                let mut builder = Cache::builder().max_capacity(config.view_dedup_capacity);
+                if config.view_dedup_seconds > 0 {
+                    builder = builder
+                        .time_to_live(std::time::Duration::from_secs(config.view_dedup_seconds));
+                }
+                builder.build()
+            },
             create_task: None,
             views_task: None,
+            maintain_task: None,
+            hot_task: None,
         };
         let ps = this.clone();
-        let _ps2 = this.clone();
+        let ps2 = this.clone();
         let ps3 = this.clone();
+        let ps4 = this.clone();
         let create_task = ChannelCacheTask::new(
             "create_posts".into(),
             config.buffer_size,
@@ -139,8 +210,47 @@ impl PostSystem {
                 }
             },
         );
+        let check_task_interval_seconds = config.check_task_interval_seconds as u64;
+        let maintain_task = tokio::spawn(async move {
+            loop {
+                let mut tx = ps2.core.begin_unwrap(true).await;
+                match ps2.purge_deleted_posts(tx.as_mut(), 1000).await {
+                    Ok(cover_keys) => {
+                        tx.commit_unwrap().await;
+                        // objects are deleted after the commit, so they are kept if it fails.
+                        for key in cover_keys {
+                            if let Err(e) = ps2.s3.delete_object(&key).await {
+                                error!("Can't delete cover `{key}`: {e}");
+                            }
+                        }
+                    }
+                    Err(e) => error!("Can't purge deleted posts: {e}"),
+                }
+                let mut tx = ps2.core.begin_unwrap(true).await;
+                match ps2.lock_inactive_posts(tx.as_mut()).await {
+                    Ok(_) => tx.commit_unwrap().await,
+                    Err(e) => error!("Can't lock inactive posts: {e}"),
+                }
+                tokio::time::sleep(std::time::Duration::from_secs(check_task_interval_seconds))
+                    .await;
+            }
+        });
         this.create_task = Some(create_task);
         this.views_task = Some(views_task);
+        // 0 would recompute without rest while holding the write lock.
+        let hot_recompute_seconds = config.hot_recompute_seconds.max(1);
+        let hot_task = tokio::spawn(async move {
+            loop {
+                let mut tx = ps4.core.begin_unwrap(true).await;
+                match ps4.recompute_hot_scores(tx.as_mut()).await {
+                    Ok(_) => tx.commit_unwrap().await,
+                    Err(e) => error!("Can't recompute hot scores: {e}"),
+                }
+                tokio::time::sleep(std::time::Duration::from_secs(hot_recompute_seconds)).await;
+            }
+        });
+        this.maintain_task = Some(Arc::new(maintain_task));
+        this.hot_task = Some(Arc::new(hot_task));
         this
     }
 
@@ -152,11 +262,20 @@ impl PostSystem {
             .is_some())
     }
 
-    pub async fn increment_views(&self, post_id: usizedb) -> Result<()> {
+    /// Increment views of post once in `view_dedup_seconds` for same viewer.
+    /// Viewer is the hash of user id or ip, None is always counted. Return false if the view is not counted.
+    pub async fn increment_views(&self, post_id: usizedb, viewer: Option<u64>) -> Result<bool> {
+        if let (Some(viewer), true) = (viewer, self.core.get_config().view_dedup_seconds > 0) {
+            let key = (post_id, viewer);
+            if self.viewed_posts.contains_key(&key) {
+                return Ok(false);
+            }
+            self.viewed_posts.insert(key, ()).await;
+        }
         match self.views_task.as_ref() {
             Some(task) => {
-                let r = task.send(post_id).await?;
-                Ok(r)
+                task.send(post_id).await?;
+                Ok(true)
             }
             None => bail!("Don't have the task."),
         }
@@ -179,18 +298,51 @@ impl PostSystem {
         if r.rows_affected() == 0 {
             error!("Can't update views in table posts");
         }
+        let category_id: Option<usizedb> =
+            sqlx::query_scalar("SELECT category_id FROM posts WHERE id = ?")
+                .bind(post_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        if let Some(category_id) = category_id {
+            fofo_utils::increment_category_daily_stats(&mut *tx, category_id, 0, 0, count, 0)
+                .await?;
+        }
+        let now = Utc::now().timestamp();
+        sqlx::query(
+            "INSERT INTO post_daily_views (post_id, day, views) VALUES (?, ?, ?) 
+            ON CONFLICT(post_id, day) DO UPDATE SET views = views + excluded.views",
+        )
+        .bind(post_id)
+        .bind(now - now % 86400)
+        .bind(count)
+        .execute(&mut *tx)
+        .await?;
 
         Ok(())
     }
 
+    /// Get daily views of post between `start` and `end` timestamp.
+    pub async fn get_daily_views(
+        &self,
+        tx: &mut SqliteConnection,
+        post_id: usizedb,
+        start: usizedb,
+        end: usizedb,
+        limit: usizedb,
+    ) -> Result<Vec<PostDailyViews>> {
+        Ok(sqlx::query_as(
+            "SELECT day, views FROM post_daily_views WHERE post_id = ? AND day >= ? AND day <= ? ORDER BY day LIMIT ?",
+        )
+        .bind(post_id)
+        .bind(start - start % 86400)
+        .bind(end)
+        .bind(limit)
+        .fetch_all(&mut *tx)
+        .await?)
+    }
+
     pub async fn create_post(&self, user_id: usizedb, mut post: PostToCreate) -> Result<PostInfo> {
-        if let Some(url) = &post.cover_url {
-            if let Some(key) = self.s3.try_parse_url_to_key(url) {
-                if url != key {
-                    post.cover_url = Some(key.to_owned())
-                }
-            }
-        }
+        self.normalize_cover_url(&mut post);
         match self.create_task.as_ref() {
             Some(task) => {
                 let post = task.send((user_id, post)).await?;
@@ -200,6 +352,31 @@ impl PostSystem {
         }
     }
 
+    /// Create post in the transaction of caller instead of the batch task, for the checks which
+    /// must be in the same transaction as the insert.
+    pub async fn insert_post(
+        &self,
+        tx: &mut SqliteConnection,
+        user_id: usizedb,
+        mut post: PostToCreate,
+    ) -> Result<PostInfo> {
+        self.normalize_cover_url(&mut post);
+        match self.create_posts(tx, vec![(user_id, post)]).await?.pop() {
+            Some(post) => Ok(post),
+            None => bail!("Insert failed."),
+        }
+    }
+
+    fn normalize_cover_url(&self, post: &mut PostToCreate) {
+        if let Some(url) = &post.cover_url {
+            if let Some(key) = self.s3.try_parse_url_to_key(url) {
+                if url != key {
+                    post.cover_url = Some(key.to_owned())
+                }
+            }
+        }
+    }
+
     pub async fn create_posts(
         &self,
         tx: &mut SqliteConnection,
@@ -207,41 +384,67 @@ impl PostSystem {
     ) -> Result<Vec<PostInfo>> {
         let len = posts.len() as usizedb;
         let now = Utc::now().timestamp() as usizedb;
-        let mut query_builder = QueryBuilder::new("INSERT INTO posts (created_by_id, title, content, content_type, likes, dislikes, views, category_id, tags, created_at, last_edit_at, last_edit_by_id, status, last_comment_at, last_comment_by_id, total_comment, total_comment_post, cover_url, top_index) ");
-        query_builder.push_values(&posts, |mut b, (user_id, post)| {
-            b.push_bind(user_id)
-                .push_bind(&post.title)
-                .push_bind(&post.content)
-                .push_bind(&post.content_type)
-                .push_bind(0)
-                .push_bind(0)
-                .push_bind(0)
-                .push_bind(post.category_id)
-                .push_bind(fofo_utils::array_to_string(&post.tags))
-                .push_bind(now)
-                .push_bind(now)
-                .push_bind(user_id)
-                .push_bind(PostStatus::Active)
-                .push_bind(0)
-                .push_bind(0)
-                .push_bind(0)
-                .push_bind(0)
-                .push_bind(&post.cover_url)
-                .push_bind(post.top_index);
-        });
+        let mut query_builder = QueryBuilder::new("INSERT INTO posts (created_by_id, title, content, content_type, likes, dislikes, views, category_id, tags, created_at, last_edit_at, last_edit_by_id, status, last_comment_at, last_comment_by_id, total_comment, total_comment_post, cover_url, top_index, hot_score, excerpt, word_count, reading_time) ");
+        let hot_score = self.get_hot_score(0, 0, 0, now, 0, now);
+        let config = self.core.get_config();
+        let metas: Vec<_> = posts
+            .iter()
+            .map(|(_, post)| {
+                PostSystem::get_content_meta(&config, post.content_type, &post.content)
+            })
+            .collect();
+        query_builder.push_values(
+            posts.iter().zip(&metas),
+            |mut b, ((user_id, post), meta)| {
+                b.push_bind(user_id)
+                    .push_bind(&post.title)
+                    .push_bind(&post.content)
+                    .push_bind(&post.content_type)
+                    .push_bind(0)
+                    .push_bind(0)
+                    .push_bind(0)
+                    .push_bind(post.category_id)
+                    .push_bind(fofo_utils::array_to_string(&post.tags))
+                    .push_bind(now)
+                    .push_bind(now)
+                    .push_bind(user_id)
+                    .push_bind(if post.pending {
+                        PostStatus::Pending
+                    } else {
+                        PostStatus::Active
+                    })
+                    .push_bind(0)
+                    .push_bind(0)
+                    .push_bind(0)
+                    .push_bind(0)
+                    .push_bind(&post.cover_url)
+                    .push_bind(post.top_index)
+                    .push_bind(hot_score)
+                    .push_bind(&meta.excerpt)
+                    .push_bind(meta.word_count)
+                    .push_bind(meta.reading_time);
+            },
+        );
         let query = query_builder.build();
         let r = query.execute(&mut *tx).await?;
 
         if r.rows_affected() == (len as u64) {
             let mut category_id_map = HashMap::with_capacity(posts.len()); // key is category id, value is total post for this create.
             let mut user_id_map = HashMap::with_capacity(posts.len()); // key is user id, value is total post for this create.
+            let mut posters = HashSet::with_capacity(posts.len()); // category id and user id of the authors.
             let base_id = r.last_insert_rowid() as usizedb - len + 1;
             let p: Vec<_> = posts
                 .into_iter()
+                .zip(metas)
                 .enumerate()
-                .map(|(i, (user_id, ptc))| {
-                    *category_id_map.entry(ptc.category_id).or_insert(0) += 1;
-                    *user_id_map.entry(user_id).or_insert(0) += 1;
+                .map(|(i, ((user_id, ptc), meta))| {
+                    // pending posts are counted after approved.
+                    if !ptc.pending {
+                        *category_id_map.entry(ptc.category_id).or_insert(0) += 1;
+                        *user_id_map.entry(user_id).or_insert(0) += 1;
+                        posters.insert((ptc.category_id, user_id));
+                    }
+                    let rendered_html = self.render_html(ptc.content_type, &ptc.content);
                     PostInfo {
                         id: base_id + i as usizedb,
                         created_by_id: user_id,
@@ -260,19 +463,47 @@ impl PostSystem {
                         total_comment: 0,
                         last_edit_by_id: user_id,
                         last_comment_by_id: user_id,
-                        status: PostStatus::Active,
+                        status: if ptc.pending {
+                            PostStatus::Pending
+                        } else {
+                            PostStatus::Active
+                        },
                         cover_url: ptc.cover_url,
                         top_index: ptc.top_index,
+                        deleted_at: 0,
+                        deleted_by_id: 0,
+                        locked: false,
+                        lock_reason: String::new(),
+                        locked_at: 0,
+                        hot_score,
+                        excerpt: meta.excerpt,
+                        word_count: meta.word_count,
+                        reading_time: meta.reading_time,
+                        accepted_comment_id: 0,
+                        solved: false,
+                        rendered_html,
                     }
                 })
                 .collect();
             for (category_id, total_post) in category_id_map {
                 fofo_utils::increment_category_total_post(&mut *tx, category_id, total_post)
                     .await?;
+                fofo_utils::increment_category_daily_stats(
+                    &mut *tx,
+                    category_id,
+                    total_post,
+                    0,
+                    0,
+                    0,
+                )
+                .await?;
             }
             for (user_id, total_post) in user_id_map {
                 fofo_utils::increment_user_total_post(&mut *tx, user_id, total_post).await?;
             }
+            for (category_id, user_id) in posters {
+                fofo_utils::add_category_daily_poster(&mut *tx, category_id, user_id).await?;
+            }
             Ok(p)
         } else {
             bail!("Insert failed.")
@@ -294,6 +525,8 @@ impl PostSystem {
                 }
             }
         }
+        let meta =
+            PostSystem::get_content_meta(&self.core.get_config(), post.content_type, &post.content);
         let r = sqlx::query(
             "UPDATE posts SET 
             title = ?,
@@ -303,7 +536,10 @@ impl PostSystem {
             last_edit_at = ?,
             last_edit_by_id = ?,
             cover_url = ?,
-            top_index=? 
+            top_index=?,
+            excerpt = ?,
+            word_count = ?,
+            reading_time = ?
             WHERE id = ?",
         )
         .bind(&post.title)
@@ -314,6 +550,9 @@ impl PostSystem {
         .bind(user_id)
         .bind(&post.cover_url)
         .bind(post.top_index)
+        .bind(&meta.excerpt)
+        .bind(meta.word_count)
+        .bind(meta.reading_time)
         .bind(post_id)
         .execute(&mut *tx)
         .await?;
@@ -330,7 +569,23 @@ impl PostSystem {
         })
     }
 
-    async fn invalidate_cache(&self, id: usizedb) {
+    /// Set the accepted answer of post, `comment_id` 0 means no answer.
+    pub async fn set_accepted_comment(
+        &self,
+        tx: &mut SqliteConnection,
+        id: usizedb,
+        comment_id: usizedb,
+    ) -> Result<bool> {
+        let r = sqlx::query("UPDATE posts SET accepted_comment_id = ? WHERE id = ?")
+            .bind(comment_id)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        self.invalidate_cache(id).await;
+        Ok(r.rows_affected() == 1)
+    }
+
+    pub async fn invalidate_cache(&self, id: usizedb) {
         self.cached_posts.invalidate(&id).await;
         self.cached_posts_array
             .invalidate_entries_if(move |_, value| {
@@ -344,7 +599,7 @@ impl PostSystem {
             .unwrap();
     }
 
-    const POST_NO_CONTENT_COLS: &'static str = "id, title, tags, created_by_id, content_type, likes, dislikes, views, category_id, created_at, last_edit_at, last_edit_by_id, last_comment_at, last_comment_by_id, total_comment, total_comment_post, cover_url, status, top_index";
+    const POST_NO_CONTENT_COLS: &'static str = "id, title, tags, created_by_id, content_type, likes, dislikes, views, category_id, created_at, last_edit_at, last_edit_by_id, last_comment_at, last_comment_by_id, total_comment, total_comment_post, cover_url, status, top_index, deleted_at, deleted_by_id, locked, lock_reason, locked_at, hot_score, excerpt, word_count, reading_time, accepted_comment_id";
 
     pub async fn get_post(
         &self,
@@ -373,7 +628,7 @@ impl PostSystem {
         tx: &mut SqliteConnection,
         id: usizedb,
     ) -> Result<PostBaseInfo> {
-        Ok(sqlx::query_as("SELECT id, created_by_id, content_type, likes, dislikes, views, category_id, created_at, last_edit_at, last_edit_by_id, last_comment_at, last_comment_by_id, total_comment, total_comment_post, cover_url, status, top_index FROM posts WHERE id=? LIMIT 1")
+        Ok(sqlx::query_as("SELECT id, created_by_id, content_type, likes, dislikes, views, category_id, created_at, last_edit_at, last_edit_by_id, last_comment_at, last_comment_by_id, total_comment, total_comment_post, cover_url, status, top_index, deleted_at, deleted_by_id, locked, lock_reason, locked_at, accepted_comment_id FROM posts WHERE id=? LIMIT 1")
         .bind(id)
         .fetch_one(&mut *tx).await?)
     }
@@ -384,17 +639,30 @@ impl PostSystem {
             let cover_url: Option<String> = row.get("cover_url");
             cover_url.map(|url| self.s3.get_real_url(url))
         };
+        let hidden = status == PostStatus::Banned || status == PostStatus::Deleted;
+        let content: Option<String> = if !hidden && fetch_content {
+            row.try_get("content").unwrap()
+        } else {
+            None
+        };
+        let excerpt = if hidden {
+            String::new()
+        } else {
+            let excerpt: Option<String> = row.try_get("excerpt").unwrap();
+            excerpt.unwrap_or_default()
+        };
+        let content_type: ContentType = row.try_get("content_type").unwrap();
+        let accepted_comment_id: usizedb = row.try_get("accepted_comment_id").unwrap();
+        let rendered_html = content
+            .as_ref()
+            .and_then(|content| self.render_html(content_type, content));
         PostInfo {
             id: row.try_get("id").unwrap(),
             created_by_id: row.try_get("created_by_id").unwrap(),
             title: row.try_get("title").unwrap(),
-            content: if status != PostStatus::Banned && fetch_content {
-                row.try_get("content").unwrap()
-            } else {
-                None
-            },
+            content,
             status,
-            content_type: row.try_get("content_type").unwrap(),
+            content_type,
             likes: row.try_get("likes").unwrap(),
             dislikes: row.try_get("dislikes").unwrap(),
             views: row.try_get("views").unwrap(),
@@ -408,18 +676,91 @@ impl PostSystem {
             total_comment: row.try_get("total_comment").unwrap(),
             total_comment_post: row.try_get("total_comment_post").unwrap(),
             top_index: row.try_get("top_index").unwrap(),
+            deleted_at: row.try_get("deleted_at").unwrap(),
+            deleted_by_id: row.try_get("deleted_by_id").unwrap(),
+            locked: row.try_get("locked").unwrap(),
+            lock_reason: row.try_get("lock_reason").unwrap(),
+            locked_at: row.try_get("locked_at").unwrap(),
+            hot_score: row.try_get("hot_score").unwrap(),
+            excerpt,
+            word_count: row.try_get("word_count").unwrap(),
+            reading_time: row.try_get("reading_time").unwrap(),
+            accepted_comment_id,
+            solved: accepted_comment_id > 0,
             cover_url,
+            rendered_html,
         }
     }
 
-    fn get_order_sql(&self, order: PostAlgorithmOrder) -> &'static str {
+    fn get_content_meta(
+        config: &SafeConfig,
+        content_type: ContentType,
+        content: &str,
+    ) -> ContentMeta {
+        content_type.get_meta(
+            content,
+            config.excerpt_length as usize,
+            config.reading_words_per_minute as usizedb,
+        )
+    }
+
+    /// Fill the content meta of posts created before it's stored, excerpt is NULL only for them,
+    /// so each post is filled once.
+    async fn fill_content_meta(tx: &mut SqliteConnection, config: &SafeConfig) -> Result<()> {
+        let rows = sqlx::query("SELECT id, content, content_type FROM posts WHERE excerpt IS NULL")
+            .fetch_all(&mut *tx)
+            .await?;
+        for row in rows {
+            let content: String = row.get("content");
+            let meta = PostSystem::get_content_meta(config, row.get("content_type"), &content);
+            sqlx::query(
+                "UPDATE posts SET excerpt = ?, word_count = ?, reading_time = ? WHERE id = ?",
+            )
+            .bind(&meta.excerpt)
+            .bind(meta.word_count)
+            .bind(meta.reading_time)
+            .bind(row.get::<usizedb, _>("id"))
+            .execute(&mut *tx)
+            .await?;
+        }
+        Ok(())
+    }
+
+    fn render_html(&self, content_type: ContentType, content: &str) -> Option<String> {
+        if self.core.get_config().render_html {
+            Some(content_type.render_html(content))
+        } else {
+            None
+        }
+    }
+
+    fn get_order_col(&self, order: PostAlgorithmOrder) -> &'static str {
         match order {
-            PostAlgorithmOrder::Hot => {
-                "((likes + dislikes) * 500) + views + (total_comment * 100) DESC"
-            }
-            PostAlgorithmOrder::Views => "views DESC",
-            PostAlgorithmOrder::Likes => "likes DESC",
-            PostAlgorithmOrder::Newest => "created_at DESC",
+            PostAlgorithmOrder::Hot => "hot_score",
+            PostAlgorithmOrder::Views => "views",
+            PostAlgorithmOrder::Likes => "likes",
+            PostAlgorithmOrder::Newest => "created_at",
+        }
+    }
+
+    /// The cursor to fetch posts after this post.
+    pub fn get_cursor(&self, post: &PostInfo, order: PostAlgorithmOrder) -> Cursor {
+        let key = match order {
+            PostAlgorithmOrder::Hot => post.hot_score,
+            PostAlgorithmOrder::Views => post.views as f64,
+            PostAlgorithmOrder::Likes => post.likes as f64,
+            PostAlgorithmOrder::Newest => post.created_at as f64,
+        };
+        Cursor::new(post.top_index, key, post.id)
+    }
+
+    /// Condition of posts in the category, or in the category and its descendants.
+    fn category_condition(category_id: usizedb, include_descendants: bool) -> String {
+        if include_descendants {
+            let cte = fofo_utils::category_descendants_cte(&category_id.to_string());
+            format!("category_id IN ({cte} SELECT id FROM down)")
+        } else {
+            format!("category_id={category_id}")
         }
     }
 
@@ -434,16 +775,48 @@ impl PostSystem {
         created_by_id: Option<usizedb>,
         distinct: bool,
         top_order_enable: bool,
+        cursor: Option<Cursor>,
+        read_filter: Option<(PostReadFilter, usizedb)>,
+        solved: Option<bool>,
+        include_descendants: bool,
     ) -> Result<Vec<PostInfo>> {
-        let order_by = self.get_order_sql(order);
-        let offset = index * limit;
-        let mut conds = Vec::with_capacity(3);
+        let order_col = self.get_order_col(order);
+        let order_by = format!("{order_col} DESC, id DESC");
+        // cursor mode ignores index.
+        let (offset, cursor_cond) = match &cursor {
+            Some(cursor) => (
+                0,
+                format!(
+                    "AND {}",
+                    cursor.to_condition(order_col, true, top_order_enable)
+                ),
+            ),
+            None => (index * limit, String::new()),
+        };
+        let mut conds = Vec::with_capacity(4);
+        conds.push(format!(
+            "status NOT IN ({}, {})",
+            PostStatus::Deleted as u8,
+            PostStatus::Pending as u8
+        ));
         if let Some(category_id) = category_id {
-            conds.push(format!("category_id={category_id}"))
+            conds.push(Self::category_condition(category_id, include_descendants))
         }
         if let Some(created_by_id) = created_by_id {
             conds.push(format!("created_by_id={created_by_id}"))
         }
+        if let Some(solved) = solved {
+            conds.push(if solved {
+                "accepted_comment_id > 0".to_owned()
+            } else {
+                "accepted_comment_id = 0".to_owned()
+            })
+        }
+        let read_cond = read_filter.and_then(|(filter, user_id)| filter.to_condition(user_id));
+        let cacheable = read_cond.is_none(); // read status changes per user frequently.
+        if let Some(read_cond) = read_cond {
+            conds.push(read_cond)
+        }
         if !filter_time.is_lifetime() {
             let time = filter_time.to_timestamp(true);
             conds.push(if top_order_enable {
@@ -452,11 +825,7 @@ impl PostSystem {
                 format!("created_at >= {time}")
             });
         }
-        let where_conds = if conds.is_empty() {
-            Cow::Borrowed("")
-        } else {
-            Cow::Owned(format!("WHERE {}", conds.join(" AND ")))
-        };
+        let where_conds = format!("WHERE {}", conds.join(" AND "));
         let top_order = if top_order_enable {
             "top_index DESC,"
         } else {
@@ -469,11 +838,11 @@ impl PostSystem {
                 "WITH cte AS (
                     SELECT {cols}, ROW_NUMBER() OVER (PARTITION BY created_by_id ORDER BY {top_order} {order_by}) AS rn FROM posts {where_conds} ORDER BY {top_order} {order_by}
                   )
-                  SELECT {cols} FROM cte WHERE rn = 1 LIMIT {limit} OFFSET {offset};",
+                  SELECT {cols} FROM cte WHERE rn = 1 {cursor_cond} ORDER BY {top_order} {order_by} LIMIT {limit} OFFSET {offset};",
             )
         } else {
             format!(
-                "SELECT {cols} FROM posts {where_conds} ORDER BY {top_order} {order_by} LIMIT {limit} OFFSET {offset};"
+                "SELECT {cols} FROM posts {where_conds} {cursor_cond} ORDER BY {top_order} {order_by} LIMIT {limit} OFFSET {offset};"
             )
         };
         let key = PostArrayKey {
@@ -481,7 +850,12 @@ impl PostSystem {
             filter_time,
         };
 
-        Ok(if let Some(cached) = self.cached_posts_array.get(&key) {
+        let cached = if cacheable {
+            self.cached_posts_array.get(&key)
+        } else {
+            None
+        };
+        Ok(if let Some(cached) = cached {
             cached
         } else {
             let mut arr = Vec::with_capacity(limit as _);
@@ -491,7 +865,9 @@ impl PostSystem {
                     arr.push(self.from_row(row, false).await)
                 }
             }
-            self.cached_posts_array.insert(key, arr.clone()).await;
+            if cacheable {
+                self.cached_posts_array.insert(key, arr.clone()).await;
+            }
             arr
         })
     }
@@ -504,15 +880,35 @@ impl PostSystem {
         created_by_id: Option<usizedb>,
         distinct: bool,
         top_order_enable: bool,
+        read_filter: Option<(PostReadFilter, usizedb)>,
+        solved: Option<bool>,
+        include_descendants: bool,
     ) -> Result<usizedb> {
         // execute a query to get the row count of a table
-        let mut conds = Vec::with_capacity(3);
+        let mut conds = Vec::with_capacity(4);
+        conds.push(format!(
+            "status NOT IN ({}, {})",
+            PostStatus::Deleted as u8,
+            PostStatus::Pending as u8
+        ));
         if let Some(category_id) = category_id {
-            conds.push(format!("category_id={category_id}"))
+            conds.push(Self::category_condition(category_id, include_descendants))
         }
         if let Some(created_by_id) = created_by_id {
             conds.push(format!("created_by_id={created_by_id}"))
         }
+        if let Some(solved) = solved {
+            conds.push(if solved {
+                "accepted_comment_id > 0".to_owned()
+            } else {
+                "accepted_comment_id = 0".to_owned()
+            })
+        }
+        let read_cond = read_filter.and_then(|(filter, user_id)| filter.to_condition(user_id));
+        let cacheable = read_cond.is_none(); // read status changes per user frequently.
+        if let Some(read_cond) = read_cond {
+            conds.push(read_cond)
+        }
         if !filter_time.is_lifetime() {
             let time = filter_time.to_timestamp(true);
             conds.push(if top_order_enable {
@@ -521,11 +917,7 @@ impl PostSystem {
                 format!("created_at >= {time}")
             });
         }
-        let where_conds = if conds.is_empty() {
-            Cow::Borrowed("")
-        } else {
-            Cow::Owned(format!("WHERE {}", conds.join(" AND ")))
-        };
+        let where_conds = format!("WHERE {}", conds.join(" AND "));
         let query_str = if distinct {
             format!(
                 "WITH cte AS (
@@ -541,13 +933,20 @@ impl PostSystem {
             filter_time,
         };
 
-        Ok(if let Some(cached) = self.cached_posts_count.get(&key) {
+        let cached = if cacheable {
+            self.cached_posts_count.get(&key)
+        } else {
+            None
+        };
+        Ok(if let Some(cached) = cached {
             cached
         } else {
             let count: usizedb = sqlx::query_scalar(&key.query_str)
                 .fetch_one(&mut *tx)
                 .await?; // bind time and status because `conds.push(format!("created_at >= ? AND status != ?"));`
-            self.cached_posts_count.insert(key, count).await;
+            if cacheable {
+                self.cached_posts_count.insert(key, count).await;
+            }
             count
         })
     }
@@ -558,11 +957,16 @@ impl PostSystem {
         id: usizedb,
         status: PostStatus,
     ) -> Result<bool> {
+        let was_pending = self.get_status(tx, id).await? == PostStatus::Pending;
         let r = sqlx::query("UPDATE posts SET status = ? WHERE id = ?")
             .bind(status)
             .bind(id)
             .execute(&mut *tx)
             .await?;
+        if was_pending && status != PostStatus::Pending {
+            self.count_post(tx, id).await?;
+        }
+        fofo_utils::mark_post_stats_dirty(&mut *tx, id).await?;
         self.invalidate_cache(id).await;
         Ok(r.rows_affected() == 1)
     }
@@ -574,4 +978,681 @@ impl PostSystem {
             .await?;
         Ok(r.get("status"))
     }
+
+    pub async fn delete_post(
+        &self,
+        tx: &mut SqliteConnection,
+        id: usizedb,
+        user_id: usizedb,
+    ) -> Result<bool> {
+        let now = Utc::now().timestamp();
+        let was_pending = self.get_status(tx, id).await? == PostStatus::Pending;
+        let r = sqlx::query(
+            "UPDATE posts SET status = ?, deleted_at = ?, deleted_by_id = ? WHERE id = ? AND status != ?",
+        )
+        .bind(PostStatus::Deleted)
+        .bind(now)
+        .bind(user_id)
+        .bind(id)
+        .bind(PostStatus::Deleted)
+        .execute(&mut *tx)
+        .await?;
+        if r.rows_affected() == 1 {
+            // deleted posts are counted until purged, the rejected pending post as well.
+            if was_pending {
+                self.count_post(tx, id).await?;
+            }
+            fofo_utils::mark_post_stats_dirty(&mut *tx, id).await?;
+        }
+        self.invalidate_cache(id).await;
+        Ok(r.rows_affected() == 1)
+    }
+
+    pub async fn approve_post(&self, tx: &mut SqliteConnection, id: usizedb) -> Result<bool> {
+        let r = sqlx::query("UPDATE posts SET status = ? WHERE id = ? AND status = ?")
+            .bind(PostStatus::Active)
+            .bind(id)
+            .bind(PostStatus::Pending)
+            .execute(&mut *tx)
+            .await?;
+        if r.rows_affected() == 1 {
+            let (category_id, created_by_id) = self.count_post(tx, id).await?;
+            // the approved post is counted in today's stats of category.
+            fofo_utils::increment_category_daily_stats(&mut *tx, category_id, 1, 0, 0, 0).await?;
+            fofo_utils::add_category_daily_poster(&mut *tx, category_id, created_by_id).await?;
+            fofo_utils::mark_post_stats_dirty(&mut *tx, id).await?;
+        }
+        // pending post is not inside any list.
+        self.cached_posts.invalidate(&id).await;
+        self.cached_posts_array.invalidate_all();
+        self.cached_posts_count.invalidate_all();
+        Ok(r.rows_affected() == 1)
+    }
+
+    /// Add the post to the total post of its category and author, it is not counted while pending.
+    /// Return the category id and author id of post.
+    async fn count_post(
+        &self,
+        tx: &mut SqliteConnection,
+        id: usizedb,
+    ) -> Result<(usizedb, usizedb)> {
+        let (category_id, created_by_id): (usizedb, usizedb) =
+            sqlx::query_as("SELECT category_id, created_by_id FROM posts WHERE id = ?")
+                .bind(id)
+                .fetch_one(&mut *tx)
+                .await?;
+        fofo_utils::increment_category_total_post(&mut *tx, category_id, 1).await?;
+        fofo_utils::increment_user_total_post(&mut *tx, created_by_id, 1).await?;
+        Ok((category_id, created_by_id))
+    }
+
+    /// Pending posts in the category or all categories, oldest first.
+    pub async fn get_pending_posts(
+        &self,
+        tx: &mut SqliteConnection,
+        category_id: Option<usizedb>,
+        index: usizedb,
+        limit: usizedb,
+    ) -> Result<Vec<PostInfo>> {
+        let cols: &'static str = Self::POST_NO_CONTENT_COLS;
+        let category_cond = match category_id {
+            Some(category_id) => format!("AND category_id={category_id}"),
+            None => String::new(),
+        };
+        let offset = index * limit;
+        let mut rows = sqlx::query(&format!(
+            "SELECT {cols} FROM posts WHERE status=? {category_cond} ORDER BY id ASC LIMIT {limit} OFFSET {offset}"
+        ))
+        .bind(PostStatus::Pending)
+        .fetch(&mut *tx);
+        let mut arr = Vec::with_capacity(limit as _);
+        while let Some(row) = rows.try_next().await? {
+            arr.push(self.from_row(row, false).await)
+        }
+        Ok(arr)
+    }
+
+    pub async fn get_pending_post_count(
+        &self,
+        tx: &mut SqliteConnection,
+        category_id: Option<usizedb>,
+    ) -> Result<usizedb> {
+        let category_cond = match category_id {
+            Some(category_id) => format!("AND category_id={category_id}"),
+            None => String::new(),
+        };
+        Ok(sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM posts WHERE status=? {category_cond}"
+        ))
+        .bind(PostStatus::Pending)
+        .fetch_one(&mut *tx)
+        .await?)
+    }
+
+    /// Created time of the last post of user in the category, 0 means never posted.
+    pub async fn get_last_post_at(
+        &self,
+        tx: &mut SqliteConnection,
+        user_id: usizedb,
+        category_id: usizedb,
+    ) -> Result<usizedb> {
+        Ok(sqlx::query_scalar(
+            "SELECT COALESCE(MAX(created_at), 0) FROM posts WHERE created_by_id=? AND category_id=?",
+        )
+        .bind(user_id)
+        .bind(category_id)
+        .fetch_one(&mut *tx)
+        .await?)
+    }
+
+    pub async fn restore_post(&self, tx: &mut SqliteConnection, id: usizedb) -> Result<bool> {
+        let r = sqlx::query(
+            "UPDATE posts SET status = ?, deleted_at = 0, deleted_by_id = 0 WHERE id = ? AND status = ?",
+        )
+        .bind(PostStatus::Active)
+        .bind(id)
+        .bind(PostStatus::Deleted)
+        .execute(&mut *tx)
+        .await?;
+        if r.rows_affected() == 1 {
+            fofo_utils::mark_post_stats_dirty(&mut *tx, id).await?;
+        }
+        self.invalidate_cache(id).await;
+        Ok(r.rows_affected() == 1)
+    }
+
+    /// Gravity based hot score, `total_like` is likes plus dislikes.
+    /// The age is counted from the time between created and last comment.
+    fn get_hot_score(
+        &self,
+        total_like: usizedb,
+        views: usizedb,
+        total_comment: usizedb,
+        created_at: usizedb,
+        last_comment_at: usizedb,
+        now: usizedb,
+    ) -> f64 {
+        let config = self.core.get_config();
+        let points = total_like as f64 * config.hot_likes_weight
+            + views as f64 * config.hot_views_weight
+            + total_comment as f64 * config.hot_comments_weight;
+        let created_at = created_at as f64;
+        let active_at = created_at.max(last_comment_at as f64);
+        let time =
+            created_at + (active_at - created_at) * config.hot_activity_weight.clamp(0.0, 1.0);
+        let age_hours = ((now as f64 - time) / 3600.0).max(0.0);
+        (points + 1.0) / (age_hours + 2.0).powf(config.hot_gravity)
+    }
+
+    /// Recompute hot score of posts active in `hot_window_days`.
+    pub async fn recompute_hot_scores(&self, tx: &mut SqliteConnection) -> Result<usizedb> {
+        let window_days = self.core.get_config().hot_window_days;
+        let now = Utc::now();
+        let earliest = if window_days > 0 {
+            let earliest = (now - Duration::days(window_days as _)).timestamp();
+            sqlx::query(
+                "UPDATE posts SET hot_score = 0 WHERE hot_score != 0 AND MAX(created_at, last_comment_at) < ?",
+            )
+            .bind(earliest)
+            .execute(&mut *tx)
+            .await?;
+            earliest
+        } else {
+            0
+        };
+        let rows = sqlx::query(
+            "SELECT id, likes, dislikes, views, total_comment, created_at, last_comment_at FROM posts WHERE status != ? AND MAX(created_at, last_comment_at) >= ?",
+        )
+        .bind(PostStatus::Deleted)
+        .bind(earliest)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let now = now.timestamp() as usizedb;
+        let total = rows.len() as usizedb;
+        for row in rows {
+            let hot_score = self.get_hot_score(
+                row.get::<usizedb, _>("likes") + row.get::<usizedb, _>("dislikes"),
+                row.get("views"),
+                row.get("total_comment"),
+                row.get("created_at"),
+                row.get("last_comment_at"),
+                now,
+            );
+            sqlx::query("UPDATE posts SET hot_score = ? WHERE id = ?")
+                .bind(hot_score)
+                .bind(row.get::<usizedb, _>("id"))
+                .execute(&mut *tx)
+                .await?;
+        }
+        self.cached_posts_array.invalidate_all();
+        Ok(total)
+    }
+
+    /// Lock or unlock post, locked post refuses new comments.
+    pub async fn set_lock(
+        &self,
+        tx: &mut SqliteConnection,
+        id: usizedb,
+        locked: bool,
+        reason: &str,
+    ) -> Result<bool> {
+        let now = Utc::now().timestamp();
+        let r =
+            sqlx::query("UPDATE posts SET locked = ?, lock_reason = ?, locked_at = ? WHERE id = ?")
+                .bind(locked)
+                .bind(if locked { reason } else { "" })
+                .bind(now)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        self.invalidate_cache(id).await;
+        Ok(r.rows_affected() == 1)
+    }
+
+    /// Lock the posts without activity in `auto_lock_days`.
+    /// Posts locked or unlocked before are skipped, so a manual unlock is kept.
+    pub async fn lock_inactive_posts(&self, tx: &mut SqliteConnection) -> Result<usizedb> {
+        let lock_days = self.core.get_config().auto_lock_days;
+        if lock_days == 0 {
+            return Ok(0);
+        }
+        let now = Utc::now();
+        let earliest = (now - Duration::days(lock_days as _)).timestamp();
+        let r = sqlx::query(
+            "UPDATE posts SET locked = 1, lock_reason = ?, locked_at = ? 
+            WHERE locked = 0 AND locked_at = 0 AND status = ? AND MAX(created_at, last_comment_at) < ?",
+        )
+        .bind(format!("Locked after {lock_days} days without activity."))
+        .bind(now.timestamp())
+        .bind(PostStatus::Active)
+        .bind(earliest)
+        .execute(&mut *tx)
+        .await?;
+        if r.rows_affected() > 0 {
+            self.cached_posts.invalidate_all();
+            self.cached_posts_array.invalidate_all();
+        }
+        Ok(r.rows_affected() as usizedb)
+    }
+
+    /// Permanently remove the posts deleted before `deleted_purge_days`, with their comments and like status.
+    /// Return the storage keys of their covers, which are deleted after the transaction is committed.
+    pub async fn purge_deleted_posts(
+        &self,
+        tx: &mut SqliteConnection,
+        limit: usizedb,
+    ) -> Result<Vec<String>> {
+        let purge_days = self.core.get_config().deleted_purge_days;
+        if purge_days == 0 {
+            return Ok(vec![]);
+        }
+        let earliest = (Utc::now() - Duration::days(purge_days as _)).timestamp();
+        let rows = sqlx::query(
+            "SELECT id, created_by_id, category_id, cover_url FROM posts WHERE status = ? AND deleted_at < ? LIMIT ?",
+        )
+        .bind(PostStatus::Deleted)
+        .bind(earliest)
+        .bind(limit)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut category_id_map = HashMap::with_capacity(rows.len()); // key is category id, value is total post purged.
+        let mut user_id_map = HashMap::with_capacity(rows.len()); // key is user id, value is total post purged.
+        let mut cover_keys = Vec::new();
+        for row in rows {
+            let id: usizedb = row.get("id");
+            let cover_url: Option<String> = row.get("cover_url");
+            *category_id_map
+                .entry(row.get::<usizedb, _>("category_id"))
+                .or_insert(0) += 1;
+            *user_id_map
+                .entry(row.get::<usizedb, _>("created_by_id"))
+                .or_insert(0) += 1;
+            // comments and likes of post are counted from its created day.
+            fofo_utils::mark_post_stats_dirty(&mut *tx, id).await?;
+
+            let commenters = sqlx::query(
+                "SELECT created_by_id, COUNT(*) AS total FROM comments WHERE post_id = ? GROUP BY created_by_id",
+            )
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await?;
+            for commenter in commenters {
+                fofo_utils::decrement_user_total_comment(
+                    &mut *tx,
+                    commenter.get("created_by_id"),
+                    commenter.get("total"),
+                )
+                .await?;
+            }
+            sqlx::query("DELETE FROM all_like_status WHERE flag = ? AND flag_ref_id IN (SELECT id FROM comments WHERE post_id = ?)")
+                .bind(LikeStatusFlag::TargetComment)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM all_like_status WHERE flag = ? AND flag_ref_id = ?")
+                .bind(LikeStatusFlag::TargetPost)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM bookmarks WHERE flag = ? AND flag_ref_id IN (SELECT id FROM comments WHERE post_id = ?)")
+                .bind(BookmarkFlag::TargetComment)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM mentions WHERE flag = ? AND flag_ref_id IN (SELECT id FROM comments WHERE post_id = ?)")
+                .bind(MentionFlag::TargetComment)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM comment_quotes WHERE comment_id IN (SELECT id FROM comments WHERE post_id = ?)")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM bookmarks WHERE flag = ? AND flag_ref_id = ?")
+                .bind(BookmarkFlag::TargetPost)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM comments WHERE post_id = ?")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            self.delete_attachments(&mut *tx, id).await?;
+            sqlx::query("DELETE FROM posts WHERE id = ?")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+
+            if let Some(cover_url) = cover_url {
+                cover_keys.extend(self.unmark_cover(&mut *tx, cover_url).await?);
+            }
+            self.invalidate_cache(id).await;
+        }
+        for (category_id, total_post) in category_id_map {
+            fofo_utils::decrement_category_total_post(&mut *tx, category_id, total_post).await?;
+        }
+        for (user_id, total_post) in user_id_map {
+            fofo_utils::decrement_user_total_post(&mut *tx, user_id, total_post).await?;
+        }
+        Ok(cover_keys)
+    }
+
+    /// Unmark the cover, return its key if it is uploaded to our storage.
+    async fn unmark_cover(
+        &self,
+        tx: &mut SqliteConnection,
+        cover_url: String,
+    ) -> Result<Option<String>> {
+        // cover stored as key if it is uploaded to our storage.
+        let real_url = self.s3.get_real_url(cover_url);
+        Ok(match self.s3.try_parse_url_to_key(&real_url) {
+            Some(key) => {
+                self.s3.unmark(&mut *tx, key).await?;
+                Some(key.to_owned())
+            }
+            None => None,
+        })
+    }
+
+    /// Delete the poll and daily views of post.
+    async fn delete_attachments(&self, tx: &mut SqliteConnection, post_id: usizedb) -> Result<()> {
+        sqlx::query("DELETE FROM post_daily_views WHERE post_id = ?")
+            .bind(post_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM post_reads WHERE post_id = ?")
+            .bind(post_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM subscriptions WHERE flag = ? AND flag_ref_id = ?")
+            .bind(SubscriptionFlag::TargetPost)
+            .bind(post_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM mentions WHERE flag = ? AND flag_ref_id = ?")
+            .bind(MentionFlag::TargetPost)
+            .bind(post_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "DELETE FROM poll_votes WHERE poll_id IN (SELECT id FROM polls WHERE post_id = ?)",
+        )
+        .bind(post_id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "DELETE FROM poll_options WHERE poll_id IN (SELECT id FROM polls WHERE post_id = ?)",
+        )
+        .bind(post_id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM polls WHERE post_id = ?")
+            .bind(post_id)
+            .execute(&mut *tx)
+            .await?;
+        Ok(())
+    }
+
+    /// Move post and its comments to another category.
+    pub async fn move_post(
+        &self,
+        tx: &mut SqliteConnection,
+        id: usizedb,
+        category_id: usizedb,
+    ) -> Result<bool> {
+        let post = self.get_post_base(tx, id).await?;
+        if post.category_id == category_id {
+            return Ok(false);
+        }
+        let r = sqlx::query("UPDATE posts SET category_id = ? WHERE id = ?")
+            .bind(category_id)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE comments SET category_id = ? WHERE post_id = ?")
+            .bind(category_id)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        if post.status != PostStatus::Pending {
+            fofo_utils::decrement_category_total_post(&mut *tx, post.category_id, 1).await?;
+            fofo_utils::increment_category_total_post(&mut *tx, category_id, 1).await?;
+        }
+        fofo_utils::mark_post_stats_dirty(&mut *tx, id).await?;
+        self.invalidate_cache(id).await;
+        Ok(r.rows_affected() == 1)
+    }
+
+    /// Merge post into target post.
+    /// The post becomes a comment of target post, and its comments are moved to target post.
+    /// Likes of post go to the new comment, its bookmarks and subscriptions go to target post,
+    /// then the post is deleted by `user_id` like other deleted posts.
+    pub async fn merge_post(
+        &self,
+        tx: &mut SqliteConnection,
+        id: usizedb,
+        target_id: usizedb,
+        user_id: usizedb,
+    ) -> Result<bool> {
+        if id == target_id {
+            return Ok(false);
+        }
+        let source = sqlx::query("SELECT created_by_id, content, content_type, created_at, last_edit_at, last_edit_by_id, likes, dislikes, status FROM posts WHERE id = ? LIMIT 1")
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+        let target = self.get_post_base(tx, target_id).await?;
+        let created_by_id: usizedb = source.get("created_by_id");
+        let now = Utc::now().timestamp();
+        let r = sqlx::query(
+            "UPDATE posts SET status = ?, deleted_at = ?, deleted_by_id = ?, likes = 0, dislikes = 0 WHERE id = ? AND status != ?",
+        )
+        .bind(PostStatus::Deleted)
+        .bind(now)
+        .bind(user_id)
+        .bind(id)
+        .bind(PostStatus::Deleted)
+        .execute(&mut *tx)
+        .await?;
+        if r.rows_affected() == 0 {
+            return Ok(false);
+        }
+        // deleted posts are counted until purged, the merged pending post as well.
+        if source.get::<PostStatus, _>("status") == PostStatus::Pending {
+            self.count_post(tx, id).await?;
+        }
+        fofo_utils::mark_post_stats_dirty(&mut *tx, id).await?;
+
+        sqlx::query("UPDATE comments SET post_id = ?, category_id = ? WHERE post_id = ?")
+            .bind(target_id)
+            .bind(target.category_id)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        let comment_id = sqlx::query("INSERT INTO comments (parent_id, post_id, category_id, reply_comment_id, reply_user_id, created_at, created_by_id, last_edit_at, last_edit_by_id, content, content_type, likes, dislikes, status, total_comment, last_comment_by_id, last_comment_at, top_index) VALUES (0, ?, ?, 0, ?, ?, ?, ?, ?, ?, ?, 0, 0, ?, 0, 0, 0, 0)")
+            .bind(target_id)
+            .bind(target.category_id)
+            .bind(target.created_by_id)
+            .bind(source.get::<usizedb, _>("created_at"))
+            .bind(created_by_id)
+            .bind(source.get::<usizedb, _>("last_edit_at"))
+            .bind(source.get::<usizedb, _>("last_edit_by_id"))
+            .bind(source.get::<String, _>("content"))
+            .bind(source.get::<ContentType, _>("content_type"))
+            .bind(CommentStatus::Active)
+            .execute(&mut *tx)
+            .await?
+            .last_insert_rowid() as usizedb;
+
+        // likes are counted from their created days.
+        let earliest: Option<usizedb> = sqlx::query_scalar(
+            "SELECT MIN(created_at) FROM all_like_status WHERE flag = ? AND flag_ref_id = ?",
+        )
+        .bind(LikeStatusFlag::TargetPost)
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if let Some(earliest) = earliest {
+            fofo_utils::mark_stats_dirty(&mut *tx, earliest).await?;
+        }
+        sqlx::query("UPDATE all_like_status SET flag = ?, flag_ref_id = ? WHERE flag = ? AND flag_ref_id = ?")
+            .bind(LikeStatusFlag::TargetComment)
+            .bind(comment_id)
+            .bind(LikeStatusFlag::TargetPost)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        fofo_utils::increment_comment_like(
+            &mut *tx,
+            comment_id,
+            source.get("likes"),
+            source.get("dislikes"),
+        )
+        .await?;
+        // users who already have the target post keep their own bookmark and subscription.
+        sqlx::query(
+            "UPDATE OR IGNORE bookmarks SET flag_ref_id = ? WHERE flag = ? AND flag_ref_id = ?",
+        )
+        .bind(target_id)
+        .bind(BookmarkFlag::TargetPost)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM bookmarks WHERE flag = ? AND flag_ref_id = ?")
+            .bind(BookmarkFlag::TargetPost)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "UPDATE OR IGNORE subscriptions SET flag_ref_id = ? WHERE flag = ? AND flag_ref_id = ?",
+        )
+        .bind(target_id)
+        .bind(SubscriptionFlag::TargetPost)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM subscriptions WHERE flag = ? AND flag_ref_id = ?")
+            .bind(SubscriptionFlag::TargetPost)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        fofo_utils::recount_post_comments(&mut *tx, target_id).await?;
+        fofo_utils::increment_user_total_comment(&mut *tx, created_by_id, 1).await?;
+        self.invalidate_cache(id).await;
+        self.invalidate_cache(target_id).await;
+        Ok(true)
+    }
+
+    /// Split comments out of post into a new post.
+    /// The earliest comment becomes the content of new post, and the replies of split comments are moved with them.
+    pub async fn split_post(
+        &self,
+        tx: &mut SqliteConnection,
+        id: usizedb,
+        mut post: PostToSplit,
+    ) -> Result<Option<PostInfo>> {
+        post.comment_ids.sort_unstable();
+        post.comment_ids.dedup();
+        if post.comment_ids.is_empty() {
+            return Ok(None);
+        }
+        let ids_str = post
+            .comment_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+        let r = sqlx::query(&format!(
+            "SELECT COUNT(*) AS total FROM comments WHERE post_id = ? AND id IN ({ids_str})"
+        ))
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if r.get::<usizedb, _>("total") != post.comment_ids.len() as usizedb {
+            return Ok(None);
+        }
+
+        let first = sqlx::query("SELECT id, created_by_id, content, content_type, created_at, last_edit_at, last_edit_by_id FROM comments WHERE id = ? LIMIT 1")
+            .bind(post.comment_ids[0])
+            .fetch_one(&mut *tx)
+            .await?;
+        let first_id: usizedb = first.get("id");
+        let created_by_id: usizedb = first.get("created_by_id");
+        let content: String = first.get("content");
+        let content_type: ContentType = first.get("content_type");
+        let meta = PostSystem::get_content_meta(&self.core.get_config(), content_type, &content);
+        let r = sqlx::query("INSERT INTO posts (created_by_id, title, content, content_type, likes, dislikes, views, category_id, tags, created_at, last_edit_at, last_edit_by_id, status, last_comment_at, last_comment_by_id, total_comment, total_comment_post, cover_url, top_index, excerpt, word_count, reading_time) VALUES (?, ?, ?, ?, 0, 0, 0, ?, ?, ?, ?, ?, ?, 0, ?, 0, 0, NULL, 0, ?, ?, ?)")
+            .bind(created_by_id)
+            .bind(&post.title)
+            .bind(&content)
+            .bind(content_type)
+            .bind(post.category_id)
+            .bind(fofo_utils::array_to_string(&post.tags))
+            .bind(first.get::<usizedb, _>("created_at"))
+            .bind(first.get::<usizedb, _>("last_edit_at"))
+            .bind(first.get::<usizedb, _>("last_edit_by_id"))
+            .bind(PostStatus::Active)
+            .bind(created_by_id)
+            .bind(&meta.excerpt)
+            .bind(meta.word_count)
+            .bind(meta.reading_time)
+            .execute(&mut *tx)
+            .await?;
+        let new_id = r.last_insert_rowid() as usizedb;
+
+        // the first comment is the content of new post now.
+        sqlx::query("DELETE FROM all_like_status WHERE flag = ? AND flag_ref_id = ?")
+            .bind(LikeStatusFlag::TargetComment)
+            .bind(first_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM bookmarks WHERE flag = ? AND flag_ref_id = ?")
+            .bind(BookmarkFlag::TargetComment)
+            .bind(first_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM comments WHERE id = ?")
+            .bind(first_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(&format!(
+            "UPDATE comments SET post_id = ?, category_id = ? WHERE post_id = ? AND (id IN ({ids_str}) OR parent_id IN ({ids_str}))"
+        ))
+        .bind(new_id)
+        .bind(post.category_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(&format!(
+            "UPDATE posts SET accepted_comment_id = 0 WHERE id = ? AND accepted_comment_id IN ({ids_str})"
+        ))
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        // comments lost their parent reply new post directly.
+        sqlx::query("UPDATE comments SET parent_id = 0, reply_comment_id = 0, reply_user_id = ? WHERE post_id = ? AND parent_id != 0 AND parent_id NOT IN (SELECT id FROM comments WHERE post_id = ?)")
+            .bind(created_by_id)
+            .bind(new_id)
+            .bind(new_id)
+            .execute(&mut *tx)
+            .await?;
+        // comments left in old post replied to split comments reply their parent instead.
+        sqlx::query("UPDATE comments SET reply_comment_id = parent_id WHERE post_id = ? AND reply_comment_id != 0 AND reply_comment_id NOT IN (SELECT id FROM comments WHERE post_id = ?)")
+            .bind(id)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        fofo_utils::recount_post_comments(&mut *tx, id).await?;
+        fofo_utils::recount_post_comments(&mut *tx, new_id).await?;
+        fofo_utils::mark_post_stats_dirty(&mut *tx, new_id).await?;
+        fofo_utils::increment_category_total_post(&mut *tx, post.category_id, 1).await?;
+        fofo_utils::increment_user_total_post(&mut *tx, created_by_id, 1).await?;
+        fofo_utils::decrement_user_total_comment(&mut *tx, created_by_id, 1).await?;
+        self.invalidate_cache(id).await;
+        self.get_post(tx, new_id, true).await
+    }
 }
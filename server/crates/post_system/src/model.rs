@@ -3,7 +3,9 @@ use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use sqlx::FromRow;
 
+use comment_system::model::CommentStatus;
 use fofo_utils::{usizedb, ContentType};
+use poll_system::model::PollToCreate;
 
 #[derive(Debug, Deserialize_repr, Serialize_repr, PartialEq, Clone, Copy, sqlx::Type)]
 #[repr(u8)]
@@ -11,6 +13,9 @@ pub enum PostStatus {
     Active,
     Archived,
     Banned,
+    Deleted,
+    /// Waiting for approval of moderator, only visible to author and moderators.
+    Pending,
 }
 
 #[derive(Debug, FromRow, Deserialize, Serialize, Clone)]
@@ -36,6 +41,36 @@ pub struct PostInfo {
     pub cover_url: Option<String>,
     #[serde(default)]
     pub top_index: usizedb,
+    #[serde(default)]
+    pub deleted_at: usizedb,
+    #[serde(default)]
+    pub deleted_by_id: usizedb,
+    #[serde(default)]
+    pub locked: bool,
+    #[serde(default)]
+    pub lock_reason: String,
+    /// The last time lock changed, 0 means never locked.
+    #[serde(default)]
+    pub locked_at: usizedb,
+    #[serde(default)]
+    pub hot_score: f64,
+    /// Plain text of the beginning of content.
+    #[serde(default)]
+    pub excerpt: String,
+    #[serde(default)]
+    pub word_count: usizedb,
+    /// Estimated reading time in minutes.
+    #[serde(default)]
+    pub reading_time: usizedb,
+    /// The accepted answer in Q&A category, 0 means none.
+    #[serde(default)]
+    pub accepted_comment_id: usizedb,
+    #[serde(default)]
+    pub solved: bool,
+    /// Sanitized HTML of content, only present when content is fetched.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub rendered_html: Option<String>,
 }
 
 #[derive(Debug, FromRow, Deserialize, Serialize, Clone)]
@@ -58,6 +93,20 @@ pub struct PostBaseInfo {
     pub cover_url: Option<String>,
     #[serde(default)]
     pub top_index: usizedb,
+    #[serde(default)]
+    pub deleted_at: usizedb,
+    #[serde(default)]
+    pub deleted_by_id: usizedb,
+    #[serde(default)]
+    pub locked: bool,
+    #[serde(default)]
+    pub lock_reason: String,
+    /// The last time lock changed, 0 means never locked.
+    #[serde(default)]
+    pub locked_at: usizedb,
+    /// The accepted answer in Q&A category, 0 means none.
+    #[serde(default)]
+    pub accepted_comment_id: usizedb,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -70,6 +119,11 @@ pub struct PostToCreate {
     pub cover_url: Option<String>,
     #[serde(default)]
     pub top_index: usizedb,
+    #[serde(default)]
+    pub poll: Option<PollToCreate>,
+    /// Created as pending post, decided by the policy of category.
+    #[serde(skip)]
+    pub pending: bool,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -81,6 +135,24 @@ pub struct PostToUpdate {
     pub cover_url: Option<String>,
     #[serde(default)]
     pub top_index: usizedb,
+    /// Move post to this category, need manage permission of both categories.
+    #[serde(default)]
+    pub category_id: Option<usizedb>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PostToSplit {
+    pub title: String,
+    pub category_id: usizedb,
+    pub tags: Vec<String>,
+    pub comment_ids: Vec<usizedb>,
+}
+
+#[derive(Debug, FromRow, Deserialize, Serialize, Clone)]
+pub struct PostDailyViews {
+    /// Timestamp of the day start in UTC.
+    pub day: usizedb,
+    pub views: usizedb,
 }
 
 #[derive(Serialize_repr, Deserialize_repr, PartialEq, Debug, Clone, Copy)]
@@ -92,6 +164,34 @@ pub enum PostAlgorithmOrder {
     Newest,
 }
 
+#[derive(Serialize_repr, Deserialize_repr, PartialEq, Debug, Clone, Copy, Default)]
+#[repr(u8)]
+pub enum PostReadFilter {
+    #[default]
+    All,
+    /// Posts read before and have new comments since then.
+    Unread,
+    /// Posts never read.
+    New,
+}
+
+impl PostReadFilter {
+    /// SQL condition on `posts` for the read status of user, None if all posts are wanted.
+    pub fn to_condition(&self, user_id: usizedb) -> Option<String> {
+        let category_read_at = format!("COALESCE((SELECT read_at FROM category_reads WHERE category_id = posts.category_id AND user_id = {user_id}), 0)");
+        let deleted = CommentStatus::Deleted as u8;
+        match self {
+            PostReadFilter::All => None,
+            PostReadFilter::Unread => Some(format!(
+                "EXISTS (SELECT 1 FROM post_reads pr JOIN comments c ON c.post_id = pr.post_id WHERE pr.post_id = posts.id AND pr.user_id = {user_id} AND c.id > pr.last_read_comment_id AND c.status != {deleted} AND c.created_by_id != {user_id} AND c.created_at > {category_read_at})"
+            )),
+            PostReadFilter::New => Some(format!(
+                "(NOT EXISTS (SELECT 1 FROM post_reads WHERE post_id = posts.id AND user_id = {user_id}) AND posts.created_at > {category_read_at})"
+            )),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 pub enum PostFilterTime {
     Lifetime,
@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use sqlx::{QueryBuilder, Row, SqliteConnection};
+
+use channel_cache::ChannelCacheTask;
+use comment_system::model::CommentStatus;
+use fofo_utils::usizedb;
+use shared_core::SharedCore;
+
+use model::PostRead;
+
+pub mod model;
+
+#[derive(Debug, Clone)]
+pub struct ReadSystem {
+    core: SharedCore,
+    post_read_task: Option<ChannelCacheTask<PostReadBundle, ()>>,
+    category_read_task: Option<ChannelCacheTask<CategoryReadBundle, ()>>,
+}
+
+#[derive(Debug, Clone)]
+struct PostReadBundle {
+    post_id: usizedb,
+    user_id: usizedb,
+    last_read_comment_id: usizedb,
+    read_at: usizedb,
+}
+
+#[derive(Debug, Clone)]
+struct CategoryReadBundle {
+    category_id: usizedb,
+    user_id: usizedb,
+    read_at: usizedb,
+}
+
+impl ReadSystem {
+    pub async fn new(core: SharedCore) -> Self {
+        let mut tx = core.begin_unwrap(true).await;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS post_reads(
+                post_id INT NOT NULL,
+                user_id INT NOT NULL,
+                last_read_comment_id INT NOT NULL,
+                read_at INT NOT NULL,
+
+                PRIMARY KEY(post_id, user_id),
+                FOREIGN KEY(post_id) REFERENCES posts(id),
+                FOREIGN KEY(user_id) REFERENCES users(id)
+            );
+            CREATE TABLE IF NOT EXISTS category_reads(
+                category_id INT NOT NULL,
+                user_id INT NOT NULL,
+                read_at INT NOT NULL,
+
+                PRIMARY KEY(category_id, user_id),
+                FOREIGN KEY(category_id) REFERENCES categories(id),
+                FOREIGN KEY(user_id) REFERENCES users(id)
+            )",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS post_reads_user_id_index
+            on post_reads (user_id);",
+        )
+        .execute(tx.as_mut())
+        .await
+        .unwrap(); // create indexes.
+
+        tx.commit().await.unwrap();
+        let config = core.get_config();
+        let mut this = ReadSystem {
+            core,
+            post_read_task: None,
+            category_read_task: None,
+        };
+
+        let t = this.clone();
+        this.post_read_task = Some(ChannelCacheTask::new(
+            "set_post_reads".into(),
+            config.buffer_size,
+            config.task_trigger_ms,
+            move |arr| {
+                let t = t.clone();
+                async move {
+                    let mut tx = t.core.begin_unwrap(true).await;
+                    let r = t.mark_posts_read_all(tx.as_mut(), arr).await.unwrap();
+                    tx.commit_unwrap().await;
+                    r
+                }
+            },
+        ));
+
+        let t = this.clone();
+        this.category_read_task = Some(ChannelCacheTask::new(
+            "set_category_reads".into(),
+            config.buffer_size,
+            config.task_trigger_ms,
+            move |arr| {
+                let t = t.clone();
+                async move {
+                    let mut tx = t.core.begin_unwrap(true).await;
+                    let r = t.mark_categories_read_all(tx.as_mut(), arr).await.unwrap();
+                    tx.commit_unwrap().await;
+                    r
+                }
+            },
+        ));
+
+        this
+    }
+
+    /// Mark comments of post read until `last_read_comment_id`, the marker never goes back.
+    pub async fn mark_post_read(
+        &self,
+        post_id: usizedb,
+        user_id: usizedb,
+        last_read_comment_id: usizedb,
+    ) -> Result<()> {
+        match self.post_read_task.as_ref() {
+            Some(task) => {
+                task.send(PostReadBundle {
+                    post_id,
+                    user_id,
+                    last_read_comment_id,
+                    read_at: Utc::now().timestamp() as usizedb,
+                })
+                .await?;
+                Ok(())
+            }
+            None => bail!("Don't have the task."),
+        }
+    }
+
+    /// Mark all posts and comments in category created before now read.
+    pub async fn mark_category_read(&self, category_id: usizedb, user_id: usizedb) -> Result<()> {
+        match self.category_read_task.as_ref() {
+            Some(task) => {
+                task.send(CategoryReadBundle {
+                    category_id,
+                    user_id,
+                    read_at: Utc::now().timestamp() as usizedb,
+                })
+                .await?;
+                Ok(())
+            }
+            None => bail!("Don't have the task."),
+        }
+    }
+
+    async fn mark_posts_read_all(
+        &self,
+        tx: &mut SqliteConnection,
+        arr: Vec<PostReadBundle>,
+    ) -> Result<Vec<()>> {
+        let mut list = Vec::with_capacity(arr.len());
+        list.resize(arr.len(), ());
+
+        let mut map: HashMap<(usizedb, usizedb), PostReadBundle> =
+            HashMap::with_capacity(arr.len());
+        for bundle in arr {
+            let key = (bundle.post_id, bundle.user_id);
+            match map.get_mut(&key) {
+                Some(v) => {
+                    v.last_read_comment_id =
+                        v.last_read_comment_id.max(bundle.last_read_comment_id);
+                    v.read_at = v.read_at.max(bundle.read_at);
+                }
+                None => {
+                    map.insert(key, bundle);
+                }
+            }
+        }
+        for chunk in map.into_values().collect::<Vec<_>>().chunks(500) {
+            QueryBuilder::new("INSERT INTO post_reads (post_id, user_id, last_read_comment_id, read_at) ")
+                .push_values(chunk, |mut b, bundle| {
+                    b.push_bind(bundle.post_id)
+                        .push_bind(bundle.user_id)
+                        .push_bind(bundle.last_read_comment_id)
+                        .push_bind(bundle.read_at);
+                })
+                .push(" ON CONFLICT(post_id, user_id) DO UPDATE SET last_read_comment_id = MAX(last_read_comment_id, excluded.last_read_comment_id), read_at = excluded.read_at")
+                .build()
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        Ok(list)
+    }
+
+    async fn mark_categories_read_all(
+        &self,
+        tx: &mut SqliteConnection,
+        arr: Vec<CategoryReadBundle>,
+    ) -> Result<Vec<()>> {
+        let mut list = Vec::with_capacity(arr.len());
+        list.resize(arr.len(), ());
+
+        let mut map: HashMap<(usizedb, usizedb), usizedb> = HashMap::with_capacity(arr.len());
+        for bundle in arr {
+            let read_at = map.entry((bundle.category_id, bundle.user_id)).or_insert(0);
+            *read_at = (*read_at).max(bundle.read_at);
+        }
+        for chunk in map.into_iter().collect::<Vec<_>>().chunks(500) {
+            QueryBuilder::new("INSERT INTO category_reads (category_id, user_id, read_at) ")
+                .push_values(chunk, |mut b, ((category_id, user_id), read_at)| {
+                    b.push_bind(*category_id)
+                        .push_bind(*user_id)
+                        .push_bind(*read_at);
+                })
+                .push(" ON CONFLICT(category_id, user_id) DO UPDATE SET read_at = MAX(read_at, excluded.read_at)")
+                .build()
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        Ok(list)
+    }
+
+    pub async fn get_post_read(
+        &self,
+        tx: &mut SqliteConnection,
+        post_id: usizedb,
+        user_id: usizedb,
+    ) -> Result<Option<PostRead>> {
+        Ok(sqlx::query_as::<_, PostRead>(
+            "SELECT * FROM post_reads WHERE post_id = ? AND user_id = ? LIMIT 1",
+        )
+        .bind(post_id)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?)
+    }
+
+    /// Get id of the newest comment in post, 0 if there is no comment.
+    pub async fn get_last_comment_id(
+        &self,
+        tx: &mut SqliteConnection,
+        post_id: usizedb,
+    ) -> Result<usizedb> {
+        Ok(
+            sqlx::query_scalar("SELECT COALESCE(MAX(id), 0) FROM comments WHERE post_id = ?")
+                .bind(post_id)
+                .fetch_one(&mut *tx)
+                .await?,
+        )
+    }
+
+    /// Count comments of other users unread by user, key is post id. Posts without unread comment are absent.
+    pub async fn get_unread_comments(
+        &self,
+        tx: &mut SqliteConnection,
+        post_ids: &[usizedb],
+        user_id: usizedb,
+    ) -> Result<HashMap<usizedb, usizedb>> {
+        if post_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let ids = post_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+        let rows = sqlx::query(&format!(
+            "SELECT c.post_id, COUNT(*) AS total FROM comments c
+            JOIN posts p ON p.id = c.post_id
+            LEFT JOIN post_reads pr ON pr.post_id = c.post_id AND pr.user_id = ?
+            LEFT JOIN category_reads cr ON cr.category_id = p.category_id AND cr.user_id = ?
+            WHERE c.post_id IN ({ids}) AND c.status != ? AND c.created_by_id != ?
+            AND c.id > COALESCE(pr.last_read_comment_id, 0) AND c.created_at > COALESCE(cr.read_at, 0)
+            GROUP BY c.post_id"
+        ))
+        .bind(user_id)
+        .bind(user_id)
+        .bind(CommentStatus::Deleted)
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.get("post_id"), row.get("total")))
+            .collect())
+    }
+}
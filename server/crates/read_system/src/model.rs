@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use fofo_utils::usizedb;
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct PostRead {
+    pub post_id: usizedb,
+    pub user_id: usizedb,
+    /// Comments with id greater than it are unread.
+    pub last_read_comment_id: usizedb,
+    pub read_at: usizedb,
+}
@@ -52,6 +52,7 @@ pub trait SimpleStorageService: std::fmt::Debug + Send + Sync {
         ref_id: usizedb,
         permanent: bool,
     ) -> Result<bool>;
+    async fn unmark(&self, tx: &mut SqliteConnection, key: &str) -> Result<bool>;
 }
 
 #[derive(Debug, Clone)]
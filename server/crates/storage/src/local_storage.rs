@@ -291,6 +291,10 @@ impl SimpleStorageService for LocalStorage {
         r
     }
 
+    async fn unmark(&self, tx: &mut SqliteConnection, key: &str) -> Result<bool> {
+        self.marker.unmark(&mut *tx, key).await
+    }
+
     fn get_real_url(&self, key: String) -> String {
         if key.starts_with("https://") || key.starts_with("http://") {
             key
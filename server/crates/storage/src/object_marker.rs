@@ -129,6 +129,14 @@ impl ObjectMarker {
         Ok(r.rows_affected() == 1)
     }
 
+    pub async fn unmark(&self, tx: &mut SqliteConnection, key: &str) -> Result<bool> {
+        let r = sqlx::query("DELETE FROM marked_objects WHERE key = ?")
+            .bind(key)
+            .execute(&mut *tx)
+            .await?;
+        Ok(r.rows_affected() == 1)
+    }
+
     fn from_row(&self, row: SqliteRow) -> MarkedObject {
         MarkedObject {
             key: row.try_get("key").unwrap(),
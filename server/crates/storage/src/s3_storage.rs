@@ -158,6 +158,10 @@ impl SimpleStorageService for S3 {
         r
     }
 
+    async fn unmark(&self, tx: &mut SqliteConnection, key: &str) -> Result<bool> {
+        self.marker.unmark(&mut *tx, key).await
+    }
+
     fn get_real_url(&self, key: String) -> String {
         let conf = self.core.get_config();
         if key.starts_with("http://") || key.starts_with("https://") {